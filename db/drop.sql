@@ -1 +1 @@
-DROP TABLE game, team, widget, state, action, session;
+DROP TABLE game, team_group, team, widget, state, action, session, asset;
@@ -2,9 +2,25 @@ CREATE TABLE public.game
 (
     id uuid NOT NULL,
     name text NOT NULL,
+    time_zone text,
     PRIMARY KEY (id)
 );
 
+CREATE TABLE public.team_group
+(
+    game uuid NOT NULL,
+    id uuid NOT NULL,
+    name text NOT NULL,
+    attributes jsonb NOT NULL,
+    overrides jsonb NOT NULL DEFAULT '{}',
+    PRIMARY KEY (game, id),
+    FOREIGN KEY (game)
+        REFERENCES public.game (id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE RESTRICT,
+    UNIQUE (game, name)
+);
+
 CREATE TABLE public.team
 (
     game uuid NOT NULL,
@@ -12,11 +28,17 @@ CREATE TABLE public.team
     name text NOT NULL,
     access_code text NOT NULL,
     attributes jsonb NOT NULL,
+    overrides jsonb NOT NULL DEFAULT '{}',
+    team_group uuid,
     PRIMARY KEY (game, id),
     FOREIGN KEY (game)
         REFERENCES public.game (id) MATCH SIMPLE
         ON UPDATE CASCADE
         ON DELETE RESTRICT,
+    FOREIGN KEY (game, team_group)
+        REFERENCES public.team_group (game, id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE RESTRICT,
     UNIQUE (access_code)
 );
 
@@ -93,10 +115,24 @@ CREATE TABLE public.action
         ON DELETE CASCADE
 );
 
+CREATE TABLE public.asset
+(
+    game uuid NOT NULL,
+    ident text NOT NULL,
+    content_type text NOT NULL,
+    data bytea NOT NULL,
+    PRIMARY KEY (game, ident),
+    FOREIGN KEY (game)
+        REFERENCES public.game (id) MATCH SIMPLE
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
 CREATE INDEX widget_by_game ON widget (game);
 CREATE INDEX state_by_team ON state (game, team);
 CREATE INDEX team_by_access_code ON team (access_code);
 CREATE INDEX team_by_game ON team (game);
+CREATE INDEX team_group_by_game ON team_group (game);
 CREATE INDEX action_by_team ON action (game, team);
 CREATE INDEX action_by_game_and_time ON action (game, time);
 CREATE INDEX session_by_token ON session (token) INCLUDE (game, team);
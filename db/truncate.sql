@@ -1 +1 @@
-TRUNCATE game, team, widget, state, action, session;
+TRUNCATE game, team_group, team, widget, state, action, session, asset;
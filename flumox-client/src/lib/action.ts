@@ -1,6 +1,7 @@
 import { toast, type ToastType } from "./toast";
+import type { Span } from "./view";
 import { submit as submitRequest } from "./api/game"
-import { errorDispatchFailed, errorNotPossible, hintTaken, solutionCorrect, solutionIncorrect } from "$translations";
+import { errorDispatchFailed, errorNotPossible, checkSpelling, checkpointReached, hintTaken, lowAccuracy, tooFar, keepGoing, partAlreadySolved, partCorrect, solutionCorrect, solutionIncorrect } from "$translations";
 
 export interface AnswerAction {
     type: "answer",
@@ -16,7 +17,19 @@ export interface Metadata {
     widget: string
 }
 
-export type Action = (AnswerAction | HintAction) & Metadata;
+export interface ChoiceAction {
+    type: "choice",
+    selected: string[]
+}
+
+export interface CheckInAction {
+    type: "check-in",
+    latitude: number,
+    longitude: number,
+    accuracy: number
+}
+
+export type Action = (AnswerAction | HintAction | ChoiceAction | CheckInAction) & Metadata;
 
 export type ActionResponse = {
     result: "success",
@@ -28,18 +41,34 @@ export type ActionResponse = {
 
 export type Message = {
     "type": "custom", value: string
+} | {
+    "type": "formatted", value: Span[]
 } | { type: PredefinedMessage };
-export type PredefinedMessage = "solution-correct" | "solution-incorrect" | "hint-taken";
+export type PredefinedMessage = "solution-correct" | "solution-incorrect" | "part-correct" | "part-already-solved" | "keep-going" | "check-spelling" | "hint-taken" | "checkpoint-reached" | "too-far" | "low-accuracy";
 
-export function messageToString(message: Message): string {
-    if (message.type == "custom")
+export function messageContent(message: Message): string | Span[] {
+    if (message.type == "custom" || message.type == "formatted")
         return message.value;
     if (message.type == "solution-correct")
         return solutionCorrect;
     if (message.type == "solution-incorrect")
         return solutionIncorrect;
+    if (message.type == "part-correct")
+        return partCorrect;
+    if (message.type == "part-already-solved")
+        return partAlreadySolved;
+    if (message.type == "keep-going")
+        return keepGoing;
+    if (message.type == "check-spelling")
+        return checkSpelling;
     if (message.type == "hint-taken")
         return hintTaken;
+    if (message.type == "checkpoint-reached")
+        return checkpointReached;
+    if (message.type == "too-far")
+        return tooFar;
+    if (message.type == "low-accuracy")
+        return lowAccuracy;
 
 }
 
@@ -48,7 +77,7 @@ export async function submit(payload: Action, token: string) {
 
     if (response.result == "success") {
         if (response.toast != null) {
-            toast(messageToString(response.toast.message), response.toast.type);
+            toast(messageContent(response.toast.message), response.toast.type);
         }
     } else if (response.result == "dispatch-failed") {
         toast(errorDispatchFailed, "danger");
@@ -1,6 +1,6 @@
 import type { Action, ActionResponse } from "../action";
 import type { Instances } from "../view";
-import { get, post } from "./request";
+import { get, getBlob, post } from "./request";
 
 export function view(token: string): Promise<Instances> {
     return get("/api/view", token);
@@ -9,3 +9,7 @@ export function view(token: string): Promise<Instances> {
 export function submit(token: string, action: Action): Promise<ActionResponse> {
     return post("/api/action", action, token);
 }
+
+export function asset(token: string, ident: string): Promise<Blob> {
+    return getBlob(`/api/asset/${encodeURIComponent(ident)}`, token);
+}
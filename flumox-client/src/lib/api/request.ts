@@ -57,6 +57,22 @@ export async function get(path: string, token?: string) {
     return await response.json();
 }
 
+export async function getBlob(path: string, token?: string): Promise<Blob> {
+    let headers = new Headers();
+
+    if (token != null)
+        headers.append(auth_header, token);
+
+    let response = await fetch(path, {
+        method: "GET",
+        headers,
+    });
+
+    await assertResponseOk(response);
+
+    return await response.blob();
+}
+
 export async function post(path: string, body: any, token?: string) {
     let headers = new Headers();
 
@@ -0,0 +1,30 @@
+import { asset } from "./api/game";
+
+// Keyed by the session too, so that a team never sees assets fetched by another
+const urls = new Map<string, Promise<string>>();
+
+export function assetUrl(token: string, ident: string): Promise<string> {
+    const key = `${token}/${ident}`;
+    let url = urls.get(key);
+
+    if (url == null) {
+        const fetched = asset(token, ident).then(blob => URL.createObjectURL(blob));
+        fetched.catch(() => {
+            if (urls.get(key) == fetched) {
+                urls.delete(key);
+            }
+        });
+        urls.set(key, fetched);
+        url = fetched;
+    }
+
+    return url;
+}
+
+export function clearAssets() {
+    for (const url of urls.values()) {
+        url.then(url => URL.revokeObjectURL(url), () => {});
+    }
+
+    urls.clear();
+}
@@ -1,4 +1,5 @@
 import { session, view } from "../stores";
+import { clearAssets } from "./asset";
 
 export type SessionToken = string;
 
@@ -19,4 +20,5 @@ export interface Session {
 export function logout() {
     session.set(null);
     view.set(null);
+    clearAssets();
 }
@@ -1,9 +1,10 @@
 import { toasts } from "../stores";
+import type { Span } from "./view";
 
 export type ToastType = "danger" | "warning" | "success" | "status";
 
 export interface Toast {
-    text: string,
+    text: string | Span[],
     type: ToastType,
     key: string
 }
@@ -16,7 +17,7 @@ export function dismiss(key: string) {
     toasts.update(toasts => toasts.filter(t => t.key != key))
 }
 
-export function toast(text: string, type: ToastType) {
+export function toast(text: string | Span[], type: ToastType) {
     let key = `\0${nextId++}`;
     let toast = { text, type, key };
 
@@ -1,13 +1,59 @@
+export type Span = {
+    type: "text" | "code",
+    text: string
+} | {
+    type: "bold" | "italic",
+    content: Span[]
+} | {
+    type: "link",
+    href: string,
+    content: Span[]
+};
+
+export type Block = {
+    type: "paragraph",
+    text: string
+} | {
+    type: "rich",
+    content: Span[]
+} | {
+    type: "list",
+    ordered: boolean,
+    items: Span[][]
+} | {
+    type: "image",
+    asset: string,
+    alt: string
+} | {
+    type: "audio",
+    asset: string
+} | {
+    type: "download",
+    asset: string,
+    text: string,
+    filename?: string
+};
+
 export interface PromptView {
     type: "prompt",
     name: string,
-    details: string[],
+    details: Block[],
     prompt: string,
     submit_button: string | null,
     disabled: boolean,
     solution?: string,
+    parts?: Part[],
     hints: Hint[],
-    time: TimeSpent
+    time: TimeSpent,
+    attempts?: string[],
+    attempts_left?: number,
+    cooldown?: string
+}
+
+export interface Part {
+    ident: string,
+    name: string,
+    solution?: string
 }
 
 export type Hint = {
@@ -23,7 +69,7 @@ export type Hint = {
     button: string | null
 } | {
     state: "taken",
-    content: string[]
+    content: Block[]
 });
 
 export type TimeSpent = { type: "solving", since: string } | { type: "solved", after: string } | null;
@@ -31,13 +77,13 @@ export type TimeSpent = { type: "solving", since: string } | { type: "solved", a
 export interface TextView {
     type: "text",
     heading: string | null,
-    content: string[],
+    content: Block[],
 }
 
 export interface CountdownView {
     type: "countdown",
     name: string | null,
-    details: string[],
+    details: Block[],
     value: {
         type: "unknown"
     } | {
@@ -49,15 +95,37 @@ export interface CountdownView {
     },
 }
 
+export interface ChoiceView {
+    type: "choice",
+    name: string,
+    details: Block[],
+    question: string,
+    submit_button: string | null,
+    options: { ident: string, text: string }[],
+    multiple: boolean,
+    one_shot: boolean,
+    disabled: boolean,
+    result?: { type: "solved" | "failed", selected: string[] }
+}
+
+export interface CheckpointView {
+    type: "checkpoint",
+    name: string,
+    details: Block[],
+    check_in_button: string | null,
+    disabled: boolean,
+    reached?: string
+}
+
 export interface Instance {
     id: string,
-    view: PromptView | TextView | CountdownView,
+    view: PromptView | TextView | CountdownView | ChoiceView | CheckpointView,
     obsolete: boolean
 }
 
 export interface InstanceDelta {
     id: string,
-    view?: PromptView | TextView | CountdownView,
+    view?: PromptView | TextView | CountdownView | ChoiceView | CheckpointView,
     obsolete: boolean
 }
 
@@ -7,6 +7,7 @@ declare module "$translations" {
 
     const toastClose: string;
     const unknownView: string;
+    const assetFailed: string;
 
     const loginHeading: string;
     const loginAccessCode: string;
@@ -17,10 +18,27 @@ declare module "$translations" {
     const timeSpent: string;
     const submitButton: string;
     const takeHintButton: string;
+    const oneShotWarning: string;
+    const choiceSolved: string;
+    const choiceFailed: string;
+    const attemptsLeft: string;
+    const previousAttempts: string;
+    const cooldownRemaining: string;
+    const checkInButton: string;
+    const checkpointReachedAt: string;
+    const locating: string;
+    const locationFailed: string;
 
     const solutionCorrect: string;
     const solutionIncorrect: string;
+    const partCorrect: string;
+    const partAlreadySolved: string;
+    const keepGoing: string;
+    const checkSpelling: string;
     const hintTaken: string;
+    const checkpointReached: string;
+    const tooFar: string;
+    const lowAccuracy: string;
 
     const settingsOpen: string;
     const settingsClose: string;
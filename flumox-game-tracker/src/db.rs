@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::anyhow;
 use deadpool_postgres::Transaction;
-use flumox::{widget::prompt, Action, Config, Instance, State};
+use flumox::{widget::prompt, Action, Attributes, Config, GameState, Instance, Overrides, State};
+use serde::Deserialize;
 use time::OffsetDateTime;
+use time_expr::Zone;
 use tokio_postgres::types::Json;
 use uuid::Uuid;
 
@@ -44,14 +46,38 @@ pub async fn game_name(
     game.map(|r| Ok(r.try_get(0)?)).transpose()
 }
 
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub id: Uuid,
+    pub name: String,
+}
+
+pub async fn groups(db: &mut Transaction<'_>, game: Uuid) -> Result<Vec<Group>, InternalError> {
+    const GROUPS: &str = "SELECT id, name FROM team_group WHERE game = $1 ORDER BY name";
+
+    let stmt = db.prepare_cached(GROUPS).await?;
+    let groups = db.query(&stmt, &[&game]).await?;
+
+    groups
+        .into_iter()
+        .map(|r| {
+            Ok(Group {
+                id: r.try_get(0)?,
+                name: r.try_get(1)?,
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct Team {
     pub id: Uuid,
     pub name: String,
+    pub group: Option<Uuid>,
 }
 
 pub async fn teams(db: &mut Transaction<'_>, game: Uuid) -> Result<Vec<Team>, InternalError> {
-    const TEAMS: &str = "SELECT id, name FROM team WHERE game = $1 ORDER BY name";
+    const TEAMS: &str = "SELECT id, name, team_group FROM team WHERE game = $1 ORDER BY name";
 
     let stmt = db.prepare_cached(TEAMS).await?;
     let teams = db.query(&stmt, &[&game]).await?;
@@ -62,6 +88,7 @@ pub async fn teams(db: &mut Transaction<'_>, game: Uuid) -> Result<Vec<Team>, In
             Ok(Team {
                 id: r.try_get(0)?,
                 name: r.try_get(1)?,
+                group: r.try_get(2)?,
             })
         })
         .collect()
@@ -86,39 +113,233 @@ pub struct WidgetInstance {
     pub instance: Instance,
 }
 
-pub async fn states(
+#[derive(Debug, Clone)]
+struct Widget {
+    id: Uuid,
+    ident: String,
+    /// The config as stored, which overrides are applied to
+    source: serde_json::Value,
+    config: Config,
+}
+
+/// The widgets of a game, which are parsed once and shared by all of its teams.
+#[derive(Debug, Clone)]
+pub struct GameWidgets {
+    widgets: Vec<Widget>,
+    zone: Option<Zone>,
+}
+
+/// The attributes and overrides of a team, along with those of its group.
+#[derive(Debug, Clone)]
+pub struct TeamSetup {
+    pub id: Uuid,
+    team: Attributes,
+    group: Attributes,
+    /// Overrides of the group, followed by those of the team, which take precedence
+    overrides: [Overrides; 2],
+}
+
+pub async fn game_widgets(
     db: &mut Transaction<'_>,
     game: Uuid,
-    team: Uuid,
-) -> Result<Vec<WidgetInstance>, InternalError> {
-    const STATES: &str = concat!(
-        "SELECT widget.ident, state.state, widget.config ",
-        "FROM widget LEFT JOIN state ",
-        "ON state.game=widget.game AND state.widget=widget.id AND state.team=$2 ",
-        "WHERE widget.game=$1 ",
-        "ORDER BY widget.priority DESC"
+) -> Result<GameWidgets, InternalError> {
+    const GAME: &str = "SELECT time_zone FROM game WHERE id=$1";
+    const WIDGETS: &str = concat!(
+        "SELECT id, ident, config FROM widget ",
+        "WHERE game=$1 ",
+        "ORDER BY priority DESC"
     );
 
-    let stmt = db.prepare_cached(STATES).await?;
-    let states = db.query(&stmt, &[&game, &team]).await?;
+    let stmt = db.prepare_cached(GAME).await?;
+    let row = db.query_one(&stmt, &[&game]).await?;
+
+    let zone = match row.try_get::<_, Option<String>>(0)? {
+        Some(name) => {
+            Some(Zone::named(&name).ok_or_else(|| anyhow!("Unknown time zone \"{name}\""))?)
+        }
+        None => None,
+    };
 
-    states
+    let stmt = db.prepare_cached(WIDGETS).await?;
+    let rows = db.query(&stmt, &[&game]).await?;
+
+    let widgets = rows
         .into_iter()
         .map(|r| {
-            let ident = r.try_get(0)?;
-            let state: Option<Json<State>> = r.try_get(1)?;
-            let Json(config): Json<Config> = r.try_get(2)?;
+            let id = r.try_get(0)?;
+            let ident = r.try_get(1)?;
+            let Json(source): Json<serde_json::Value> = r.try_get(2)?;
+            let config = Config::deserialize(&source)?;
+
+            Ok(Widget {
+                id,
+                ident,
+                source,
+                config,
+            })
+        })
+        .collect::<Result<_, InternalError>>()?;
 
-            let instance = match state {
-                Some(Json(state)) => config.instance(state)?,
-                None => config.instance_default(),
-            };
+    Ok(GameWidgets { widgets, zone })
+}
+
+/// Loads the setups of all teams of a game, or only of `team` if given.
+pub async fn team_setups(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    team: Option<Uuid>,
+) -> Result<Vec<TeamSetup>, InternalError> {
+    const TEAMS: &str = concat!(
+        "SELECT team.id, team.name, team.attributes, team.overrides, ",
+        "team_group.name, team_group.attributes, team_group.overrides ",
+        "FROM team LEFT JOIN team_group ",
+        "ON team_group.game=team.game AND team_group.id=team.team_group ",
+        "WHERE team.game=$1 AND ($2::uuid IS NULL OR team.id=$2)"
+    );
+
+    let stmt = db.prepare_cached(TEAMS).await?;
+    let rows = db.query(&stmt, &[&game, &team]).await?;
 
-            Ok(WidgetInstance { ident, instance })
+    rows.into_iter()
+        .map(|r| {
+            let Json(mut team): Json<Attributes> = r.try_get(2)?;
+            team.name = r.try_get(1)?;
+            let Json(team_overrides): Json<Overrides> = r.try_get(3)?;
+
+            let group: Option<Json<Attributes>> = r.try_get(5)?;
+            let mut group = group.map(|Json(a)| a).unwrap_or_default();
+            group.name = r.try_get::<_, Option<String>>(4)?.unwrap_or_default();
+
+            let group_overrides: Option<Json<Overrides>> = r.try_get(6)?;
+            let group_overrides = group_overrides.map(|Json(o)| o).unwrap_or_default();
+
+            Ok(TeamSetup {
+                id: r.try_get(0)?,
+                team,
+                group,
+                overrides: [group_overrides, team_overrides],
+            })
+        })
+        .collect()
+}
+
+/// Loads the states of all teams of a game, or only of `team` if given.
+pub async fn widget_states(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    team: Option<Uuid>,
+) -> Result<HashMap<StateKey, State>, InternalError> {
+    const STATES: &str = concat!(
+        "SELECT team, widget, state FROM state ",
+        "WHERE game=$1 AND ($2::uuid IS NULL OR team=$2)"
+    );
+
+    let stmt = db.prepare_cached(STATES).await?;
+    let rows = db.query(&stmt, &[&game, &team]).await?;
+
+    rows.into_iter()
+        .map(|r| {
+            let team = r.try_get(0)?;
+            let widget = r.try_get(1)?;
+            let Json(state) = r.try_get(2)?;
+
+            Ok((StateKey { team, widget }, state))
         })
         .collect()
 }
 
+impl GameWidgets {
+    /// Builds the widgets of a team, taking their states out of `states`.
+    pub fn instances(
+        &self,
+        setup: &TeamSetup,
+        states: &mut HashMap<StateKey, State>,
+    ) -> Result<Vec<WidgetInstance>, InternalError> {
+        let [group, team] = &setup.overrides;
+        let layers = [group, team];
+
+        self.widgets
+            .iter()
+            .map(|widget| {
+                // Only overridden widgets are parsed again
+                let config = match Overrides::config(&layers, &widget.ident, &widget.source) {
+                    Some(config) => config?,
+                    None => widget.config.clone(),
+                };
+
+                let key = StateKey {
+                    team: setup.id,
+                    widget: widget.id,
+                };
+
+                let instance = match states.remove(&key) {
+                    Some(state) => config.instance(state)?,
+                    None => config.instance_default(),
+                };
+
+                Ok(WidgetInstance {
+                    ident: widget.ident.clone(),
+                    instance,
+                })
+            })
+            .collect()
+    }
+
+    pub fn game_state(
+        &self,
+        setup: &TeamSetup,
+        states: &mut HashMap<StateKey, State>,
+    ) -> Result<GameState, InternalError> {
+        let instances = self
+            .instances(setup, states)?
+            .into_iter()
+            .map(|w| (w.ident, w.instance))
+            .collect();
+
+        Ok(GameState {
+            instances,
+            team: setup.team.clone(),
+            group: setup.group.clone(),
+            zone: self.zone,
+        })
+    }
+}
+
+async fn team_setup(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    team: Uuid,
+) -> Result<TeamSetup, InternalError> {
+    team_setups(db, game, Some(team))
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("Unknown team {team}").into())
+}
+
+pub async fn states(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    team: Uuid,
+) -> Result<Vec<WidgetInstance>, InternalError> {
+    let widgets = game_widgets(db, game).await?;
+    let setup = team_setup(db, game, team).await?;
+    let mut states = widget_states(db, game, Some(team)).await?;
+
+    widgets.instances(&setup, &mut states)
+}
+
+pub async fn game_state(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    team: Uuid,
+) -> Result<GameState, InternalError> {
+    let widgets = game_widgets(db, game).await?;
+    let setup = team_setup(db, game, team).await?;
+    let mut states = widget_states(db, game, Some(team)).await?;
+
+    widgets.game_state(&setup, &mut states)
+}
+
 #[derive(Debug, Clone)]
 pub struct ActionInfo {
     pub widget: String,
@@ -166,9 +387,11 @@ pub struct RecentActionInfo {
     pub payload: Action,
 }
 
+/// Lists the latest actions of all teams, or only those in `group` if given.
 pub async fn recent_actions(
     db: &mut Transaction<'_>,
     game: Uuid,
+    group: Option<Uuid>,
 ) -> Result<Vec<RecentActionInfo>, InternalError> {
     const ACTIONS: &str = concat!(
         "SELECT widget.ident, team.name, action.time, action.payload ",
@@ -178,12 +401,13 @@ pub async fn recent_actions(
         "JOIN team ",
         "ON action.game=team.game AND action.team=team.id ",
         "WHERE action.game=$1 ",
+        "AND ($2::uuid IS NULL OR team.team_group=$2) ",
         "ORDER BY action.time DESC ",
         "LIMIT 30"
     );
 
     let stmt = db.prepare_cached(ACTIONS).await?;
-    let actions = db.query(&stmt, &[&game]).await?;
+    let actions = db.query(&stmt, &[&game, &group]).await?;
 
     actions
         .into_iter()
@@ -255,7 +479,7 @@ pub async fn prompt_states(
 pub struct PromptConfig {
     pub id: Uuid,
     pub ident: String,
-    pub config: prompt::Config,
+    pub config: Arc<prompt::Config>,
 }
 
 pub async fn prompts(
@@ -281,7 +505,7 @@ pub async fn prompts(
             let Json(config): Json<Config> = r.try_get(2)?;
 
             let config = match config {
-                Config::Prompt(config) => *config,
+                Config::Prompt(config) => config,
                 _ => return Err(InternalError::from(anyhow!("Expected a prompt widget"))),
             };
 
@@ -1,3 +1,5 @@
+use std::fmt::{self, Display, Formatter};
+
 use anyhow::Error;
 use axum::{
     http::StatusCode,
@@ -16,6 +18,12 @@ impl<T: Into<Error>> From<T> for InternalError {
     }
 }
 
+impl Display for InternalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 impl IntoResponse for InternalError {
     fn into_response(self) -> Response {
         let error = self.0;
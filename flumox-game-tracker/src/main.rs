@@ -43,6 +43,7 @@ async fn serve(state: State, address: SocketAddr, creds: Option<Credentials>) ->
         .route("/", get(routes::root))
         .route("/:game/", get(routes::game))
         .route("/:game/:team/", get(routes::team))
+        .route("/:game/:team/explain", get(routes::explain))
         .fallback(|| async { not_found("Page") });
 
     let app = if let Some(creds) = creds {
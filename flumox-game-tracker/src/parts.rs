@@ -2,6 +2,8 @@ use axum::http::StatusCode;
 use flumox::Action;
 use maud::{html, Markup, PreEscaped, DOCTYPE};
 use time::OffsetDateTime;
+use time_expr::{EvalError, Node, Trace, Value};
+use uuid::Uuid;
 
 pub fn page(title: &str, body: Markup) -> Markup {
     html! {
@@ -36,11 +38,95 @@ pub fn action_description(payload: &Action) -> Markup {
     match payload {
         Action::Answer(answer) => html!("Submitted answer " i { (answer.answer) }),
         Action::Hint(hint) => html!("Taken hint " b { (hint.ident) }),
+        Action::Choice(choice) => html!("Selected " i { (choice.selected.join(", ")) }),
+        Action::CheckIn(check_in) => html!(
+            "Checked in at " (coordinates(check_in.latitude, check_in.longitude))
+            " (± " (format!("{:.0}", check_in.accuracy)) " m)"
+        ),
         #[allow(unreachable_patterns)]
         _ => html!("Unknown action"),
     }
 }
 
+/// Shows a position, linking to it on a map.
+pub fn coordinates(latitude: f64, longitude: f64) -> Markup {
+    html!(
+        a href={"https://www.openstreetmap.org/?mlat=" (latitude) "&mlon=" (longitude)} {
+            (format!("{latitude:.6}, {longitude:.6}"))
+        }
+    )
+}
+
+pub fn value_description(value: &Value) -> Markup {
+    fn bool_description(value: bool) -> &'static str {
+        if value {
+            "true"
+        } else {
+            "false"
+        }
+    }
+
+    if value.changes().is_empty() {
+        return html!(@if value.initial() { "always" } @else { "never" });
+    }
+
+    let changes = value
+        .changes()
+        .iter()
+        .scan(value.initial(), |state, &time| {
+            *state = !*state;
+            Some((time, *state))
+        });
+
+    html!(
+        (bool_description(value.initial()))
+        @for (time, state) in changes {
+            ", " (bool_description(state)) " from " (datetime(time))
+        }
+    )
+}
+
+fn node_label(node: &Node) -> &str {
+    match node {
+        Node::Path(label) | Node::Expr(label) => label,
+    }
+}
+
+fn result_description(result: &Result<Value, EvalError>) -> Markup {
+    match result {
+        Ok(value) => value_description(value),
+        Err(error) => html!(pre { (error.snippet(None)) }),
+    }
+}
+
+pub fn trace(trace: &Trace) -> Markup {
+    html!(
+        li {
+            code { (node_label(&trace.node)) }
+            ": "
+            (result_description(&trace.result))
+
+            @if !trace.children.is_empty() {
+                ul {
+                    @for child in &trace.children {
+                        (self::trace(child))
+                    }
+                }
+            }
+        }
+    )
+}
+
+pub fn explain_form(game: Uuid, team: Uuid, path: &str) -> Markup {
+    html!(
+        form action={"/" (game) "/" (team) "/explain"} method="get" {
+            input type="text" name="path" value=(path) placeholder="first.hint.spoiler.available";
+            " "
+            button type="submit" { "Explain" }
+        }
+    )
+}
+
 pub fn datetime(time: OffsetDateTime) -> Markup {
     let timestamp = time.unix_timestamp_nanos() / 1_000_000;
 
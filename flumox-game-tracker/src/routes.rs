@@ -1,17 +1,24 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use anyhow::anyhow;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
 use deadpool_postgres::Pool;
 use flumox::Instance;
 use maud::{html, Markup};
 use serde::Deserialize;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
-    db::{self, ActionInfo, RecentActionInfo, StateKey},
+    db::{self, ActionInfo, Group, RecentActionInfo, StateKey, Team, TeamSetup},
     error::InternalError,
-    parts::{action_description, datetime, not_found, page, short_time, table_style, time_script},
+    parts::{
+        action_description, coordinates, datetime, explain_form, not_found, page, short_time,
+        table_style, time_script, trace,
+    },
 };
 
 pub async fn root(State(pool): State<Pool>) -> Result<Markup, InternalError> {
@@ -39,9 +46,19 @@ pub struct GamePath {
     game: Uuid,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameQuery {
+    /// Only show teams in this group
+    #[serde(default)]
+    group: Option<Uuid>,
+}
+
+type Ranking<'a> = Vec<(&'a Team, Result<(i64, Option<OffsetDateTime>), String>)>;
+
 pub async fn game(
     State(pool): State<Pool>,
     Path(path): Path<GamePath>,
+    Query(query): Query<GameQuery>,
 ) -> Result<(StatusCode, Markup), InternalError> {
     let mut client = pool.get().await?;
     let mut client = client.transaction().await?;
@@ -52,10 +69,116 @@ pub async fn game(
         return Ok(not_found("Game"));
     };
 
-    let teams = db::teams(&mut client, path.game).await?;
+    let groups = db::groups(&mut client, path.game).await?;
+
+    if query
+        .group
+        .is_some_and(|group| !groups.iter().any(|g| g.id == group))
+    {
+        return Ok(not_found("Group"));
+    }
+
+    let teams: Vec<Team> = db::teams(&mut client, path.game)
+        .await?
+        .into_iter()
+        .filter(|team| query.group.is_none() || team.group == query.group)
+        .collect();
+
     let prompts = db::prompts(&mut client, path.game).await?;
     let prompt_states = db::prompt_states(&mut client, path.game).await?;
-    let actions = db::recent_actions(&mut client, path.game).await?;
+    let actions = db::recent_actions(&mut client, path.game, query.group).await?;
+
+    let widgets = db::game_widgets(&mut client, path.game).await?;
+    let mut setups: HashMap<Uuid, TeamSetup> = db::team_setups(&mut client, path.game, None)
+        .await?
+        .into_iter()
+        .map(|setup| (setup.id, setup))
+        .collect();
+    let mut states = db::widget_states(&mut client, path.game, None).await?;
+
+    let time = OffsetDateTime::now_utc();
+    let mut scores = Vec::new();
+
+    for team in &teams {
+        let setup = setups
+            .remove(&team.id)
+            .ok_or_else(|| anyhow!("Missing setup of team {}", team.id))?;
+
+        // A broken team is shown in the ranking rather than hiding all the others
+        let score = widgets
+            .game_state(&setup, &mut states)
+            .map_err(|e| e.to_string())
+            .and_then(|state| state.score(time).map_err(|e| e.to_string()))
+            .map(|score| (score.at(time), score.last_change(time)));
+
+        scores.push((team, score));
+    }
+
+    // Each group is ranked separately, followed by the teams without one
+    let mut rankings: Vec<(Option<&Group>, Ranking)> = groups
+        .iter()
+        .map(|group| (Some(group), Vec::new()))
+        .chain([(None, Vec::new())])
+        .collect();
+
+    for entry in scores {
+        let group = entry.0.group;
+
+        if let Some((_, ranking)) = rankings.iter_mut().find(|(g, _)| g.map(|g| g.id) == group) {
+            ranking.push(entry);
+        }
+    }
+
+    rankings.retain(|(_, ranking)| !ranking.is_empty());
+
+    for (_, ranking) in &mut rankings {
+        // Teams which reached their score sooner rank higher, teams with errors last
+        ranking.sort_by_key(|(_, score)| match score {
+            Ok((score, since)) => (false, Reverse(*score), *since),
+            Err(_) => (true, Reverse(0), None),
+        });
+    }
+
+    fn ranking_table(game: Uuid, ranking: &Ranking) -> Markup {
+        html!(
+            table {
+                thead {
+                    tr {
+                        th { "#" }
+                        th { "Team" }
+                        th { "Score" }
+                        th { "Since" }
+                    }
+                }
+
+                tbody {
+                    @for (rank, (team, score)) in ranking.iter().enumerate() {
+                        tr {
+                            td {
+                                @if score.is_ok() {
+                                    (rank + 1)
+                                }
+                            }
+                            td {
+                                a href={"/" (game) "/" (team.id) "/"} { (team.name) }
+                            }
+                            @match score {
+                                Ok((score, since)) => {
+                                    td { (score) }
+                                    td {
+                                        @if let Some(since) = since {
+                                            (short_time(*since))
+                                        }
+                                    }
+                                }
+                                Err(error) => td colspan="2" { i { "Error: " (error) } },
+                            }
+                        }
+                    }
+                }
+            }
+        )
+    }
 
     fn action(action: &RecentActionInfo) -> Markup {
         html!(
@@ -78,6 +201,39 @@ pub async fn game(
             html!(
                 h1 { (&game) }
 
+                @if !groups.is_empty() {
+                    p {
+                        "Groups: "
+                        @if query.group.is_none() {
+                            b { "All" }
+                        } @else {
+                            a href={"/" (path.game) "/"} { "All" }
+                        }
+                        @for group in &groups {
+                            " | "
+                            @if query.group == Some(group.id) {
+                                b { (group.name) }
+                            } @else {
+                                a href={"/" (path.game) "/?group=" (group.id)} { (group.name) }
+                            }
+                        }
+                    }
+                }
+
+                h2 { "Ranking" }
+                @for (group, ranking) in &rankings {
+                    @if !groups.is_empty() {
+                        h3 {
+                            @match group {
+                                Some(group) => (group.name),
+                                None => "No group",
+                            }
+                        }
+                    }
+
+                    (ranking_table(path.game, ranking))
+                }
+
                 h2 { "Teams" }
                 table {
                     thead {
@@ -166,6 +322,50 @@ pub async fn team(
                         None => i { "Not solved" },
                     }
                 }
+                @if !state.parts.is_empty() {
+                    p { "Parts solved:" }
+                    ul {
+                        @for part in state.parts.iter() {
+                            li {
+                                b { (part.0) }
+                                ": "
+                                i { (part.1.canonical_text) }
+                                " at "
+                                i { (datetime(part.1.time)) }
+                            }
+                        }
+                    }
+                }
+                @if !state.milestones.is_empty() {
+                    p { "Milestones reached:" }
+                    ul {
+                        @for milestone in state.milestones.iter() {
+                            li {
+                                b { (milestone.0) }
+                                " at "
+                                i { (datetime(*milestone.1)) }
+                            }
+                        }
+                    }
+                }
+                p { "Wrong answers:" }
+                @if !state.attempts.is_empty() {
+                    ul {
+                        @for attempt in &state.attempts {
+                            li {
+                                @if let Some(answer) = &attempt.answer {
+                                    i { (answer) }
+                                } @else {
+                                    "Answer not kept"
+                                }
+                                " at "
+                                i { (datetime(attempt.time)) }
+                            }
+                        }
+                    }
+                } @else {
+                    p { i { "None" } }
+                }
                 p { "Hints taken:" }
                 @if !state.hints.is_empty() {
                     ul {
@@ -181,6 +381,31 @@ pub async fn team(
                     p { i { "None" } }
                 }
             )),
+            Instance::Choice(config, state) => Some(html!(
+                h3 { (config.style.name) " (" (ident) ")" }
+                p {
+                    @match (&state.solved, &state.failed) {
+                        (Some(details), _) => "Solved at " i { (datetime(details.time)) },
+                        (None, Some(details)) => {
+                            "Failed with " i { (details.selected.join(", ")) }
+                            " at " i { (datetime(details.time)) }
+                        }
+                        (None, None) => i { "Not answered" },
+                    }
+                }
+            )),
+            Instance::Checkpoint(config, state) => Some(html!(
+                h3 { (config.style.name) " (" (ident) ")" }
+                p {
+                    @match &state.reached {
+                        Some(details) => {
+                            "Reached at " i { (datetime(details.time)) }
+                            " from " (coordinates(details.latitude, details.longitude))
+                        }
+                        None => i { "Not reached" },
+                    }
+                }
+            )),
             _ => None,
         }
     }
@@ -204,6 +429,9 @@ pub async fn team(
             html!(
                 h1 { (&team) }
 
+                h2 { "Explain" }
+                (explain_form(path.game, path.team, ""))
+
                 h2 { "State" }
                 @for w in &widgets {
                     @if let Some(state) = widget(&w.instance, &w.ident) {
@@ -224,3 +452,51 @@ pub async fn team(
         ),
     ))
 }
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExplainQuery {
+    #[serde(default)]
+    path: String,
+}
+
+pub async fn explain(
+    State(pool): State<Pool>,
+    Path(path): Path<TeamPath>,
+    Query(query): Query<ExplainQuery>,
+) -> Result<(StatusCode, Markup), InternalError> {
+    let mut client = pool.get().await?;
+    let mut client = client.transaction().await?;
+
+    let team = db::team_name(&mut client, path.game, path.team).await?;
+
+    let Some(team) = team else {
+        return Ok(not_found("Team"));
+    };
+
+    let state = db::game_state(&mut client, path.game, path.team).await?;
+    let time = OffsetDateTime::now_utc();
+
+    let explanation = (!query.path.is_empty()).then(|| state.explain(&query.path, time));
+
+    Ok((
+        StatusCode::OK,
+        page(
+            &team,
+            html!(
+                h1 {
+                    a href={"/" (path.game) "/" (path.team) "/"} { (&team) }
+                }
+
+                h2 { "Explain" }
+                (explain_form(path.game, path.team, &query.path))
+
+                @if let Some(explanation) = &explanation {
+                    p { "Evaluated at " (datetime(time)) }
+                    ul { (trace(explanation)) }
+                }
+
+                (time_script())
+            ),
+        ),
+    ))
+}
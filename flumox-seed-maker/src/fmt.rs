@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use flumox::Config;
+use serde_json::Value;
+
+use crate::{preprocess, replace_templates_in_string, template_ident, Game};
+
+/// The keys which widget configs keep expressions under, at any depth.
+const EXPR_KEYS: [&str; 7] = [
+    "visible",
+    "disabled",
+    "obsolete",
+    "available",
+    "time",
+    "show",
+    "hide",
+];
+
+/// A step into a JSON5 document, either a key of an object or an index into an array.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Rewrites every expression in a game file in canonical form.
+///
+/// Only the string literals containing expressions are touched,
+/// so comments and the layout of the file are preserved.
+pub fn format_game(source: &str) -> Result<String> {
+    let raw: Game = json5::from_str(source)?;
+
+    let mut game = raw.clone();
+    preprocess(&mut game)?;
+
+    let mut sources = HashSet::new();
+
+    for widget in &game.widgets {
+        let config: Config = match serde_json::from_value(widget.config.clone()) {
+            Ok(config) => config,
+            Err(error) => bail!("widget {}: {error}", widget.ident),
+        };
+
+        let instance = config.instance_default();
+
+        for field in instance.fields() {
+            if let Some(expr) = field.expr {
+                sources.insert(expr.source().to_owned());
+            }
+        }
+    }
+
+    let idents: Vec<String> = game.widgets.iter().map(|w| w.ident.clone()).collect();
+    let mut replacements = HashMap::new();
+
+    let is_expr = |string: &str, idx: usize| {
+        replace_templates_in_string(string, |s| template_ident(s, &idents, idx))
+            .is_ok_and(|string| sources.contains(&string))
+    };
+
+    for (idx, widget) in raw.widgets.iter().enumerate() {
+        let path = [key("widgets"), Segment::Index(idx), key("config")];
+
+        for_each_expr(&widget.config, &mut path.to_vec(), &mut |path, string| {
+            if is_expr(string, idx) {
+                add_replacement(&mut replacements, path, string);
+            }
+        });
+    }
+
+    for (name, mixin) in &raw.mixins {
+        let path = [key("mixins"), key(name)];
+
+        for_each_expr(mixin, &mut path.to_vec(), &mut |path, string| {
+            if (0..idents.len()).any(|idx| is_expr(string, idx)) {
+                add_replacement(&mut replacements, path, string);
+            }
+        });
+    }
+
+    let teams = raw.teams.iter().map(|t| (&t.attributes, &t.overrides));
+    let groups = raw.groups.iter().map(|g| (&g.attributes, &g.overrides));
+
+    for (list, layers) in [
+        ("teams", teams.collect::<Vec<_>>()),
+        ("groups", groups.collect()),
+    ] {
+        for (idx, (attributes, overrides)) in layers.into_iter().enumerate() {
+            let path = [key(list), Segment::Index(idx)];
+
+            // Overrides are merged into widget configs before they're parsed,
+            // so every expression in them which parses is formatted
+            let mut overrides_path = path.to_vec();
+            overrides_path.push(key("overrides"));
+
+            for_each_expr(overrides, &mut overrides_path, &mut |path, string| {
+                add_replacement(&mut replacements, path, string);
+            });
+
+            // Only attributes given as plain strings are conditions, text and numbers are objects
+            let Value::Object(map) = attributes else {
+                continue;
+            };
+
+            for (name, value) in map {
+                if let Value::String(string) = value {
+                    let mut path = path.to_vec();
+                    path.extend([key("attributes"), key(name)]);
+
+                    add_replacement(&mut replacements, &path, string);
+                }
+            }
+        }
+    }
+
+    rewrite_strings(source, |path, _| replacements.get(path).cloned())
+}
+
+fn key(key: &str) -> Segment {
+    Segment::Key(key.to_owned())
+}
+
+/// Calls `f` with every string kept under one of the [`EXPR_KEYS`], along with its path.
+fn for_each_expr(value: &Value, path: &mut Vec<Segment>, f: &mut impl FnMut(&[Segment], &str)) {
+    match value {
+        Value::String(string) => {
+            if let Some(Segment::Key(key)) = path.last() {
+                if EXPR_KEYS.contains(&key.as_str()) {
+                    f(path, string);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                path.push(Segment::Index(i));
+                for_each_expr(value, path, f);
+                path.pop();
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map {
+                path.push(Segment::Key(key.clone()));
+                for_each_expr(value, path, f);
+                path.pop();
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+fn add_replacement(
+    replacements: &mut HashMap<Vec<Segment>, String>,
+    path: &[Segment],
+    string: &str,
+) {
+    if let Some(formatted) = format_expr(string) {
+        if formatted != string {
+            replacements.insert(path.to_vec(), formatted);
+        }
+    }
+}
+
+/// Formats an expression, keeping templates like `@[-1]` intact.
+fn format_expr(string: &str) -> Option<String> {
+    let mut templates = Vec::new();
+
+    let string = replace_templates_in_string(string, |name| {
+        templates.push(name);
+        Ok(format!("__template_{}__", templates.len() - 1))
+    })
+    .ok()?;
+
+    let mut formatted = time_expr::parse(&string).ok()?.to_string();
+
+    for (i, name) in templates.iter().enumerate() {
+        formatted = formatted.replace(&format!("__template_{i}__"), &format!("@[{name}]"));
+    }
+
+    Some(formatted)
+}
+
+/// Where the scanner of a JSON5 document is within an object or an array.
+enum Frame {
+    /// Within an object, after the key of the current value if it has been read
+    Object(Option<String>),
+    /// Within an array, at the index of the current value
+    Array(usize),
+}
+
+/// Replaces the values of string literals in a JSON5 document, leaving keys alone.
+///
+/// `replace` is given the path to each value along with the value itself.
+fn rewrite_strings(
+    source: &str,
+    mut replace: impl FnMut(&[Segment], &str) -> Option<String>,
+) -> Result<String> {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.char_indices().peekable();
+    let mut copied = 0;
+    let mut frames = Vec::new();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '/' if chars.next_if(|&(_, c)| c == '/').is_some() => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '/' if chars.next_if(|&(_, c)| c == '*').is_some() => {
+                let mut last = ' ';
+
+                for (_, c) in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+
+                    last = c;
+                }
+            }
+            '{' => frames.push(Frame::Object(None)),
+            '[' => frames.push(Frame::Array(0)),
+            '}' | ']' => {
+                frames.pop();
+            }
+            ',' => match frames.last_mut() {
+                Some(Frame::Object(key)) => *key = None,
+                Some(Frame::Array(index)) => *index += 1,
+                None => {}
+            },
+            quote @ ('"' | '\'') => {
+                let mut escaped = false;
+                let mut end = source.len();
+
+                for (i, c) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == quote {
+                        end = i + c.len_utf8();
+                        break;
+                    }
+                }
+
+                let literal = &source[start..end];
+                let value: String = json5::from_str(literal)?;
+
+                if let Some(Frame::Object(key @ None)) = frames.last_mut() {
+                    *key = Some(value);
+                    continue;
+                }
+
+                let path: Vec<Segment> = frames
+                    .iter()
+                    .filter_map(|frame| match frame {
+                        Frame::Object(key) => key.clone().map(Segment::Key),
+                        Frame::Array(index) => Some(Segment::Index(*index)),
+                    })
+                    .collect();
+
+                if let Some(replacement) = replace(&path, &value) {
+                    result.push_str(&source[copied..start]);
+                    result.push_str(&quote_string(&replacement, quote));
+                    copied = end;
+                }
+            }
+            // Keys don't have to be quoted in JSON5
+            c if is_identifier(c) => {
+                let mut end = start + c.len_utf8();
+
+                while let Some((i, c)) = chars.next_if(|&(_, c)| is_identifier(c)) {
+                    end = i + c.len_utf8();
+                }
+
+                if let Some(Frame::Object(key @ None)) = frames.last_mut() {
+                    *key = Some(source[start..end].to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    result.push_str(&source[copied..]);
+    Ok(result)
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn quote_string(string: &str, quote: char) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+    result.push(quote);
+
+    for c in string.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c == quote => {
+                result.push('\\');
+                result.push(c);
+            }
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result.push(quote);
+    result
+}
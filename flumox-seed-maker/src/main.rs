@@ -1,18 +1,21 @@
 use std::{
     collections::{HashMap, HashSet},
-    fmt::{Display, Formatter},
+    fmt::{Display, Formatter, Write as _},
     fs::{self, File},
     io::{stdin, stdout, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, bail, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use flumox::{GameDefinition, GroupDefinition, TeamDefinition};
 use postgres_protocol::escape::escape_literal;
 use serde::{Deserialize, Serialize};
 use serde_json::{map::Entry, Map, Value};
 use uuid::Uuid;
 
+mod fmt;
+
 #[derive(Debug, Clone, Copy)]
 struct Escape<T>(T);
 
@@ -25,18 +28,58 @@ where
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+struct OptionalEscape<T>(Option<T>);
+
+impl<T> Display for OptionalEscape<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(value) => Escape(value).fmt(f),
+            None => write!(f, "NULL"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct Widget {
     ident: String,
     config: Value,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct Group {
+    name: String,
+    #[serde(default = "empty_object")]
+    attributes: Value,
+    /// Changes to the configs of widgets for teams in this group, keyed by widget ident
+    #[serde(default = "empty_object")]
+    overrides: Value,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct Team {
     name: String,
     access_code: String,
     #[serde(default = "empty_object")]
     attributes: Value,
+    /// Changes to the configs of widgets for this team, keyed by widget ident
+    #[serde(default = "empty_object")]
+    overrides: Value,
+    /// The name of the team's group
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    ident: String,
+    /// The path to the file, relative to the game file
+    path: PathBuf,
+    #[serde(default)]
+    content_type: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -45,10 +88,16 @@ struct Game {
     #[serde(default)]
     id: Option<Uuid>,
     #[serde(default)]
+    time_zone: Option<String>,
+    #[serde(default)]
     widgets: Vec<Widget>,
     #[serde(default)]
+    groups: Vec<Group>,
+    #[serde(default)]
     teams: Vec<Team>,
     #[serde(default)]
+    assets: Vec<Asset>,
+    #[serde(default)]
     mixins: HashMap<String, Value>,
 }
 
@@ -57,8 +106,12 @@ fn empty_object() -> Value {
 }
 
 #[derive(Debug, Clone, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 /// Prepares a seed from JSON5
 struct Options {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input path (default stdin)
     input: Option<PathBuf>,
     /// Output path (default stdout)
@@ -76,6 +129,22 @@ struct Options {
     /// Act on widget with a given ident
     #[arg(long = "widget", short)]
     widgets: Vec<String>,
+
+    /// Don't check the game for invalid expressions
+    #[arg(long)]
+    no_check: bool,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Rewrite every expression in a game file in canonical form
+    Fmt {
+        /// Input path (default stdin)
+        input: Option<PathBuf>,
+        /// Output path (default stdout)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -132,20 +201,171 @@ impl Widget {
     }
 }
 
+impl Asset {
+    fn content_type(&self) -> &str {
+        if let Some(content_type) = &self.content_type {
+            return content_type;
+        }
+
+        let extension = self.path.extension().and_then(|e| e.to_str());
+
+        match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("svg") => "image/svg+xml",
+            Some("mp3") => "audio/mpeg",
+            Some("ogg" | "oga") => "audio/ogg",
+            Some("wav") => "audio/wav",
+            Some("m4a") => "audio/mp4",
+            Some("pdf") => "application/pdf",
+            Some("txt") => "text/plain",
+            _ => "application/octet-stream",
+        }
+    }
+
+    fn data(&self) -> Result<String> {
+        let data = fs::read(&self.path)
+            .map_err(|e| anyhow!("failed to read asset {}: {e}", self.path.display()))?;
+
+        let mut hex = String::with_capacity(data.len() * 2);
+
+        for byte in data {
+            write!(hex, "{byte:02x}")?;
+        }
+
+        Ok(hex)
+    }
+
+    pub fn seed(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
+        writeln!(
+            w,
+            "INSERT INTO asset (game, ident, content_type, data) VALUES ({}, {}, {}, decode({}, 'hex'));",
+            Escape(game),
+            Escape(&self.ident),
+            Escape(self.content_type()),
+            Escape(self.data()?)
+        )?;
+
+        Ok(())
+    }
+
+    pub fn patch(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
+        writeln!(
+            w,
+            concat!(
+                "INSERT INTO asset (game, ident, content_type, data) VALUES ({}, {}, {}, decode({}, 'hex')) ",
+                "ON CONFLICT (game, ident) DO UPDATE SET content_type = excluded.content_type, data = excluded.data;"
+            ),
+            Escape(game),
+            Escape(&self.ident),
+            Escape(self.content_type()),
+            Escape(self.data()?)
+        )?;
+
+        Ok(())
+    }
+}
+
+impl Group {
+    pub fn seed(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
+        let id = Uuid::new_v4();
+
+        let attributes = serde_json::to_string(&self.attributes)?;
+        let overrides = serde_json::to_string(&self.overrides)?;
+
+        writeln!(
+            w,
+            "INSERT INTO team_group (game, id, name, attributes, overrides) VALUES ({}, {}, {}, {}, {});",
+            Escape(game),
+            Escape(id),
+            Escape(&self.name),
+            Escape(attributes),
+            Escape(overrides)
+        )?;
+
+        Ok(())
+    }
+
+    pub fn patch(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
+        let id = Uuid::new_v4();
+
+        let attributes = serde_json::to_string(&self.attributes)?;
+        let overrides = serde_json::to_string(&self.overrides)?;
+
+        writeln!(
+            w,
+            concat!(
+                "INSERT INTO team_group (game, id, name, attributes, overrides) VALUES ({}, {}, {}, {}, {}) ",
+                "ON CONFLICT (game, name) DO UPDATE SET attributes = excluded.attributes, overrides = excluded.overrides;"
+            ),
+            Escape(game),
+            Escape(id),
+            Escape(&self.name),
+            Escape(attributes),
+            Escape(overrides)
+        )?;
+
+        Ok(())
+    }
+}
+
 impl Team {
+    /// Looks up the id of the team's group by its name.
+    fn group(&self, game: Uuid) -> String {
+        match &self.group {
+            Some(group) => format!(
+                "(SELECT id FROM team_group WHERE game = {} AND name = {})",
+                Escape(game),
+                Escape(group)
+            ),
+            None => String::from("NULL"),
+        }
+    }
+
     pub fn seed(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
         let id = Uuid::new_v4();
 
         let attributes = serde_json::to_string(&self.attributes)?;
+        let overrides = serde_json::to_string(&self.overrides)?;
+
+        writeln!(
+            w,
+            "INSERT INTO team (game, id, name, access_code, attributes, overrides, team_group) VALUES ({}, {}, {}, {}, {}, {}, {});",
+            Escape(game),
+            Escape(id),
+            Escape(&self.name),
+            Escape(&self.access_code),
+            Escape(attributes),
+            Escape(overrides),
+            self.group(game)
+        )?;
+
+        Ok(())
+    }
+
+    pub fn patch(&self, w: &mut impl Write, game: Uuid) -> Result<()> {
+        let id = Uuid::new_v4();
+
+        let attributes = serde_json::to_string(&self.attributes)?;
+        let overrides = serde_json::to_string(&self.overrides)?;
 
+        // Access codes are unique across all games, so a team of another game is never updated
         writeln!(
             w,
-            "INSERT INTO team (game, id, name, access_code, attributes) VALUES ({}, {}, {}, {}, {});",
+            concat!(
+                "INSERT INTO team (game, id, name, access_code, attributes, overrides, team_group) VALUES ({}, {}, {}, {}, {}, {}, {}) ",
+                "ON CONFLICT (access_code) DO UPDATE SET name = excluded.name, attributes = excluded.attributes, ",
+                "overrides = excluded.overrides, team_group = excluded.team_group WHERE team.game = excluded.game;"
+            ),
             Escape(game),
             Escape(id),
             Escape(&self.name),
             Escape(&self.access_code),
-            Escape(attributes)
+            Escape(attributes),
+            Escape(overrides),
+            self.group(game)
         )?;
 
         Ok(())
@@ -159,19 +379,28 @@ impl Game {
         writeln!(w, "BEGIN;")?;
         writeln!(
             w,
-            "INSERT INTO game (id, name) VALUES ({}, {});",
+            "INSERT INTO game (id, name, time_zone) VALUES ({}, {}, {});",
             Escape(id),
-            Escape(&self.name)
+            Escape(&self.name),
+            OptionalEscape(self.time_zone.as_ref())
         )?;
 
         for (i, widget) in self.widgets.iter().enumerate() {
             widget.seed(w, id, i)?;
         }
 
+        for group in &self.groups {
+            group.seed(w, id)?;
+        }
+
         for team in &self.teams {
             team.seed(w, id)?;
         }
 
+        for asset in &self.assets {
+            asset.seed(w, id)?;
+        }
+
         writeln!(w, "COMMIT;")?;
 
         Ok(())
@@ -180,6 +409,27 @@ impl Game {
     pub fn patch(&self, w: &mut impl Write, id: Uuid, widgets: HashSet<String>) -> Result<()> {
         writeln!(w, "BEGIN;")?;
 
+        if widgets.is_empty() {
+            writeln!(
+                w,
+                "UPDATE game SET time_zone = {} WHERE id = {};",
+                OptionalEscape(self.time_zone.as_ref()),
+                Escape(id),
+            )?;
+
+            for asset in &self.assets {
+                asset.patch(w, id)?;
+            }
+
+            for group in &self.groups {
+                group.patch(w, id)?;
+            }
+
+            for team in &self.teams {
+                team.patch(w, id)?;
+            }
+        }
+
         for (i, widget) in self.widgets.iter().enumerate() {
             if widgets.is_empty() || widgets.contains(&widget.ident) {
                 widget.patch(w, id, widgets.is_empty().then_some(i))?;
@@ -195,45 +445,48 @@ impl Game {
     }
 }
 
-fn preprocess(game: &mut Game) -> Result<()> {
-    fn replace_templates_in_string(
-        str: &str,
-        mut replacer: impl FnMut(String) -> Result<String>,
-    ) -> Result<String> {
-        let mut chars = str.chars().peekable();
-        let mut result = String::new();
-
-        while let Some(char) = chars.next() {
-            if char == '@' && chars.next_if_eq(&'[').is_some() {
-                let mut name = String::new();
-
-                loop {
-                    match chars.next() {
-                        Some(']') => break,
-                        Some(c) => name.push(c),
-                        None => bail!("no ']' to close open '@['"),
-                    }
+fn replace_templates_in_string(
+    str: &str,
+    mut replacer: impl FnMut(String) -> Result<String>,
+) -> Result<String> {
+    let mut chars = str.chars().peekable();
+    let mut result = String::new();
+
+    while let Some(char) = chars.next() {
+        if char == '@' && chars.next_if_eq(&'[').is_some() {
+            let mut name = String::new();
+
+            loop {
+                match chars.next() {
+                    Some(']') => break,
+                    Some(c) => name.push(c),
+                    None => bail!("no ']' to close open '@['"),
                 }
-
-                result.push_str(&replacer(name)?);
-            } else {
-                result.push(char);
             }
-        }
 
-        Ok(result)
+            result.push_str(&replacer(name)?);
+        } else {
+            result.push(char);
+        }
     }
 
+    Ok(result)
+}
+
+/// Resolves a template like `@[-1]` to the ident of the widget at that offset from `idx`.
+fn template_ident(offset: String, idents: &[String], idx: usize) -> Result<String> {
+    let offset: isize = offset.parse()?;
+    idx.checked_add_signed(offset)
+        .and_then(|i| idents.get(i))
+        .cloned()
+        .ok_or_else(|| anyhow!("invalid index {offset}"))
+}
+
+fn preprocess(game: &mut Game) -> Result<()> {
     fn replace_templates(value: &mut Value, idents: &[String], idx: usize) -> Result<()> {
         match value {
             Value::String(string) => {
-                *string = replace_templates_in_string(&string, |s| {
-                    let offset: isize = s.parse()?;
-                    idx.checked_add_signed(offset)
-                        .and_then(|i| idents.get(i))
-                        .cloned()
-                        .ok_or_else(|| anyhow!("invalid index {offset}"))
-                })?;
+                *string = replace_templates_in_string(string, |s| template_ident(s, idents, idx))?;
             }
             Value::Array(values) => {
                 for value in values {
@@ -302,21 +555,94 @@ fn preprocess(game: &mut Game) -> Result<()> {
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let opts = Options::parse();
+fn check(game: &Game) -> Result<()> {
+    let definition = GameDefinition {
+        name: game.name.clone(),
+        time_zone: game.time_zone.clone(),
+        widgets: game
+            .widgets
+            .iter()
+            .map(|w| (w.ident.clone(), w.config.clone()))
+            .collect(),
+        groups: game
+            .groups
+            .iter()
+            .map(|g| GroupDefinition {
+                name: g.name.clone(),
+                attributes: g.attributes.clone(),
+                overrides: g.overrides.clone(),
+            })
+            .collect(),
+        teams: game
+            .teams
+            .iter()
+            .map(|t| TeamDefinition {
+                name: t.name.clone(),
+                attributes: t.attributes.clone(),
+                overrides: t.overrides.clone(),
+                group: t.group.clone(),
+            })
+            .collect(),
+        assets: game.assets.iter().map(|a| a.ident.clone()).collect(),
+    };
+
+    let errors = definition.check();
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    for error in &errors {
+        eprintln!("{error}");
+    }
 
-    let input = match &opts.input {
-        Some(path) => fs::read_to_string(path)?,
+    bail!("game has {} problem(s)", errors.len());
+}
+
+fn read_input(path: Option<&PathBuf>) -> Result<String> {
+    match path {
+        Some(path) => Ok(fs::read_to_string(path)?),
         None => {
             let mut string = String::new();
             stdin().lock().read_to_string(&mut string)?;
-            string
+            Ok(string)
         }
-    };
+    }
+}
+
+fn main() -> Result<()> {
+    let opts = Options::parse();
+
+    if let Some(Command::Fmt { input, output }) = &opts.command {
+        let formatted = fmt::format_game(&read_input(input.as_ref())?)?;
+
+        match output {
+            Some(path) => fs::write(path, formatted)?,
+            None => stdout().lock().write_all(formatted.as_bytes())?,
+        }
+
+        return Ok(());
+    }
+
+    let input = read_input(opts.input.as_ref())?;
 
     let mut game: Game = json5::from_str(&input)?;
     preprocess(&mut game)?;
 
+    let base = opts
+        .input
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new(""));
+
+    for asset in &mut game.assets {
+        asset.path = base.join(&asset.path);
+    }
+
+    if !opts.no_check {
+        check(&game)?;
+    }
+
     fn generate(mut output: impl Write, game: Game, opts: Options) -> Result<()> {
         let id = opts.game_id.or(game.id);
 
@@ -342,3 +668,6 @@ fn main() -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod test;
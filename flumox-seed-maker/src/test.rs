@@ -0,0 +1,107 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{fmt::format_game, Team};
+
+#[test]
+fn fmt_expressions() {
+    let source = r#"{
+    // The first widget
+    name: "Game",
+    widgets: [
+        {
+            ident: "first",
+            config: {
+                type: "text",
+                heading: "First",
+                content: [{ text: "Hi", show: "always|never" }],
+                visible: "always&never",
+            },
+        },
+        {
+            "ident": "second",
+            "config": { "type": "text", "content": [], "visible": '@[-1].visible|never' },
+        },
+        { ident: "third", config: { "@mixin": "hidden", type: "text", content: [] } },
+    ],
+    mixins: { hidden: { visible: "never|first.visible" } },
+}"#;
+
+    let formatted = format_game(source).unwrap();
+
+    assert_eq!(
+        formatted,
+        source
+            .replace("\"always|never\"", "\"always | never\"")
+            .replace("\"always&never\"", "\"always & never\"")
+            .replace("'@[-1].visible|never'", "'@[-1].visible | never'")
+            .replace("\"never|first.visible\"", "\"never | first.visible\"")
+    );
+}
+
+#[test]
+fn fmt_only_expression_positions() {
+    // Text equal to the source of an expression is left alone
+    let source = r#"{
+    name: "always&never",
+    widgets: [
+        {
+            ident: "first",
+            config: {
+                type: "prompt",
+                name: "always&never",
+                details: ["always&never"],
+                prompt: "always&never",
+                solutions: [{ type: "alphanumeric", solution: "always&never" }],
+                visible: "always&never",
+            },
+        },
+    ],
+    groups: [{ name: "always&never", attributes: { open: "always&never" } }],
+    teams: [
+        {
+            name: "always&never",
+            access_code: "always&never",
+            attributes: { motto: { text: "always&never" } },
+            overrides: { first: { name: "always&never", disabled: "always&never" } },
+        },
+    ],
+}"#;
+
+    let formatted = format_game(source).unwrap();
+
+    let expected = source
+        .replace("visible: \"always&never\"", "visible: \"always & never\"")
+        .replace("open: \"always&never\"", "open: \"always & never\"")
+        .replace("disabled: \"always&never\"", "disabled: \"always & never\"");
+
+    assert_eq!(formatted, expected);
+    assert_eq!(formatted.matches("always & never").count(), 3);
+}
+
+#[test]
+fn patch_team() {
+    let team: Team = serde_json::from_value(json!({
+        "name": "Team",
+        "access_code": "secret",
+        "attributes": { "start": "2024-06-01 12:00 +0" },
+        "group": "Fast",
+    }))
+    .unwrap();
+
+    let mut output = Vec::new();
+    team.patch(&mut output, Uuid::nil()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    // Teams missing from the database are added, and existing ones get new attributes
+    assert!(output.starts_with("INSERT INTO team "), "{output}");
+    assert!(
+        output.contains("attributes = excluded.attributes"),
+        "{output}"
+    );
+    assert!(
+        output.contains("'{\"start\":\"2024-06-01 12:00 +0\"}'"),
+        "{output}"
+    );
+    assert!(output.contains("name = 'Fast'"), "{output}");
+}
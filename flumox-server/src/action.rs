@@ -13,7 +13,8 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    db::{add_action, load_state, set_state, LoadStateError},
+    cache::ConfigCache,
+    db::{add_action, load_state, set_state, ConfigError, LoadStateError},
     error::InternalError,
 };
 
@@ -27,6 +28,7 @@ pub enum SubmissionResponse {
 
 pub async fn submit_action(
     db: &mut Client,
+    cache: &ConfigCache,
     game: Uuid,
     team: Uuid,
     widget: Uuid,
@@ -43,6 +45,7 @@ pub async fn submit_action(
 
     async fn run(
         db: &mut Client,
+        cache: &ConfigCache,
         game: Uuid,
         team: Uuid,
         widget: Uuid,
@@ -55,7 +58,7 @@ pub async fn submit_action(
             .start()
             .await?;
 
-        let (game_state, meta) = load_state(&mut db, game, team).await?;
+        let (game_state, meta) = load_state(&mut db, cache, game, team).await?;
 
         let (ident, _) = meta
             .iter()
@@ -68,7 +71,7 @@ pub async fn submit_action(
             .ok_or(ActionError::UnknownIdent)?;
 
         let mut cache = Cache::default();
-        let env = Environment::new(&game_state, &mut cache, ident);
+        let env = Environment::new(&game_state, &mut cache, ident, time);
         let ctx = ActionContext { env, time };
 
         let ActionEffect { new_state, toast } = instance.submit(action, ctx)?;
@@ -90,7 +93,7 @@ pub async fn submit_action(
     info!(%game, %team, %widget, %time, "Action by {team} for {widget} received: {action:?}");
 
     loop {
-        match run(db, game, team, widget, &action, time).await {
+        match run(db, cache, game, team, widget, &action, time).await {
             Ok(toast) => break Ok(SubmissionResponse::Success { toast }),
             Err(ProcessActionError::Action(ActionError::NotPossible)) => {
                 break Ok(SubmissionResponse::NotPossible)
@@ -106,6 +109,9 @@ pub async fn submit_action(
             Err(ProcessActionError::StateMismatch(error)) => {
                 break Err(error.into());
             }
+            Err(ProcessActionError::Config(error)) => {
+                break Err(error.into());
+            }
             Err(ProcessActionError::Db(error)) => match error.code() {
                 Some(&SqlState::T_R_SERIALIZATION_FAILURE | &SqlState::T_R_DEADLOCK_DETECTED) => {
                     if let Some(delay) = retries.next() {
@@ -133,6 +139,8 @@ pub enum ProcessActionError {
     Action(#[from] ActionError),
     #[error(transparent)]
     StateMismatch(#[from] StateMismatchError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
     #[error("failed to comunicate with database: {0}")]
     Db(#[from] tokio_postgres::Error),
 }
@@ -142,6 +150,7 @@ impl From<LoadStateError> for ProcessActionError {
         match value {
             LoadStateError::Database(e) => e.into(),
             LoadStateError::StateMismatch(e) => e.into(),
+            LoadStateError::Config(e) => e.into(),
         }
     }
 }
@@ -1,4 +1,12 @@
-use axum::Json;
+use axum::{
+    extract::{Path, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
 use deadpool_postgres::Client;
 use flumox::Action;
 use serde::Deserialize;
@@ -8,26 +16,29 @@ use uuid::Uuid;
 
 use crate::{
     action::{submit_action, SubmissionResponse},
-    db::load_state,
+    cache::ConfigCache,
+    db::{load_asset, load_state, Asset},
     error::InternalError,
     extract::DbConnection,
     session::Session,
-    types::WidgetInstance,
+    types::{ScoreInfo, WidgetInstance},
     view::render,
 };
 
 pub async fn view(
     Session { game, team }: Session,
+    State(cache): State<ConfigCache>,
     DbConnection(db): DbConnection,
 ) -> Result<Json<Vec<WidgetInstance>>, InternalError> {
     async fn run(
         mut db: Client,
+        cache: &ConfigCache,
         game: Uuid,
         team: Uuid,
         time: OffsetDateTime,
     ) -> Result<Vec<WidgetInstance>, InternalError> {
         let mut db = db.transaction().await?;
-        let (game, meta) = load_state(&mut db, game, team).await?;
+        let (game, meta) = load_state(&mut db, cache, game, team).await?;
         db.commit().await?;
 
         Ok(render(&game, &meta, time)?.widgets)
@@ -35,7 +46,7 @@ pub async fn view(
 
     let time = OffsetDateTime::now_utc();
 
-    match run(db, game, team, time).await {
+    match run(db, &cache, game, team, time).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => {
             error!("Failed to render view: {err}");
@@ -44,6 +55,94 @@ pub async fn view(
     }
 }
 
+pub async fn score(
+    Session { game, team }: Session,
+    State(cache): State<ConfigCache>,
+    DbConnection(db): DbConnection,
+) -> Result<Json<ScoreInfo>, InternalError> {
+    async fn run(
+        mut db: Client,
+        cache: &ConfigCache,
+        game: Uuid,
+        team: Uuid,
+        time: OffsetDateTime,
+    ) -> Result<ScoreInfo, InternalError> {
+        let mut db = db.transaction().await?;
+        let (game, _) = load_state(&mut db, cache, game, team).await?;
+        db.commit().await?;
+
+        let score = game.score(time)?.at(time);
+
+        Ok(ScoreInfo { score })
+    }
+
+    let time = OffsetDateTime::now_utc();
+
+    match run(db, &cache, game, team, time).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => {
+            error!("Failed to compute score: {err}");
+            Err(err)
+        }
+    }
+}
+
+pub async fn asset(
+    Session { game, team }: Session,
+    State(cache): State<ConfigCache>,
+    DbConnection(db): DbConnection,
+    Path(ident): Path<String>,
+) -> Result<Response, InternalError> {
+    async fn run(
+        mut db: Client,
+        cache: &ConfigCache,
+        game: Uuid,
+        team: Uuid,
+        ident: &str,
+        time: OffsetDateTime,
+    ) -> Result<Option<Asset>, InternalError> {
+        let mut db = db.transaction().await?;
+        let (state, meta) = load_state(&mut db, cache, game, team).await?;
+
+        // Assets are only served once they are shown to the team
+        let visible = render(&state, &meta, time)?
+            .widgets
+            .iter()
+            .any(|w| w.view.assets().contains(&ident));
+
+        let asset = if visible {
+            load_asset(&mut db, game, ident).await?
+        } else {
+            None
+        };
+
+        db.commit().await?;
+
+        Ok(asset)
+    }
+
+    let time = OffsetDateTime::now_utc();
+
+    match run(db, &cache, game, team, &ident, time).await {
+        Ok(Some(Asset { content_type, data })) => Ok((
+            [
+                (CONTENT_TYPE, content_type),
+                // Assets are uploaded by organizers, so browsers mustn't guess their type,
+                // and they are gated per team, so shared caches mustn't keep them
+                (X_CONTENT_TYPE_OPTIONS, "nosniff".to_owned()),
+                (CACHE_CONTROL, "private".to_owned()),
+            ],
+            data,
+        )
+            .into_response()),
+        Ok(None) => Ok(StatusCode::NOT_FOUND.into_response()),
+        Err(err) => {
+            error!("Failed to load asset: {err}");
+            Err(err)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Submission {
     widget: Uuid,
@@ -53,10 +152,11 @@ pub struct Submission {
 
 pub async fn submit(
     Session { game, team }: Session,
+    State(cache): State<ConfigCache>,
     DbConnection(mut db): DbConnection,
     Json(Submission { widget, action }): Json<Submission>,
 ) -> Result<Json<SubmissionResponse>, InternalError> {
-    match submit_action(&mut db, game, team, widget, action).await {
+    match submit_action(&mut db, &cache, game, team, widget, action).await {
         Ok(r) => Ok(Json(r)),
         Err(error) => {
             error!("Failed to evaluate action: {error}");
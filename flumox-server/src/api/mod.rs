@@ -3,7 +3,7 @@ mod session;
 mod socket;
 
 use axum::http::StatusCode;
-pub use game::{submit, view};
+pub use game::{asset, score, submit, view};
 pub use session::{login, me};
 pub use socket::sync_socket;
 
@@ -22,6 +22,7 @@ use tokio::{select, sync::broadcast::error::RecvError, time::sleep};
 use tracing::warn;
 
 use crate::{
+    cache::ConfigCache,
     db::{load_state, team_by_session_token, LoadStateError},
     error::{InternalError, InternalErrorType},
     message::{Channels, Invalidate},
@@ -111,7 +112,12 @@ async fn wait_until(time: OffsetDateTime) {
     }
 }
 
-async fn run(socket: &mut WebSocket, pool: Pool, channels: Channels) -> Result<(), RunSocketError> {
+async fn run(
+    socket: &mut WebSocket,
+    pool: Pool,
+    cache: ConfigCache,
+    channels: Channels,
+) -> Result<(), RunSocketError> {
     let (token, compress) = loop {
         match socket.recv().await.transpose()? {
             Some(Message::Text(payload)) => match serde_json::from_str(&payload) {
@@ -145,7 +151,7 @@ async fn run(socket: &mut WebSocket, pool: Pool, channels: Channels) -> Result<(
     let (mut state, mut meta) = {
         let mut db = pool.get().await?;
         let mut db = db.transaction().await?;
-        load_state(&mut db, game, team).await?
+        load_state(&mut db, &cache, game, team).await?
     };
 
     let RenderResult {
@@ -193,7 +199,7 @@ async fn run(socket: &mut WebSocket, pool: Pool, channels: Channels) -> Result<(
             (state, meta) = {
                 let mut db = pool.get().await?;
                 let mut db = db.transaction().await?;
-                load_state(&mut db, game, team).await?
+                load_state(&mut db, &cache, game, team).await?
             };
         }
 
@@ -216,6 +222,7 @@ async fn run(socket: &mut WebSocket, pool: Pool, channels: Channels) -> Result<(
 
 pub async fn sync_socket(
     State(pool): State<Pool>,
+    State(cache): State<ConfigCache>,
     State(channels): State<Channels>,
     ws: WebSocketUpgrade,
 ) -> Response {
@@ -223,7 +230,7 @@ pub async fn sync_socket(
         warn!("Websocket upgrade failed: {error}");
     })
     .on_upgrade(|mut socket| async move {
-        if let Err(error) = run(&mut socket, pool, channels).await {
+        if let Err(error) = run(&mut socket, pool, cache, channels).await {
             match &error {
                 RunSocketError::Internal(error) => {
                     if let Ok(payload) = internal_error(error) {
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::Arc};
+
+use flumox::Config;
+use parking_lot::Mutex;
+use time_expr::Zone;
+use uuid::Uuid;
+
+use crate::types::TeamId;
+
+#[derive(Debug, Clone)]
+pub struct WidgetConfig {
+    pub id: Uuid,
+    pub ident: String,
+    pub config: Config,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    pub widgets: Arc<[WidgetConfig]>,
+    pub zone: Option<Zone>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    games: HashMap<Uuid, GameConfig>,
+    /// Configs with the overrides of a team and its group applied
+    teams: HashMap<TeamId, GameConfig>,
+    generation: u64,
+}
+
+/// Parsed widget configs of games and teams, so that they aren't parsed again on every load.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// The state of the cache at the time a config was requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generation(u64);
+
+impl ConfigCache {
+    pub fn get(&self, game: Uuid) -> (Option<GameConfig>, Generation) {
+        let inner = self.inner.lock();
+        (
+            inner.games.get(&game).cloned(),
+            Generation(inner.generation),
+        )
+    }
+
+    pub fn insert(&self, game: Uuid, config: GameConfig, generation: Generation) {
+        let mut inner = self.inner.lock();
+
+        // The config might have been loaded before the invalidation, so it may be stale
+        if Generation(inner.generation) == generation {
+            inner.games.insert(game, config);
+        }
+    }
+
+    pub fn get_team(&self, team: TeamId) -> (Option<GameConfig>, Generation) {
+        let inner = self.inner.lock();
+        (
+            inner.teams.get(&team).cloned(),
+            Generation(inner.generation),
+        )
+    }
+
+    pub fn insert_team(&self, team: TeamId, config: GameConfig, generation: Generation) {
+        let mut inner = self.inner.lock();
+
+        if Generation(inner.generation) == generation {
+            inner.teams.insert(team, config);
+        }
+    }
+
+    pub fn invalidate(&self, game: Uuid) {
+        let mut inner = self.inner.lock();
+
+        inner.generation += 1;
+        inner.games.remove(&game);
+        inner.teams.retain(|team, _| team.game != game);
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+
+        inner.generation += 1;
+        inner.games.clear();
+        inner.teams.clear();
+    }
+}
@@ -0,0 +1,35 @@
+use deadpool_postgres::Pool;
+use tracing::{error, info, warn};
+
+use crate::{db::game_definitions, error::InternalError};
+
+/// Logs every problem found in the configs of the games in the database.
+pub async fn check_games(pool: Pool) {
+    if let Err(error) = run(&pool).await {
+        error!("Failed to check game configs: {error}");
+    }
+}
+
+async fn run(pool: &Pool) -> Result<(), InternalError> {
+    let games = {
+        let mut db = pool.get().await?;
+        let mut db = db.transaction().await?;
+        game_definitions(&mut db).await?
+    };
+
+    let mut problems = 0;
+
+    for (id, game) in &games {
+        for problem in game.check() {
+            warn!(game = %id, "Game {name} is misconfigured: {problem}", name = game.name);
+            problems += 1;
+        }
+    }
+
+    info!(
+        "Checked configs of {count} games, found {problems} problem(s)",
+        count = games.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use deadpool_postgres::Transaction;
+use tokio_postgres::Error;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+pub async fn load_asset(
+    db: &mut Transaction<'_>,
+    game: Uuid,
+    ident: &str,
+) -> Result<Option<Asset>, Error> {
+    const LOAD_ASSET: &str = "SELECT content_type, data FROM asset WHERE game=$1 AND ident=$2";
+
+    let statement = db.prepare_cached(LOAD_ASSET).await?;
+    let row = db.query_opt(&statement, &[&game, &ident]).await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(Asset {
+        content_type: row.try_get(0)?,
+        data: row.try_get(1)?,
+    }))
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use deadpool_postgres::Transaction;
+use flumox::{GameDefinition, GroupDefinition, TeamDefinition};
+use tokio_postgres::{types::Json, Error};
+use uuid::Uuid;
+
+pub async fn game_definitions(
+    db: &mut Transaction<'_>,
+) -> Result<HashMap<Uuid, GameDefinition>, Error> {
+    const GAMES: &str = "SELECT id, name, time_zone FROM game";
+    const WIDGETS: &str = "SELECT game, ident, config FROM widget ORDER BY priority DESC";
+    const GROUPS: &str = "SELECT game, name, attributes, overrides FROM team_group ORDER BY name";
+    const TEAMS: &str = concat!(
+        "SELECT team.game, team.name, team.attributes, team.overrides, team_group.name ",
+        "FROM team LEFT JOIN team_group ",
+        "ON team_group.game=team.game AND team_group.id=team.team_group ",
+        "ORDER BY team.name"
+    );
+    const ASSETS: &str = "SELECT game, ident FROM asset ORDER BY ident";
+
+    let mut games = HashMap::new();
+
+    let statement = db.prepare_cached(GAMES).await?;
+
+    for row in db.query(&statement, &[]).await? {
+        let id: Uuid = row.try_get(0)?;
+        let name: String = row.try_get(1)?;
+        let time_zone: Option<String> = row.try_get(2)?;
+
+        games.insert(
+            id,
+            GameDefinition {
+                name,
+                time_zone,
+                ..Default::default()
+            },
+        );
+    }
+
+    let statement = db.prepare_cached(WIDGETS).await?;
+
+    for row in db.query(&statement, &[]).await? {
+        let game: Uuid = row.try_get(0)?;
+        let ident: String = row.try_get(1)?;
+        let Json(config) = row.try_get(2)?;
+
+        if let Some(game) = games.get_mut(&game) {
+            game.widgets.push((ident, config));
+        }
+    }
+
+    let statement = db.prepare_cached(GROUPS).await?;
+
+    for row in db.query(&statement, &[]).await? {
+        let game: Uuid = row.try_get(0)?;
+        let name: String = row.try_get(1)?;
+        let Json(attributes) = row.try_get(2)?;
+        let Json(overrides) = row.try_get(3)?;
+
+        if let Some(game) = games.get_mut(&game) {
+            game.groups.push(GroupDefinition {
+                name,
+                attributes,
+                overrides,
+            });
+        }
+    }
+
+    let statement = db.prepare_cached(TEAMS).await?;
+
+    for row in db.query(&statement, &[]).await? {
+        let game: Uuid = row.try_get(0)?;
+        let name: String = row.try_get(1)?;
+        let Json(attributes) = row.try_get(2)?;
+        let Json(overrides) = row.try_get(3)?;
+        let group: Option<String> = row.try_get(4)?;
+
+        if let Some(game) = games.get_mut(&game) {
+            game.teams.push(TeamDefinition {
+                name,
+                attributes,
+                overrides,
+                group,
+            });
+        }
+    }
+
+    let statement = db.prepare_cached(ASSETS).await?;
+
+    for row in db.query(&statement, &[]).await? {
+        let game: Uuid = row.try_get(0)?;
+        let ident: String = row.try_get(1)?;
+
+        if let Some(game) = games.get_mut(&game) {
+            game.assets.push(ident);
+        }
+    }
+
+    Ok(games)
+}
@@ -1,5 +1,9 @@
+mod asset;
+mod check;
 mod session;
 mod state;
 
+pub use asset::{load_asset, Asset};
+pub use check::game_definitions;
 pub use session::{login, team_by_session_token, team_info, LoginResult};
-pub use state::{add_action, load_state, set_state, LoadStateError};
+pub use state::{add_action, load_state, set_state, ConfigError, LoadStateError};
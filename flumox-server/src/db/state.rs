@@ -1,61 +1,217 @@
 use std::collections::HashMap;
 
 use deadpool_postgres::Transaction;
-use flumox::{Action, Config, GameState, State, StateMismatchError};
+use flumox::{Action, Attributes, GameState, Overrides, State, StateMismatchError};
 use indexmap::IndexMap;
 use thiserror::Error;
 use time::OffsetDateTime;
+use time_expr::Zone;
 use tokio_postgres::{types::Json, Error};
 use uuid::Uuid;
 
 use crate::{
+    cache::{ConfigCache, GameConfig, WidgetConfig},
     error::InternalError,
     message::{invalidate, InvalidateMessage},
-    types::InstanceMetadata,
+    types::{InstanceMetadata, TeamId},
 };
 
 pub async fn load_state(
     db: &mut Transaction<'_>,
+    cache: &ConfigCache,
     game: Uuid,
     team: Uuid,
 ) -> Result<(GameState, HashMap<String, InstanceMetadata>), LoadStateError> {
-    const LOAD_STATE: &str = concat!(
-        "SELECT widget.id, widget.ident, widget.config, state.state ",
-        "FROM widget LEFT JOIN state ",
-        "ON state.game=widget.game AND state.widget=widget.id AND state.team=$2 ",
-        "WHERE widget.game=$1 ",
-        "ORDER BY widget.priority DESC"
-    );
+    // Taken before the overrides are read, so that a config built from stale ones isn't cached
+    let team_id = TeamId { game, team };
+    let (cached, generation) = cache.get_team(team_id);
+
+    const LOAD_STATE: &str = "SELECT widget, state FROM state WHERE game=$1 AND team=$2";
 
     let statement = db.prepare_cached(LOAD_STATE).await?;
     let rows = db.query(&statement, &[&game, &team]).await?;
 
+    let mut states = HashMap::new();
+
+    for row in rows {
+        let widget: Uuid = row.try_get(0)?;
+        let Json(state): Json<State> = row.try_get(1)?;
+
+        states.insert(widget, state);
+    }
+
+    const LOAD_TEAM: &str = concat!(
+        "SELECT team.name, team.attributes, team.overrides, ",
+        "team_group.name, team_group.attributes, team_group.overrides ",
+        "FROM team LEFT JOIN team_group ",
+        "ON team_group.game=team.game AND team_group.id=team.team_group ",
+        "WHERE team.game=$1 AND team.id=$2"
+    );
+
+    let statement = db.prepare_cached(LOAD_TEAM).await?;
+    let row = db.query_one(&statement, &[&game, &team]).await?;
+
+    let Json(mut team): Json<Attributes> = row.try_get(1)?;
+    team.name = row.try_get(0)?;
+
+    let group_attributes: Option<Json<Attributes>> = row.try_get(4)?;
+    let mut group = group_attributes.map(|Json(a)| a).unwrap_or_default();
+    group.name = row.try_get::<_, Option<String>>(3)?.unwrap_or_default();
+
+    let GameConfig { widgets, zone } = match cached {
+        Some(config) => config,
+        None => {
+            let overrides = |idx| match row.try_get::<_, Option<Json<Overrides>>>(idx) {
+                Ok(overrides) => Ok(overrides.map(|Json(o)| o).unwrap_or_default()),
+                Err(source) => Err(ConfigError::Overrides { source }),
+            };
+
+            // Overrides of the group are applied first, so those of the team take precedence
+            let layers = [&overrides(5)?, &overrides(2)?];
+
+            let config = override_config(db, cache, game, &layers).await?;
+            cache.insert_team(team_id, config.clone(), generation);
+            config
+        }
+    };
+
     let mut instances = IndexMap::new();
     let mut metadata = HashMap::new();
 
-    for row in rows {
-        let id: Uuid = row.try_get(0)?;
-        let ident: String = row.try_get(1)?;
-        let Json(config): Json<Config> = row.try_get(2)?;
+    for WidgetConfig { id, ident, config } in widgets.iter() {
+        // The widget configs are shared with the cache rather than copied
+        let config = config.clone();
 
-        let instance = if let Some(Json(state)) = row.try_get(3)? {
+        let instance = if let Some(state) = states.remove(id) {
             config.instance(state)?
         } else {
             config.instance_default()
         };
 
         instances.insert(ident.clone(), instance);
-        metadata.insert(ident, InstanceMetadata { id });
+        metadata.insert(ident.clone(), InstanceMetadata { id: *id });
     }
 
-    const LOAD_TEAM: &str = "SELECT attributes FROM team WHERE game=$1 AND id=$2";
+    Ok((
+        GameState {
+            instances,
+            team,
+            group,
+            zone,
+        },
+        metadata,
+    ))
+}
 
-    let statement = db.prepare_cached(LOAD_TEAM).await?;
-    let row = db.query_one(&statement, &[&game, &team]).await?;
+/// Loads the config of a game with the overrides of each layer applied.
+async fn override_config(
+    db: &mut Transaction<'_>,
+    cache: &ConfigCache,
+    game: Uuid,
+    layers: &[&Overrides],
+) -> Result<GameConfig, LoadStateError> {
+    const LOAD_SOURCES: &str = "SELECT ident, config FROM widget WHERE game=$1 AND ident=ANY($2)";
+
+    let config = load_config(db, cache, game).await?;
+    let idents: Vec<&str> = layers.iter().flat_map(|o| o.widgets()).collect();
+
+    if idents.is_empty() {
+        return Ok(config);
+    }
+
+    // Only the overridden widgets are needed as stored, and only until they're merged
+    let statement = db.prepare_cached(LOAD_SOURCES).await?;
+    let rows = db.query(&statement, &[&game, &idents]).await?;
+
+    let mut sources = HashMap::new();
+
+    for row in rows {
+        let ident: String = row.try_get(0)?;
+        let Json(source): Json<serde_json::Value> = row.try_get(1)?;
 
-    let Json(team) = row.try_get(0)?;
+        sources.insert(ident, source);
+    }
+
+    let mut widgets = Vec::new();
+
+    for widget in config.widgets.iter() {
+        let overridden = sources
+            .get(&widget.ident)
+            .and_then(|source| Overrides::config(layers, &widget.ident, source));
+
+        let config = match overridden {
+            Some(Ok(config)) => config,
+            Some(Err(source)) => {
+                let ident = widget.ident.clone();
+                return Err(ConfigError::Override { ident, source }.into());
+            }
+            None => widget.config.clone(),
+        };
 
-    Ok((GameState { instances, team }, metadata))
+        widgets.push(WidgetConfig {
+            id: widget.id,
+            ident: widget.ident.clone(),
+            config,
+        });
+    }
+
+    Ok(GameConfig {
+        widgets: widgets.into(),
+        zone: config.zone,
+    })
+}
+
+async fn load_config(
+    db: &mut Transaction<'_>,
+    cache: &ConfigCache,
+    game: Uuid,
+) -> Result<GameConfig, LoadStateError> {
+    const LOAD_GAME: &str = "SELECT time_zone FROM game WHERE id=$1";
+    const LOAD_WIDGETS: &str = concat!(
+        "SELECT id, ident, config FROM widget ",
+        "WHERE game=$1 ",
+        "ORDER BY priority DESC"
+    );
+
+    let (cached, generation) = cache.get(game);
+
+    if let Some(config) = cached {
+        return Ok(config);
+    }
+
+    let statement = db.prepare_cached(LOAD_GAME).await?;
+    let row = db.query_one(&statement, &[&game]).await?;
+
+    let zone = match row.try_get::<_, Option<String>>(0)? {
+        Some(name) => Some(Zone::named(&name).ok_or(ConfigError::Zone { name })?),
+        None => None,
+    };
+
+    let statement = db.prepare_cached(LOAD_WIDGETS).await?;
+    let rows = db.query(&statement, &[&game]).await?;
+
+    let mut widgets = Vec::new();
+
+    for row in rows {
+        let id: Uuid = row.try_get(0)?;
+        let ident: String = row.try_get(1)?;
+        let Json(source): Json<serde_json::Value> = row.try_get(2)?;
+
+        let config = match serde_json::from_value(source) {
+            Ok(config) => config,
+            Err(source) => return Err(ConfigError::Widget { ident, source }.into()),
+        };
+
+        widgets.push(WidgetConfig { id, ident, config });
+    }
+
+    let config = GameConfig {
+        widgets: widgets.into(),
+        zone,
+    };
+    cache.insert(game, config.clone(), generation);
+
+    Ok(config)
 }
 
 pub async fn set_state(
@@ -113,6 +269,26 @@ pub enum LoadStateError {
     Database(#[from] Error),
     #[error(transparent)]
     StateMismatch(#[from] StateMismatchError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("invalid config for widget \"{ident}\": {source}")]
+    Widget {
+        ident: String,
+        source: serde_json::Error,
+    },
+    #[error("unknown time zone \"{name}\"")]
+    Zone { name: String },
+    #[error("invalid override for widget \"{ident}\": {source}")]
+    Override {
+        ident: String,
+        source: serde_json::Error,
+    },
+    #[error("invalid team overrides: {source}")]
+    Overrides { source: Error },
 }
 
 impl From<LoadStateError> for InternalError {
@@ -120,6 +296,7 @@ impl From<LoadStateError> for InternalError {
         match value {
             LoadStateError::Database(e) => e.into(),
             LoadStateError::StateMismatch(e) => e.into(),
+            LoadStateError::Config(e) => e.into(),
         }
     }
 }
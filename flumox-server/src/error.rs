@@ -9,6 +9,8 @@ use serde::Serialize;
 use thiserror::Error;
 use time_expr::EvalError;
 
+use crate::db::ConfigError;
+
 #[derive(Debug, Error)]
 pub enum InternalError {
     #[error("failed to comunicate with database: {source}")]
@@ -23,7 +25,12 @@ pub enum InternalError {
         #[from]
         source: StateMismatchError,
     },
-    #[error("failed to evaluate expression: {source}")]
+    #[error("failed to load game config: {source}")]
+    BadConfig {
+        #[from]
+        source: ConfigError,
+    },
+    #[error("failed to evaluate expression: {}", .source.snippet(None))]
     Eval {
         #[from]
         source: EvalError,
@@ -54,6 +61,7 @@ impl InternalError {
             InternalError::Database { .. } => InternalErrorType::Database,
             InternalError::Pool => InternalErrorType::Database,
             InternalError::BadStateType { .. } => InternalErrorType::Config,
+            InternalError::BadConfig { .. } => InternalErrorType::Config,
             InternalError::Eval { .. } => InternalErrorType::Config,
         }
     }
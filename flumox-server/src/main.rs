@@ -1,6 +1,7 @@
 use std::{net::SocketAddr, path::PathBuf};
 
 use anyhow::Result;
+use cache::ConfigCache;
 use channel_map::ChannelMap;
 use clap::{ArgAction, Parser};
 use deadpool_postgres::{Manager, Pool};
@@ -15,6 +16,8 @@ use tracing_subscriber::{
 
 mod action;
 mod api;
+mod cache;
+mod check;
 mod db;
 mod error;
 mod extract;
@@ -53,7 +56,7 @@ fn connect_db(config: Config) -> Result<Pool> {
     Ok(pool)
 }
 
-fn start_message_listener(config: Config) -> Channels {
+fn start_message_listener(config: Config, config_cache: ConfigCache) -> Channels {
     let (online_sender, online_receiver) = watch::channel(false);
     let (reconnect, _) = broadcast::channel(1);
     let game = ChannelMap::new(1);
@@ -62,6 +65,7 @@ fn start_message_listener(config: Config) -> Channels {
     tokio::spawn(message::listen(
         config,
         ChannelSender {
+            config_cache,
             online: online_sender,
             reconnect: reconnect.clone(),
             invalidate_game: game.clone(),
@@ -101,9 +105,19 @@ async fn main() -> Result<()> {
     setup_tracing(&options)?;
 
     let pool = connect_db(options.db.clone())?;
-    let channels = start_message_listener(options.db);
+    let config_cache = ConfigCache::default();
+    let channels = start_message_listener(options.db, config_cache.clone());
 
-    let state = State { pool, channels };
+    tokio::spawn(check::check_games(pool.clone()));
+
+    let state = State {
+        pool,
+        config_cache,
+        channels,
+    };
 
     serve(state, options.address, options.serve).await
 }
+
+#[cfg(test)]
+mod test;
@@ -10,7 +10,10 @@ use super::{ChannelSender, Invalidate, InvalidateMessage};
 
 fn process_message(message: Notification, channels: &ChannelSender) {
     match serde_json::from_str(message.payload()) {
-        Ok(InvalidateMessage::Game { game }) => channels.invalidate_game.send(&game, Invalidate),
+        Ok(InvalidateMessage::Game { game }) => {
+            channels.config_cache.invalidate(game);
+            channels.invalidate_game.send(&game, Invalidate)
+        }
         Ok(InvalidateMessage::Team { game, team }) => channels
             .invalidate_team
             .send(&TeamId { game, team }, Invalidate),
@@ -34,6 +37,7 @@ async fn run_connection(
 
         if result.is_ok() {
             info!("Listening for messages");
+            channels.config_cache.clear();
             let _ = channels.reconnect.send(Invalidate);
             channels.online.send_replace(true);
         }
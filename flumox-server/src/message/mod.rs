@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, watch};
 use uuid::Uuid;
 
-use crate::types::TeamId;
+use crate::{cache::ConfigCache, types::TeamId};
 
 mod listen;
 mod notify;
@@ -24,6 +24,7 @@ pub struct Channels {
 
 #[derive(Debug)]
 pub struct ChannelSender {
+    pub config_cache: ConfigCache,
     pub online: watch::Sender<bool>,
     pub reconnect: broadcast::Sender<Invalidate>,
     pub invalidate_game: ChannelMap<Uuid, Invalidate>,
@@ -26,6 +26,8 @@ pub async fn serve(state: State, address: SocketAddr, serve: Option<PathBuf>) ->
         .route("/login", post(api::login))
         .route("/me", get(api::me))
         .route("/view", get(api::view))
+        .route("/score", get(api::score))
+        .route("/asset/:ident", get(api::asset))
         .route("/action", post(api::submit))
         .route("/sync", get(api::sync_socket))
         .fallback(api::not_found)
@@ -1,10 +1,11 @@
 use axum::extract::FromRef;
 use deadpool_postgres::Pool;
 
-use crate::message::Channels;
+use crate::{cache::ConfigCache, message::Channels};
 
 #[derive(Debug, Clone, FromRef)]
 pub struct State {
     pub pool: Pool,
+    pub config_cache: ConfigCache,
     pub channels: Channels,
 }
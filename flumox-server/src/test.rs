@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    cache::{ConfigCache, GameConfig},
+    types::TeamId,
+};
+
+fn config() -> GameConfig {
+    GameConfig {
+        widgets: Arc::new([]),
+        zone: None,
+    }
+}
+
+#[test]
+fn cache_hit() {
+    let cache = ConfigCache::default();
+    let game = Uuid::now_v7();
+
+    let (cached, generation) = cache.get(game);
+    assert!(cached.is_none());
+
+    cache.insert(game, config(), generation);
+
+    let (cached, later) = cache.get(game);
+    assert!(cached.is_some());
+    assert_eq!(generation, later);
+}
+
+#[test]
+fn cache_stale_insert() {
+    let cache = ConfigCache::default();
+    let game = Uuid::now_v7();
+    let team = TeamId {
+        game,
+        team: Uuid::now_v7(),
+    };
+
+    let (_, generation) = cache.get(game);
+    let (_, team_generation) = cache.get_team(team);
+
+    // A config loaded before the game changed mustn't be cached
+    cache.invalidate(game);
+    cache.insert(game, config(), generation);
+    cache.insert_team(team, config(), team_generation);
+
+    assert!(cache.get(game).0.is_none());
+    assert!(cache.get_team(team).0.is_none());
+
+    let (_, generation) = cache.get(game);
+    cache.insert(game, config(), generation);
+    assert!(cache.get(game).0.is_some());
+}
+
+#[test]
+fn cache_invalidate() {
+    let cache = ConfigCache::default();
+    let (game, other) = (Uuid::now_v7(), Uuid::now_v7());
+    let team = |game| TeamId {
+        game,
+        team: Uuid::now_v7(),
+    };
+    let (team, other_team) = (team(game), team(other));
+
+    let (_, generation) = cache.get(game);
+    cache.insert(game, config(), generation);
+    cache.insert(other, config(), generation);
+    cache.insert_team(team, config(), generation);
+    cache.insert_team(other_team, config(), generation);
+
+    cache.invalidate(game);
+
+    assert!(cache.get(game).0.is_none());
+    assert!(cache.get_team(team).0.is_none());
+    assert!(cache.get(other).0.is_some());
+    assert!(cache.get_team(other_team).0.is_some());
+
+    cache.clear();
+
+    assert!(cache.get(other).0.is_none());
+    assert!(cache.get_team(other_team).0.is_none());
+}
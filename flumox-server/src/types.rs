@@ -13,6 +13,11 @@ pub struct TeamInfo {
     pub game: GameInfo,
 }
 
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ScoreInfo {
+    pub score: i64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TeamId {
     pub game: Uuid,
@@ -24,13 +24,14 @@ pub fn render(
     let mut result = Vec::new();
 
     for (ident, widget) in game.instances.iter() {
-        let env = Environment::new(game, &mut cache, ident);
+        let env = Environment::new(game, &mut cache, ident, time);
         let ctx = ViewContext::new(env, &mut tracker);
 
         if let Some(view) = widget.view(ctx)? {
             let Some(meta) = meta.get(ident) else {
                 return Err(EvalError::UnknownPath {
                     path: ident.clone().into_boxed_str(),
+                    span: None,
                 });
             };
 
@@ -44,6 +45,10 @@ pub fn render(
 
     result.sort_by_key(|w| w.obsolete);
 
+    if let Some(time) = cache.valid_until() {
+        tracker.record_change(time);
+    }
+
     Ok(RenderResult {
         widgets: result,
         valid_until: tracker.valid_until(),
@@ -9,6 +9,8 @@ use crate::{error::ActionResult, expr::Expr, ActionError, Environment, Instance,
 pub enum Action {
     Answer(Answer),
     Hint(Hint),
+    Choice(Choice),
+    CheckIn(CheckIn),
 }
 
 impl Instance {
@@ -20,6 +22,12 @@ impl Instance {
             (Instance::Prompt(config, state), Action::Hint(action)) => config
                 .take_hint(state, action, ctx)
                 .map(|e| e.map(Box::new).map(State::Prompt)),
+            (Instance::Choice(config, state), Action::Choice(action)) => config
+                .submit_choice(state, action, ctx)
+                .map(|e| e.map(Box::new).map(State::Choice)),
+            (Instance::Checkpoint(config, state), Action::CheckIn(action)) => config
+                .check_in(state, action, ctx)
+                .map(|e| e.map(Box::new).map(State::Checkpoint)),
             _ => Err(ActionError::WidgetMismatch),
         }
     }
@@ -79,3 +87,16 @@ pub struct Answer {
 pub struct Hint {
     pub ident: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Choice {
+    pub selected: Vec<String>,
+}
+
+/// A position reported by the team's device, with its accuracy in meters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CheckIn {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: f64,
+}
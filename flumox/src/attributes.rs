@@ -1,13 +1,50 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{
+        self,
+        value::{MapAccessDeserializer, StrDeserializer},
+        MapAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
+use time_expr::{EvalError, Number};
 
-use crate::{expr::Expr, Environment, EvalResult};
+use crate::{check::Field, expr::Expr, Environment, EvalResult};
+
+/// The module holding the attributes of the team.
+pub(crate) const TEAM: &str = "team";
+
+/// The module holding the attributes of the team's group.
+pub(crate) const GROUP: &str = "group";
+
+/// The name of the team or group, which is inserted into text as `{team.name}`.
+const NAME: &str = "name";
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Attributes {
-    map: HashMap<String, Expr>,
+    map: HashMap<String, Attribute>,
+    /// The name of the team or group, which is stored alongside its attributes
+    #[serde(skip)]
+    pub name: String,
+}
+
+/// A value set for each team or group, which is either a condition, a string or a whole number.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum Attribute {
+    Condition(Expr),
+    Text { text: String },
+    Number { number: i64 },
+}
+
+/// The keys of an attribute given as an object, exactly one of which has to be set.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AttributeValue {
+    text: Option<String>,
+    number: Option<i64>,
 }
 
 impl Attributes {
@@ -16,10 +53,96 @@ impl Attributes {
             return Err(env.unknown_path(path));
         };
 
-        let Some(expr) = self.map.get(id) else {
+        let Some(Attribute::Condition(expr)) = self.map.get(id) else {
             return Err(env.unknown_path(path));
         };
 
         env.eval(expr)
     }
+
+    pub fn resolve_number(&self, path: &[&str], env: Environment) -> Result<Number, EvalError> {
+        match *path {
+            [id] => match self.map.get(id) {
+                Some(Attribute::Number { number }) => Ok(Number::constant(*number)),
+                _ => Err(env.unknown_path(path)),
+            },
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    /// Returns the attribute as it should be inserted into text, if it's a string or a number.
+    pub fn text(&self, name: &str) -> Option<String> {
+        // Teams without a group have a group without a name
+        if name == NAME {
+            return Some(self.name.clone()).filter(|name| !name.is_empty());
+        }
+
+        match self.map.get(name)? {
+            Attribute::Condition(_) => None,
+            Attribute::Text { text } => Some(text.clone()),
+            Attribute::Number { number } => Some(number.to_string()),
+        }
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields: Vec<_> = self
+            .map
+            .iter()
+            .filter_map(|(name, attribute)| match attribute {
+                Attribute::Condition(expr) => Some(Field::expr(name.as_str(), expr)),
+                Attribute::Number { .. } => Some(Field::number(name.as_str())),
+                Attribute::Text { .. } => None,
+            })
+            .collect();
+
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        fields
+    }
+}
+
+impl<'de> Deserialize<'de> for Attribute {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct AttributeVisitor;
+
+        impl<'de> Visitor<'de> for AttributeVisitor {
+            type Value = Attribute;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an expression, or an object with text or a number")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Expr::deserialize(StrDeserializer::new(v)).map(Attribute::Condition)
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let value = AttributeValue::deserialize(MapAccessDeserializer::new(map))?;
+
+                match value {
+                    AttributeValue {
+                        text: Some(text),
+                        number: None,
+                    } => Ok(Attribute::Text { text }),
+                    AttributeValue {
+                        text: None,
+                        number: Some(number),
+                    } => Ok(Attribute::Number { number }),
+                    _ => Err(de::Error::custom(
+                        "attribute must have either text or a number",
+                    )),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(AttributeVisitor)
+    }
 }
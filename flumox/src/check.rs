@@ -0,0 +1,448 @@
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use crate::{
+    attributes::{GROUP, TEAM},
+    expr::Expr,
+    game::GameState,
+    score::{POINTS, SCORE},
+};
+
+/// A value exposed by a widget or the team, or an expression used internally by one.
+#[derive(Debug, Clone)]
+pub struct Field<'a> {
+    /// The path of the field within its widget, where a `#` segment stands for any positive index
+    pub name: String,
+    pub expr: Option<&'a Expr>,
+    pub resolvable: bool,
+    /// Whether the field is a number rather than a condition
+    pub number: bool,
+    /// Expressions evaluated while computing a field derived from the team's state
+    pub uses: Vec<&'a Expr>,
+    /// Paths of attributes inserted into text shown by the widget, such as `team.start`
+    pub variables: Vec<String>,
+    /// Idents of assets shown by the widget
+    pub assets: Vec<&'a str>,
+}
+
+impl<'a> Field<'a> {
+    /// A value defined by an expression, which other expressions can refer to.
+    pub fn expr(name: impl Into<String>, expr: &'a Expr) -> Self {
+        Field {
+            name: name.into(),
+            expr: Some(expr),
+            resolvable: true,
+            number: false,
+            uses: Vec::new(),
+            variables: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    /// A value derived from the team's state, which other expressions can refer to.
+    pub fn state(name: impl Into<String>) -> Self {
+        Field {
+            name: name.into(),
+            expr: None,
+            resolvable: true,
+            number: false,
+            uses: Vec::new(),
+            variables: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    /// A number derived from the team's state, which other expressions can refer to.
+    pub fn number(name: impl Into<String>) -> Self {
+        Field {
+            name: name.into(),
+            expr: None,
+            resolvable: true,
+            number: true,
+            uses: Vec::new(),
+            variables: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    /// Records expressions which are evaluated while computing the field.
+    pub fn using(mut self, exprs: impl IntoIterator<Item = &'a Expr>) -> Self {
+        self.uses.extend(exprs);
+        self
+    }
+
+    /// Records assets which are shown along with the field.
+    pub fn showing(mut self, assets: impl IntoIterator<Item = &'a str>) -> Self {
+        self.assets.extend(assets);
+        self
+    }
+
+    /// Text which team or group attributes are inserted into.
+    pub fn template(name: impl Into<String>, variables: Vec<String>) -> Self {
+        Field {
+            name: name.into(),
+            expr: None,
+            resolvable: false,
+            number: false,
+            uses: Vec::new(),
+            variables,
+            assets: Vec::new(),
+        }
+    }
+
+    /// An expression which can't be referred to by other expressions.
+    pub fn internal(name: impl Into<String>, expr: &'a Expr) -> Self {
+        Field {
+            name: name.into(),
+            expr: Some(expr),
+            resolvable: false,
+            number: false,
+            uses: Vec::new(),
+            variables: Vec::new(),
+            assets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// The field containing the expression, such as `first.hint.spoiler.available`
+    pub location: String,
+    /// Whether other expressions can refer to the field
+    pub resolvable: bool,
+    /// The paths the expression refers to, with `this` replaced by the widget's ident
+    pub paths: Vec<String>,
+    /// The paths to numbers the expression refers to
+    pub numbers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CheckError {
+    #[error("{location}: unknown widget \"{ident}\" in path \"{path}\"")]
+    UnknownIdent {
+        location: String,
+        ident: String,
+        path: String,
+    },
+    #[error("{location}: unknown hint \"{hint}\" in path \"{path}\"")]
+    UnknownHint {
+        location: String,
+        hint: String,
+        path: String,
+    },
+    #[error("{location}: unknown attribute \"{path}\"")]
+    UnknownAttribute { location: String, path: String },
+    #[error("{location}: unknown path \"{path}\"")]
+    UnknownPath { location: String, path: String },
+    #[error("{location}: unknown asset \"{asset}\"")]
+    UnknownAsset { location: String, asset: String },
+    #[error("{location}: time literal has no time zone and the game has no default")]
+    MissingTimeZone { location: String },
+    #[error("circular dependency: {}", .paths.join(" -> "))]
+    CircularDependency { paths: Vec<String> },
+}
+
+impl GameState {
+    fn fields(&self) -> Vec<(&str, Vec<Field<'_>>)> {
+        let mut modules: Vec<_> = self
+            .instances
+            .iter()
+            .map(|(ident, instance)| (ident.as_str(), instance.fields()))
+            .collect();
+
+        let mut team = self.team.fields();
+        team.push(Field::number(SCORE));
+
+        modules.push((TEAM, team));
+        modules.push((GROUP, self.group.fields()));
+        modules
+    }
+
+    /// Lists every expression and computed number in the game along with the paths it refers to.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut dependencies = Vec::new();
+
+        for (module, fields) in self.fields() {
+            for field in fields {
+                // Simplification drops operands such as `b` in `never & b`,
+                // but their paths still have to exist
+                let exprs: Vec<time_expr::Expr> = field
+                    .expr
+                    .into_iter()
+                    .chain(field.uses)
+                    .map(Expr::unsimplified)
+                    .collect();
+
+                if exprs.is_empty() {
+                    continue;
+                }
+
+                let normalize = |paths: Vec<&[Box<str>]>| {
+                    paths
+                        .into_iter()
+                        .map(|path| normalize_path(module, path))
+                        .collect()
+                };
+
+                dependencies.push(Dependency {
+                    location: format!("{module}.{}", field.name),
+                    resolvable: field.resolvable,
+                    paths: normalize(exprs.iter().flat_map(|e| e.paths()).collect()),
+                    numbers: normalize(exprs.iter().flat_map(|e| e.number_paths()).collect()),
+                });
+            }
+        }
+
+        dependencies.push(Dependency {
+            location: format!("{TEAM}.{SCORE}"),
+            resolvable: true,
+            paths: Vec::new(),
+            numbers: self
+                .scored()
+                .map(|ident| format!("{ident}.{POINTS}"))
+                .collect(),
+        });
+
+        dependencies
+    }
+
+    /// Finds references to nonexistent paths and circular dependencies
+    /// without evaluating any expressions.
+    pub fn check(&self) -> Vec<CheckError> {
+        let known_by_kind = |number: bool| -> HashMap<&str, HashSet<String>> {
+            self.fields()
+                .into_iter()
+                .map(|(module, fields)| {
+                    let names = fields
+                        .into_iter()
+                        .filter(|f| f.resolvable && f.number == number)
+                        .map(|f| f.name)
+                        .collect();
+
+                    (module, names)
+                })
+                .collect()
+        };
+
+        let known = known_by_kind(false);
+        let known_numbers = known_by_kind(true);
+
+        let dependencies = self.dependencies();
+        let mut errors = Vec::new();
+
+        if self.zone.is_none() {
+            for (module, fields) in self.fields() {
+                for field in fields {
+                    if field
+                        .expr
+                        .is_some_and(|e| e.unsimplified().uses_default_zone())
+                    {
+                        errors.push(CheckError::MissingTimeZone {
+                            location: format!("{module}.{}", field.name),
+                        });
+                    }
+                }
+            }
+        }
+
+        for (module, fields) in self.fields() {
+            for field in fields {
+                for variable in &field.variables {
+                    if self.text(variable).is_none() {
+                        errors.push(CheckError::UnknownAttribute {
+                            location: format!("{module}.{}", field.name),
+                            path: variable.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        for dependency in &dependencies {
+            for path in &dependency.paths {
+                if let Some(error) = check_path(&known, &dependency.location, path) {
+                    errors.push(error);
+                }
+            }
+
+            for path in &dependency.numbers {
+                if let Some(error) = check_path(&known_numbers, &dependency.location, path) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        errors.extend(
+            find_cycles(&dependencies)
+                .into_iter()
+                .map(|paths| CheckError::CircularDependency { paths }),
+        );
+
+        errors
+    }
+
+    /// Finds assets shown by widgets which aren't among the game's `assets`.
+    pub fn check_assets(&self, assets: &[String]) -> Vec<CheckError> {
+        let mut errors = Vec::new();
+
+        for (module, fields) in self.fields() {
+            for field in fields {
+                for asset in field.assets {
+                    if !assets.iter().any(|a| a == asset) {
+                        errors.push(CheckError::UnknownAsset {
+                            location: format!("{module}.{}", field.name),
+                            asset: asset.to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+fn normalize_path(module: &str, path: &[Box<str>]) -> String {
+    let mut segments = path.iter().map(|s| &**s);
+    let mut string = String::new();
+
+    match segments.next() {
+        Some("this") => string.push_str(module),
+        Some(first) => string.push_str(first),
+        None => {}
+    }
+
+    for segment in segments {
+        string.push('.');
+        string.push_str(segment);
+    }
+
+    string
+}
+
+fn check_path(
+    known: &HashMap<&str, HashSet<String>>,
+    location: &str,
+    path: &str,
+) -> Option<CheckError> {
+    let (module, subpath) = path.split_once('.').unwrap_or((path, ""));
+
+    let Some(fields) = known.get(module) else {
+        return Some(CheckError::UnknownIdent {
+            location: location.to_owned(),
+            ident: module.to_owned(),
+            path: path.to_owned(),
+        });
+    };
+
+    if fields.contains(subpath) || index_pattern(subpath).is_some_and(|p| fields.contains(&p)) {
+        return None;
+    }
+
+    if module == TEAM || module == GROUP {
+        return Some(CheckError::UnknownAttribute {
+            location: location.to_owned(),
+            path: path.to_owned(),
+        });
+    }
+
+    if let Some(rest) = subpath.strip_prefix("hint.") {
+        let hint = rest.split('.').next().unwrap_or(rest);
+        let prefix = format!("hint.{hint}.");
+
+        if !fields.iter().any(|f| f.starts_with(&prefix)) {
+            return Some(CheckError::UnknownHint {
+                location: location.to_owned(),
+                hint: hint.to_owned(),
+                path: path.to_owned(),
+            });
+        }
+    }
+
+    Some(CheckError::UnknownPath {
+        location: location.to_owned(),
+        path: path.to_owned(),
+    })
+}
+
+/// Replaces a trailing positive index in a path, such as the `3` in `attempts.3`, with `#`.
+fn index_pattern(path: &str) -> Option<String> {
+    let (prefix, last) = path.rsplit_once('.')?;
+    last.parse::<usize>().ok().filter(|&n| n > 0)?;
+
+    Some(format!("{prefix}.#"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+struct CycleFinder<'a> {
+    dependencies: &'a [Dependency],
+    nodes: HashMap<&'a str, usize>,
+    marks: Vec<Mark>,
+    stack: Vec<usize>,
+    cycles: Vec<Vec<String>>,
+}
+
+impl<'a> CycleFinder<'a> {
+    fn visit(&mut self, node: usize) {
+        match self.marks[node] {
+            Mark::Done => return,
+            Mark::InProgress => {
+                let start = self.stack.iter().position(|&n| n == node).unwrap_or(0);
+
+                let mut cycle: Vec<String> = self.stack[start..]
+                    .iter()
+                    .map(|&n| self.dependencies[n].location.clone())
+                    .collect();
+                cycle.push(self.dependencies[node].location.clone());
+
+                self.cycles.push(cycle);
+                return;
+            }
+            Mark::Unvisited => {}
+        }
+
+        self.marks[node] = Mark::InProgress;
+        self.stack.push(node);
+
+        let dependency = &self.dependencies[node];
+
+        for path in dependency.paths.iter().chain(&dependency.numbers) {
+            if let Some(&next) = self.nodes.get(path.as_str()) {
+                self.visit(next);
+            }
+        }
+
+        self.stack.pop();
+        self.marks[node] = Mark::Done;
+    }
+}
+
+fn find_cycles(dependencies: &[Dependency]) -> Vec<Vec<String>> {
+    let nodes = dependencies
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.resolvable)
+        .map(|(i, d)| (d.location.as_str(), i))
+        .collect();
+
+    let mut finder = CycleFinder {
+        dependencies,
+        nodes,
+        marks: vec![Mark::Unvisited; dependencies.len()],
+        stack: Vec::new(),
+        cycles: Vec::new(),
+    };
+
+    for node in 0..dependencies.len() {
+        finder.visit(node);
+    }
+
+    finder.cycles
+}
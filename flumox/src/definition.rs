@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::Value;
+use time_expr::Zone;
+
+use crate::{Attributes, CheckError, Config, GameState, Instance, Overrides};
+
+/// A game as it's stored, before any of its parts are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct GameDefinition {
+    pub name: String,
+    pub time_zone: Option<String>,
+    /// The configs of the widgets, keyed by ident
+    pub widgets: Vec<(String, Value)>,
+    pub groups: Vec<GroupDefinition>,
+    pub teams: Vec<TeamDefinition>,
+    /// The idents of the assets stored for the game
+    pub assets: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupDefinition {
+    pub name: String,
+    pub attributes: Value,
+    pub overrides: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamDefinition {
+    pub name: String,
+    pub attributes: Value,
+    pub overrides: Value,
+    /// The name of the team's group
+    pub group: Option<String>,
+}
+
+impl GameDefinition {
+    /// Parses every part of the game and checks the widgets of every team,
+    /// returning a description of each problem found.
+    pub fn check(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut instances = IndexMap::new();
+
+        let zone = self.time_zone.as_deref().and_then(|name| {
+            let zone = Zone::named(name);
+
+            if zone.is_none() {
+                problems.push(format!("unknown time zone \"{name}\""));
+            }
+
+            zone
+        });
+
+        for (ident, config) in &self.widgets {
+            match Config::deserialize(config) {
+                Ok(config) => {
+                    instances.insert(ident.clone(), config.instance_default());
+                }
+                Err(error) => problems.push(format!("widget {ident}: {error}")),
+            }
+        }
+
+        let mut groups = HashMap::new();
+
+        for group in &self.groups {
+            let name = &group.name;
+
+            let parsed = attributes(name, &group.attributes)
+                .and_then(|attributes| Ok((attributes, self.overrides(&group.overrides)?)));
+
+            match parsed {
+                Ok(group) => {
+                    groups.insert(name, group);
+                }
+                Err(problem) => problems.push(format!("group {name}: {problem}")),
+            }
+        }
+
+        let mut teams = Vec::new();
+        let no_group = (Attributes::default(), Overrides::default());
+
+        for team in &self.teams {
+            let name = &team.name;
+
+            let (group, group_overrides) = match &team.group {
+                Some(group) => match groups.get(group) {
+                    Some(group) => group,
+                    None => {
+                        // Problems of groups which exist have already been reported
+                        if !self.groups.iter().any(|g| g.name == *group) {
+                            problems.push(format!("team {name}: unknown group \"{group}\""));
+                        }
+
+                        continue;
+                    }
+                },
+                None => &no_group,
+            };
+
+            let parsed = attributes(name, &team.attributes).and_then(|attributes| {
+                let overrides = self.overrides(&team.overrides)?;
+                let instances = self.team_instances(&[group_overrides, &overrides], &instances)?;
+
+                Ok((attributes, instances))
+            });
+
+            match parsed {
+                Ok((attributes, instances)) => {
+                    teams.push((Some(name), attributes, group.clone(), instances))
+                }
+                Err(problem) => problems.push(format!("team {name}: {problem}")),
+            }
+        }
+
+        if self.teams.is_empty() {
+            teams.push((
+                None,
+                Attributes::default(),
+                Attributes::default(),
+                instances,
+            ));
+        }
+
+        let mut seen = HashSet::new();
+
+        for (name, team, group, instances) in teams {
+            let state = GameState {
+                instances,
+                team,
+                group,
+                zone,
+            };
+
+            for error in state
+                .check()
+                .into_iter()
+                .chain(state.check_assets(&self.assets))
+            {
+                match (&error, name) {
+                    (CheckError::UnknownAttribute { .. }, None) => {}
+                    (CheckError::UnknownAttribute { .. }, Some(name)) => {
+                        problems.push(format!("team {name}: {error}"))
+                    }
+                    _ => {
+                        let error = error.to_string();
+
+                        if seen.insert(error.clone()) {
+                            problems.push(error);
+                        }
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Parses the overrides of a team or group, which may only change existing widgets.
+    fn overrides(&self, value: &Value) -> Result<Overrides, String> {
+        let overrides = Overrides::deserialize(value).map_err(|e| e.to_string())?;
+
+        for ident in overrides.widgets() {
+            if !self.widgets.iter().any(|(i, _)| i == ident) {
+                return Err(format!("override of unknown widget \"{ident}\""));
+            }
+        }
+
+        Ok(overrides)
+    }
+
+    /// Builds the widgets of a team with the overrides of each layer applied.
+    fn team_instances(
+        &self,
+        layers: &[&Overrides],
+        instances: &IndexMap<String, Instance>,
+    ) -> Result<IndexMap<String, Instance>, String> {
+        let mut instances = instances.clone();
+
+        for (ident, source) in &self.widgets {
+            if let Some(config) = Overrides::config(layers, ident, source) {
+                let config = config.map_err(|e| format!("widget {ident}: {e}"))?;
+                instances.insert(ident.clone(), config.instance_default());
+            }
+        }
+
+        Ok(instances)
+    }
+}
+
+fn attributes(name: &str, value: &Value) -> Result<Attributes, String> {
+    let mut attributes = Attributes::deserialize(value).map_err(|e| e.to_string())?;
+    attributes.name = name.to_owned();
+
+    Ok(attributes)
+}
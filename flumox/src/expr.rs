@@ -1,13 +1,21 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
-use serde::{Deserialize, Serialize};
-use time_expr::{eval, EvalError, Resolve, Value};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use time::OffsetDateTime;
+use time_expr::{parse, EvalError, Number, Resolve, Trace, Value, Zone};
 
-use crate::{error::EvalResult, game::GameState};
+use crate::{
+    attributes::{GROUP, TEAM},
+    error::EvalResult,
+    game::GameState,
+    score::{team_score, SCORE},
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct Expr(pub String);
+#[derive(Debug, Clone)]
+pub struct Expr {
+    source: String,
+    ast: time_expr::Expr,
+}
 
 impl Default for Expr {
     fn default() -> Self {
@@ -17,44 +25,151 @@ impl Default for Expr {
 
 impl Expr {
     pub fn never() -> Self {
-        Expr(String::from("never"))
+        Expr {
+            source: String::from("never"),
+            ast: time_expr::Expr::Literal {
+                value: Value::never(),
+            },
+        }
     }
 
     pub fn always() -> Self {
-        Expr(String::from("always"))
+        Expr {
+            source: String::from("always"),
+            ast: time_expr::Expr::Literal {
+                value: Value::always(),
+            },
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn ast(&self) -> &time_expr::Expr {
+        &self.ast
+    }
+
+    /// Parses the expression again without simplifying it,
+    /// so that operands which can't affect its value are kept.
+    pub fn unsimplified(&self) -> time_expr::Expr {
+        parse(&self.source).expect("source of a parsed expression should parse")
     }
 }
 
-impl<S> From<S> for Expr
-where
-    S: Into<String>,
-{
-    fn from(value: S) -> Self {
-        Expr(value.into())
+impl FromStr for Expr {
+    type Err = EvalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Expr {
+            source: s.to_owned(),
+            ast: parse(s)?.simplify(),
+        })
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+
+        Expr::from_str(&source).map_err(|error| {
+            de::Error::custom(format_args!(
+                "invalid expression: {}",
+                error.snippet(Some(&source))
+            ))
+        })
     }
 }
 
 #[derive(Debug)]
-enum EvaluationState {
+enum EvaluationState<T> {
     Evaluating,
-    Evaluated(Value),
+    Evaluated(T),
 }
 
 #[derive(Debug)]
 pub struct Environment<'a> {
-    cache: &'a mut HashMap<String, EvaluationState>,
+    cache: &'a mut Cache,
     pub this: Option<&'a str>,
     pub game: &'a GameState,
+    pub time: OffsetDateTime,
+    traces: Option<&'a mut Vec<Trace>>,
 }
 
 #[derive(Debug, Default)]
-pub struct Cache(HashMap<String, EvaluationState>);
+pub struct Cache {
+    values: HashMap<String, EvaluationState<Value>>,
+    numbers: HashMap<String, EvaluationState<Number>>,
+    /// When values depending on the current time, like `today 18:00`, stop being valid
+    valid_until: Option<OffsetDateTime>,
+}
+
+impl Cache {
+    /// Returns when some of the cached values stop being valid, even though no state changed.
+    pub fn valid_until(&self) -> Option<OffsetDateTime> {
+        self.valid_until
+    }
+}
 
 impl<'a> Resolve for Environment<'a> {
     fn resolve(&mut self, path: &[&str]) -> EvalResult {
         let Some((module, subpath)) = path.split_first() else {
             return Err(EvalError::UnknownPath {
                 path: String::new().into(),
+                span: None,
+            });
+        };
+
+        let module = match (module, self.this) {
+            (&"this", Some(this)) => this,
+            (other, _) => other,
+        };
+
+        self.resolve_cached(module, subpath, None)
+    }
+
+    fn resolve_traced(&mut self, path: &[&str]) -> Trace {
+        let Some((module, subpath)) = path.split_first() else {
+            return Trace::path(
+                "",
+                Err(EvalError::UnknownPath {
+                    path: String::new().into(),
+                    span: None,
+                }),
+            );
+        };
+
+        let module = match (module, self.this) {
+            (&"this", Some(this)) => this,
+            (other, _) => other,
+        };
+
+        let mut children = Vec::new();
+        let result = self.resolve_cached(module, subpath, Some(&mut children));
+
+        Trace {
+            children,
+            ..Trace::path(Environment::path_to_string(module, subpath), result)
+        }
+    }
+
+    fn resolve_number(&mut self, path: &[&str]) -> Result<Number, EvalError> {
+        let Some((module, subpath)) = path.split_first() else {
+            return Err(EvalError::UnknownPath {
+                path: String::new().into(),
+                span: None,
             });
         };
 
@@ -63,26 +178,74 @@ impl<'a> Resolve for Environment<'a> {
             (other, _) => other,
         };
 
-        self.resolve_cached(module, subpath)
+        let key = Environment::path_to_string(module, subpath);
+
+        match self.cache.numbers.get(&key) {
+            Some(EvaluationState::Evaluating) => Err(EvalError::CircularDependency {
+                path: key.into(),
+                span: None,
+            }),
+            Some(EvaluationState::Evaluated(number)) => Ok(number.clone()),
+            None => {
+                self.cache
+                    .numbers
+                    .insert(key.clone(), EvaluationState::Evaluating);
+
+                let result = self.resolve_number_raw(module, subpath);
+
+                match &result {
+                    Ok(number) => self
+                        .cache
+                        .numbers
+                        .insert(key, EvaluationState::Evaluated(number.clone())),
+                    Err(_) => self.cache.numbers.remove(&key),
+                };
+
+                result
+            }
+        }
+    }
+
+    fn default_zone(&self) -> Option<Zone> {
+        self.game.zone
+    }
+
+    fn now(&self) -> Option<OffsetDateTime> {
+        Some(self.time)
+    }
+
+    fn expires(&mut self, time: OffsetDateTime) {
+        let valid_until = &mut self.cache.valid_until;
+        *valid_until = Some(valid_until.map_or(time, |t| t.min(time)));
     }
 }
 
 impl<'a> Environment<'a> {
-    fn resolve_cached(&mut self, module: &str, path: &[&str]) -> EvalResult {
+    fn resolve_cached(
+        &mut self,
+        module: &str,
+        path: &[&str],
+        traces: Option<&mut Vec<Trace>>,
+    ) -> EvalResult {
         let key = Environment::path_to_string(module, path);
 
-        match self.cache.get(&key) {
-            Some(EvaluationState::Evaluating) => {
-                Err(EvalError::CircularDependency { path: key.into() })
-            }
-            Some(EvaluationState::Evaluated(value)) => Ok(*value),
+        match self.cache.values.get(&key) {
+            Some(EvaluationState::Evaluating) => Err(EvalError::CircularDependency {
+                path: key.into(),
+                span: None,
+            }),
+            Some(EvaluationState::Evaluated(value)) => Ok(value.clone()),
             None => {
-                self.cache.insert(key.clone(), EvaluationState::Evaluating);
+                self.cache
+                    .values
+                    .insert(key.clone(), EvaluationState::Evaluating);
 
-                let result = self.resolve_raw(module, path, &key);
+                let result = self.resolve_raw(module, path, &key, traces);
 
-                if let Ok(value) = result {
-                    self.cache.insert(key, EvaluationState::Evaluated(value));
+                if let Ok(value) = &result {
+                    self.cache
+                        .values
+                        .insert(key, EvaluationState::Evaluated(value.clone()));
                 }
 
                 result
@@ -90,34 +253,102 @@ impl<'a> Environment<'a> {
         }
     }
 
-    fn resolve_raw(&mut self, module: &str, path: &[&str], path_str: &str) -> EvalResult {
+    fn resolve_raw(
+        &mut self,
+        module: &str,
+        path: &[&str],
+        path_str: &str,
+        traces: Option<&mut Vec<Trace>>,
+    ) -> EvalResult {
         let env = Environment {
             cache: self.cache,
             this: Some(module),
             game: self.game,
+            time: self.time,
+            traces,
         };
 
-        if module == "team" {
-            self.game.team.resolve(path, env)
-        } else {
-            let instance = self
-                .game
-                .instances
-                .get(module)
-                .ok_or(EvalError::UnknownPath {
-                    path: path_str.into(),
-                })?;
-
-            instance.resolve(path, env)
+        match module {
+            TEAM => self.game.team.resolve(path, env),
+            GROUP => self.game.group.resolve(path, env),
+            _ => {
+                let instance = self
+                    .game
+                    .instances
+                    .get(module)
+                    .ok_or(EvalError::UnknownPath {
+                        path: path_str.into(),
+                        span: None,
+                    })?;
+
+                instance.resolve(path, env)
+            }
+        }
+    }
+
+    fn resolve_number_raw(&mut self, module: &str, path: &[&str]) -> Result<Number, EvalError> {
+        let env = Environment {
+            cache: self.cache,
+            this: Some(module),
+            game: self.game,
+            time: self.time,
+            traces: None,
+        };
+
+        match (module, path) {
+            (TEAM, [SCORE]) => team_score(env),
+            (TEAM, _) => self.game.team.resolve_number(path, env),
+            (GROUP, _) => self.game.group.resolve_number(path, env),
+            _ => {
+                let instance = self
+                    .game
+                    .instances
+                    .get(module)
+                    .ok_or_else(|| env.unknown_path(path))?;
+
+                instance.resolve_number(path, env)
+            }
         }
     }
 
     pub fn own(&mut self, path: &[&str]) -> EvalResult {
-        self.resolve_cached(self.this.unwrap_or("this"), path)
+        self.resolve_cached(self.this.unwrap_or("this"), path, None)
     }
 
     pub fn eval(&mut self, expr: &Expr) -> EvalResult {
-        eval(&expr.0, self)
+        // Taken out for the duration, since the environment also resolves the paths
+        let mut traces = self.traces.take();
+        let result = expr.ast.eval_traced(self, traces.as_deref_mut());
+        self.traces = traces;
+
+        result.map_err(|error| self.locate(error, expr))
+    }
+
+    /// Attaches the source of an expression and the field it belongs to to an error.
+    fn locate(&self, error: EvalError, expr: &Expr) -> EvalError {
+        let Some(module) = self.this else {
+            return error;
+        };
+
+        let fields = match module {
+            TEAM => Some(self.game.team.fields()),
+            GROUP => Some(self.game.group.fields()),
+            _ => self.game.instances.get(module).map(|i| i.fields()),
+        };
+
+        let field = fields.into_iter().flatten().find(|f| {
+            f.expr
+                .is_some_and(|field_expr| std::ptr::eq(field_expr, expr))
+        });
+
+        error.in_expr(module, field.map(|f| f.name).as_deref(), &expr.source)
+    }
+
+    /// Resolves a path such as `first.hint.spoiler.available`,
+    /// recording every expression evaluated along the way.
+    pub fn explain(&mut self, path: &str) -> Trace {
+        let path: Vec<&str> = path.split('.').collect();
+        self.resolve_traced(&path)
     }
 
     fn path_to_string(module: &str, subpath: &[&str]) -> String {
@@ -134,14 +365,39 @@ impl<'a> Environment<'a> {
     pub fn unknown_path(&self, path: &[&str]) -> EvalError {
         EvalError::UnknownPath {
             path: Environment::path_to_string(self.this.unwrap_or("this"), path).into(),
+            span: None,
         }
     }
 
-    pub fn new(game: &'a GameState, cache: &'a mut Cache, this: &'a str) -> Self {
+    pub fn new(
+        game: &'a GameState,
+        cache: &'a mut Cache,
+        this: &'a str,
+        time: OffsetDateTime,
+    ) -> Self {
         Environment {
-            cache: &mut cache.0,
+            cache,
             this: Some(this),
             game,
+            time,
+            traces: None,
         }
     }
 }
+
+impl GameState {
+    /// Explains the value of a path such as `first.hint.spoiler.available` at a given time.
+    pub fn explain(&self, path: &str, time: OffsetDateTime) -> Trace {
+        let mut cache = Cache::default();
+
+        let mut env = Environment {
+            cache: &mut cache,
+            this: None,
+            game: self,
+            time,
+            traces: None,
+        };
+
+        env.explain(path)
+    }
+}
@@ -1,8 +1,27 @@
-use crate::{attributes::Attributes, widget::Instance};
+use crate::{
+    attributes::{Attributes, GROUP, TEAM},
+    widget::Instance,
+};
 use indexmap::IndexMap;
+use time_expr::Zone;
 
 #[derive(Debug, Clone, Default)]
 pub struct GameState {
     pub instances: IndexMap<String, Instance>,
     pub team: Attributes,
+    /// The attributes of the team's group, which are empty if it isn't in one
+    pub group: Attributes,
+    /// The time zone of time literals which don't specify one
+    pub zone: Option<Zone>,
+}
+
+impl GameState {
+    /// Returns the attribute at a path like `team.start` as it should be inserted into text.
+    pub fn text(&self, path: &str) -> Option<String> {
+        match path.split_once('.')? {
+            (TEAM, name) => self.team.text(name),
+            (GROUP, name) => self.group.text(name),
+            _ => None,
+        }
+    }
 }
@@ -1,18 +1,31 @@
 mod action;
 mod attributes;
+mod check;
+mod definition;
 mod error;
 mod expr;
 mod game;
+mod markup;
+mod overrides;
+mod score;
 mod solution;
+mod template;
 mod text;
 mod toast;
 mod view_context;
 pub mod widget;
 
 pub use action::{Action, ActionContext, ActionEffect};
+pub use attributes::Attributes;
+pub use check::{CheckError, Dependency, Field};
+pub use definition::{GameDefinition, GroupDefinition, TeamDefinition};
 pub use error::{ActionError, EvalResult, StateMismatchError};
-pub use expr::{Cache, Environment};
+pub use expr::{Cache, Environment, Expr};
 pub use game::GameState;
+pub use overrides::{OverrideError, Overrides};
 pub use toast::{Toast, ToastType};
 pub use view_context::{TimeTracker, ViewContext};
 pub use widget::{Config, Instance, State, View};
+
+#[cfg(test)]
+mod test;
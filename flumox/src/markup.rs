@@ -0,0 +1,321 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::text::Block;
+
+/// Text written in a subset of Markdown, which is kept along with its source.
+///
+/// Paragraphs are separated by blank lines, and lines starting with `- `, `* ` or `1. ` form lists.
+/// Within them, `**bold**`, `*italic*`, `` `code` `` and `[links](https://example.com)` are supported.
+/// Anything else, including HTML, is shown as is.
+#[derive(Debug, Clone)]
+pub struct Markdown {
+    source: String,
+    blocks: Vec<Block>,
+}
+
+/// A piece of formatted text within a paragraph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum Span {
+    Text { text: String },
+    Bold { content: Vec<Span> },
+    Italic { content: Vec<Span> },
+    Code { text: String },
+    Link { href: String, content: Vec<Span> },
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum MarkupError {
+    #[error("link to \"{0}\" must use http, https or mailto")]
+    UnsafeLink(String),
+}
+
+const SAFE_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+impl Markdown {
+    pub fn parse(source: String) -> Result<Self, MarkupError> {
+        let blocks = parse_blocks(&source)?;
+        Ok(Markdown { source, blocks })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the text as a single line, with paragraphs and list items separated by spaces.
+    pub fn spans(&self) -> Vec<Span> {
+        let mut spans = Vec::new();
+
+        for block in &self.blocks {
+            let lines = match block {
+                Block::Rich { content } => std::slice::from_ref(content),
+                Block::List { items, .. } => items.as_slice(),
+                _ => &[],
+            };
+
+            for line in lines {
+                if !spans.is_empty() {
+                    push_text(&mut spans, " ");
+                }
+
+                for span in line {
+                    match span {
+                        Span::Text { text } => push_text(&mut spans, text),
+                        span => spans.push(span.clone()),
+                    }
+                }
+            }
+        }
+
+        spans
+    }
+}
+
+fn parse_blocks(source: &str) -> Result<Vec<Block>, MarkupError> {
+    let mut blocks = Vec::new();
+    let mut lines = Vec::new();
+
+    for line in source.lines().chain([""]) {
+        let line = line.trim();
+
+        if !line.is_empty() {
+            lines.push(line);
+        } else if !lines.is_empty() {
+            blocks.push(parse_block(&lines)?);
+            lines.clear();
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Splits a line into whether it's an item of an ordered list and its text.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some((false, text));
+    }
+
+    let number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if number.len() < line.len() {
+        if let Some(text) = number.strip_prefix(". ") {
+            return Some((true, text));
+        }
+    }
+
+    None
+}
+
+fn parse_block(lines: &[&str]) -> Result<Block, MarkupError> {
+    let Some((ordered, _)) = list_item(lines[0]) else {
+        return Ok(Block::Rich {
+            content: parse_spans(&lines.join(" "))?,
+        });
+    };
+
+    let mut items: Vec<String> = Vec::new();
+
+    for line in lines {
+        match (list_item(line), items.last_mut()) {
+            (Some((_, text)), _) => items.push(text.to_owned()),
+            // Lines which don't start a new item continue the last one
+            (None, Some(item)) => {
+                item.push(' ');
+                item.push_str(line);
+            }
+            (None, None) => unreachable!("the first line is always an item"),
+        }
+    }
+
+    Ok(Block::List {
+        ordered,
+        items: items
+            .iter()
+            .map(|item| parse_spans(item))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_spans(text: &str) -> Result<Vec<Span>, MarkupError> {
+    let chars: Vec<char> = text.chars().collect();
+    parse_chars(&chars)
+}
+
+fn parse_chars(chars: &[char]) -> Result<Vec<Span>, MarkupError> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let previous = i.checked_sub(1).map(|p| chars[p]);
+
+        if let Some((span, length)) = span_at(&chars[i..], previous)? {
+            spans.push(span);
+            i += length;
+        } else if let ['\\', c, ..] = chars[i..] {
+            if is_special(c) {
+                push_text(&mut spans, &c.to_string());
+                i += 2;
+            } else {
+                push_text(&mut spans, "\\");
+                i += 1;
+            }
+        } else {
+            push_text(&mut spans, &chars[i].to_string());
+            i += 1;
+        }
+    }
+
+    Ok(spans)
+}
+
+/// Parses a formatted span at the start of `rest`, returning it and the number of characters it spans.
+fn span_at(rest: &[char], previous: Option<char>) -> Result<Option<(Span, usize)>, MarkupError> {
+    match rest {
+        ['`', ..] => Ok(rest[1..].iter().position(|&c| c == '`').map(|end| {
+            let text = rest[1..end + 1].iter().collect();
+            (Span::Code { text }, end + 2)
+        })),
+        ['*', '*', ..] | ['_', '_', ..] => emphasis(rest, previous, 2)
+            .map(|(inner, length)| {
+                Ok((
+                    Span::Bold {
+                        content: parse_chars(inner)?,
+                    },
+                    length,
+                ))
+            })
+            .transpose(),
+        ['*', ..] | ['_', ..] => emphasis(rest, previous, 1)
+            .map(|(inner, length)| {
+                Ok((
+                    Span::Italic {
+                        content: parse_chars(inner)?,
+                    },
+                    length,
+                ))
+            })
+            .transpose(),
+        ['[', ..] => link(rest),
+        _ => Ok(None),
+    }
+}
+
+/// Finds the text between a delimiter of `width` characters at the start of `rest` and its match.
+fn emphasis(rest: &[char], previous: Option<char>, width: usize) -> Option<(&[char], usize)> {
+    let delimiter = rest[0];
+
+    // Underscores within words, such as in snake_case, don't count
+    let word = |c: Option<&char>| delimiter == '_' && c.is_some_and(|c| c.is_alphanumeric());
+
+    if word(previous.as_ref()) || rest.get(width).map_or(true, |c| c.is_whitespace()) {
+        return None;
+    }
+
+    let mut i = width;
+
+    while i < rest.len() {
+        let run = rest[i..].iter().take_while(|&&c| c == delimiter).count();
+
+        if run == 0 {
+            i += match rest[i] {
+                '\\' => 2,
+                '`' => rest[i + 1..]
+                    .iter()
+                    .position(|&c| c == '`')
+                    .map_or(1, |end| end + 2),
+                _ => 1,
+            };
+        } else if run == width
+            && i > width
+            && !rest[i - 1].is_whitespace()
+            && !word(rest.get(i + width))
+        {
+            return Some((&rest[width..i], i + width));
+        } else {
+            i += run;
+        }
+    }
+
+    None
+}
+
+fn link(rest: &[char]) -> Result<Option<(Span, usize)>, MarkupError> {
+    let Some(close) = unescaped(rest, 1, ']') else {
+        return Ok(None);
+    };
+
+    if rest.get(close + 1) != Some(&'(') {
+        return Ok(None);
+    }
+
+    let Some(end) = unescaped(rest, close + 2, ')') else {
+        return Ok(None);
+    };
+
+    let href: String = rest[close + 2..end].iter().collect();
+    let href = href.trim().to_owned();
+
+    let lowercase = href.to_ascii_lowercase();
+
+    if !SAFE_SCHEMES.iter().any(|s| lowercase.starts_with(s)) {
+        return Err(MarkupError::UnsafeLink(href));
+    }
+
+    let content = parse_chars(&rest[1..close])?;
+
+    Ok(Some((Span::Link { href, content }, end + 1)))
+}
+
+/// Finds the first `target` at or after `start` which isn't escaped.
+fn unescaped(chars: &[char], start: usize, target: char) -> Option<usize> {
+    let mut i = start;
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c == target => return Some(i),
+            '\\' => i += 2,
+            _ => i += 1,
+        }
+    }
+
+    None
+}
+
+fn is_special(c: char) -> bool {
+    matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '(' | ')')
+}
+
+fn push_text(spans: &mut Vec<Span>, text: &str) {
+    match spans.last_mut() {
+        Some(Span::Text { text: last }) => last.push_str(text),
+        _ => spans.push(Span::Text {
+            text: text.to_owned(),
+        }),
+    }
+}
+
+impl Serialize for Markdown {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Markdown {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+
+        Markdown::parse(source)
+            .map_err(|error| de::Error::custom(format_args!("invalid markdown: {error}")))
+    }
+}
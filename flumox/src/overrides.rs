@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::Config;
+
+/// Changes to the configs of widgets for a single team, keyed by widget ident.
+///
+/// Each override is merged into the widget's config as a JSON merge patch:
+/// objects are merged recursively, `null` removes a field and any other value replaces it.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "HashMap<String, Value>")]
+pub struct Overrides {
+    widgets: HashMap<String, Map<String, Value>>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum OverrideError {
+    #[error("override of widget \"{ident}\" must be an object")]
+    NotAnObject { ident: String },
+    #[error("override of widget \"{ident}\" can't change its type")]
+    TypeChanged { ident: String },
+}
+
+impl Overrides {
+    /// Lists the idents of the overridden widgets.
+    pub fn widgets(&self) -> impl Iterator<Item = &str> {
+        self.widgets.keys().map(String::as_str)
+    }
+
+    /// Builds the config of a widget with the overrides of each layer applied in order,
+    /// or returns `None` if none of them change it.
+    pub fn config(
+        layers: &[&Overrides],
+        ident: &str,
+        source: &Value,
+    ) -> Option<Result<Config, serde_json::Error>> {
+        let patches: Vec<_> = layers.iter().filter_map(|o| o.widgets.get(ident)).collect();
+
+        if patches.is_empty() {
+            return None;
+        }
+
+        let mut config = source.clone();
+
+        for patch in patches {
+            merge_object(&mut config, patch);
+        }
+
+        Some(serde_json::from_value(config))
+    }
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => merge_object(target, patch),
+        _ => *target = patch.clone(),
+    }
+}
+
+pub(crate) fn merge_object(target: &mut Value, patch: &Map<String, Value>) {
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(key);
+            }
+            _ => merge(target.entry(key.clone()).or_insert(Value::Null), value),
+        }
+    }
+}
+
+impl TryFrom<HashMap<String, Value>> for Overrides {
+    type Error = OverrideError;
+
+    fn try_from(value: HashMap<String, Value>) -> Result<Self, Self::Error> {
+        let mut widgets = HashMap::new();
+
+        for (ident, patch) in value {
+            let Value::Object(patch) = patch else {
+                return Err(OverrideError::NotAnObject { ident });
+            };
+
+            if patch.contains_key("type") {
+                return Err(OverrideError::TypeChanged { ident });
+            }
+
+            widgets.insert(ident, patch);
+        }
+
+        Ok(Overrides { widgets })
+    }
+}
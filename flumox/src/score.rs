@@ -0,0 +1,56 @@
+use time::OffsetDateTime;
+use time_expr::{EvalError, Number, Resolve};
+
+use crate::{
+    attributes::TEAM,
+    expr::{Cache, Environment},
+    game::GameState,
+    widget::Instance,
+};
+
+/// The name of the number field holding the points a widget has awarded to the team.
+pub(crate) const POINTS: &str = "points";
+
+/// The name of the team's number field holding its total score.
+pub(crate) const SCORE: &str = "score";
+
+impl Instance {
+    /// Whether the widget can award points to the team, which only depends on its type.
+    pub fn is_scored(&self) -> bool {
+        matches!(
+            self,
+            Instance::Prompt(..) | Instance::Choice(..) | Instance::Checkpoint(..)
+        )
+    }
+}
+
+impl GameState {
+    /// Lists the idents of widgets which count towards the team's score.
+    pub(crate) fn scored(&self) -> impl Iterator<Item = &str> {
+        self.instances
+            .iter()
+            .filter(|(_, instance)| instance.is_scored())
+            .map(|(ident, _)| ident.as_str())
+    }
+
+    /// Computes how the team's score changes over the course of the game.
+    pub fn score(&self, time: OffsetDateTime) -> Result<Number, EvalError> {
+        let mut cache = Cache::default();
+        let mut env = Environment::new(self, &mut cache, TEAM, time);
+
+        env.resolve_number(&["this", SCORE])
+    }
+}
+
+/// Sums the points awarded by every widget.
+pub(crate) fn team_score(mut env: Environment) -> Result<Number, EvalError> {
+    let game = env.game;
+
+    let points = game
+        .scored()
+        .map(|ident| env.resolve_number(&[ident, POINTS]))
+        .collect::<Result<Vec<_>, _>>()?;
+    let points: Vec<&Number> = points.iter().collect();
+
+    Ok(Number::sum(&points))
+}
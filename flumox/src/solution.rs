@@ -1,4 +1,6 @@
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 use unicode_normalization::UnicodeNormalization;
 
 use std::str::FromStr;
@@ -6,11 +8,66 @@ use std::str::FromStr;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum Solution {
-    Alphanumeric { solution: String },
-    Number { solution: i32 },
+    Alphanumeric {
+        solution: String,
+        /// How many letters may be missing, extra, wrong or swapped
+        #[serde(default)]
+        max_typos: usize,
+        #[serde(default)]
+        on_typo: TypoAction,
+    },
+    Number {
+        solution: i32,
+    },
+    /// A decimal number, such as a coordinate, which may be off by up to `tolerance`
+    Decimal {
+        solution: f64,
+        #[serde(default)]
+        tolerance: Tolerance,
+    },
+    /// Words which may be given in any order
+    Words {
+        words: Vec<String>,
+    },
+    /// Text which must match including case and diacritics
+    Exact {
+        solution: String,
+    },
+    /// Text which must fully match a regular expression
+    Regex {
+        pattern: Pattern,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        canonical: Option<String>,
+    },
     Any,
 }
 
+/// What to do with an answer which is only a few typos away from the solution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypoAction {
+    #[default]
+    Accept,
+    /// Reject the answer, but tell the team to check its spelling
+    Warn,
+}
+
+/// How far off a decimal answer may be, which is never negative.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct Tolerance(f64);
+
+#[derive(Debug, Clone, Error)]
+#[error("tolerance must be a finite number which isn't negative")]
+pub struct ToleranceError;
+
+/// A regular expression, which is kept along with its source.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
 fn normalize(s: &str) -> impl Iterator<Item = char> + '_ {
     s.nfkd()
         .filter(|c| c.is_alphanumeric())
@@ -21,6 +78,47 @@ fn fuzzy_equal(a: &str, b: &str) -> bool {
     Iterator::eq(normalize(a), normalize(b))
 }
 
+/// Counts the insertions, deletions, substitutions and swaps of adjacent letters
+/// needed to turn `a` into `b`, giving up once it's more than `max`.
+pub(crate) fn typo_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = normalize(a).collect();
+    let b: Vec<char> = normalize(b).collect();
+
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = Vec::new();
+    let mut current: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let before = std::mem::replace(&mut previous, current.clone());
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            let mut distance = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(before[j - 2] + 1);
+            }
+
+            current[j] = distance;
+        }
+
+        // The smallest value in a row never decreases, so once a whole row is over
+        // the limit, the distance can't come back under it
+        if current.iter().min().is_some_and(|&d| d > max) {
+            return None;
+        }
+    }
+
+    Some(current[b.len()]).filter(|&d| d <= max)
+}
+
 fn numberic_equal(a: &str, b: i32) -> bool {
     let a: String = a
         .nfkc()
@@ -34,20 +132,147 @@ fn numberic_equal(a: &str, b: i32) -> bool {
     }
 }
 
+pub(crate) fn decimal_equal(a: &str, b: f64, tolerance: f64) -> bool {
+    let a: String = a
+        .nfkc()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '.' | ','))
+        .map(|c| if c == ',' { '.' } else { c })
+        .collect();
+
+    if let Ok(a) = f64::from_str(&a) {
+        (a - b).abs() <= tolerance
+    } else {
+        false
+    }
+}
+
+pub(crate) fn words(s: &str) -> Vec<String> {
+    let mut words: Vec<String> = s
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|w| normalize(w).collect::<String>())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    words.sort();
+    words
+}
+
+pub(crate) fn exact_equal(a: &str, b: &str) -> bool {
+    let a: String = a.nfc().collect();
+    let b: String = b.nfc().collect();
+
+    Iterator::eq(a.split_whitespace(), b.split_whitespace())
+}
+
 impl Solution {
+    /// Whether the answer is accepted, allowing for typos if the solution accepts them.
     pub fn check(&self, ans: &str) -> bool {
+        self.check_exact(ans) || self.check_typos(ans)
+    }
+
+    /// Whether the answer is accepted without any typos.
+    pub fn check_exact(&self, ans: &str) -> bool {
         match self {
-            Solution::Alphanumeric { solution } => fuzzy_equal(ans, solution),
+            Solution::Alphanumeric { solution, .. } => fuzzy_equal(ans, solution),
             Solution::Number { solution } => numberic_equal(ans, *solution),
+            Solution::Decimal {
+                solution,
+                tolerance,
+            } => decimal_equal(ans, *solution, tolerance.0),
+            Solution::Words { words: solution } => words(ans) == words(&solution.join(" ")),
+            Solution::Exact { solution } => exact_equal(ans, solution),
+            Solution::Regex { pattern, .. } => pattern.regex.is_match(ans.trim()),
             Solution::Any => true,
         }
     }
 
+    fn check_typos(&self, ans: &str) -> bool {
+        match self {
+            Solution::Alphanumeric {
+                solution,
+                max_typos,
+                on_typo: TypoAction::Accept,
+            } => *max_typos > 0 && typo_distance(ans, solution, *max_typos).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether the answer is rejected, but close enough that the team should check its spelling.
+    pub fn close(&self, ans: &str) -> bool {
+        match self {
+            Solution::Alphanumeric {
+                solution,
+                max_typos,
+                on_typo: TypoAction::Warn,
+            } => {
+                *max_typos > 0
+                    && !fuzzy_equal(ans, solution)
+                    && typo_distance(ans, solution, *max_typos).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the first of `solutions` which accepts the answer, allowing for typos if `typos`.
+    pub fn find<'a>(solutions: &'a [Solution], ans: &str, typos: bool) -> Option<&'a Solution> {
+        solutions.iter().find(|s| {
+            if typos {
+                s.check(ans)
+            } else {
+                s.check_exact(ans)
+            }
+        })
+    }
+
     pub fn canonical_answer(&self) -> Option<String> {
         match self {
-            Solution::Alphanumeric { solution } => Some(solution.to_owned()),
+            Solution::Alphanumeric { solution, .. } => Some(solution.to_owned()),
             Solution::Number { solution } => Some(solution.to_string()),
+            Solution::Decimal { solution, .. } => Some(solution.to_string()),
+            Solution::Words { words } => Some(words.join(" ")),
+            Solution::Exact { solution } => Some(solution.to_owned()),
+            Solution::Regex { canonical, .. } => canonical.clone(),
             Solution::Any => None,
         }
     }
 }
+
+impl TryFrom<f64> for Tolerance {
+    type Error = ToleranceError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if value.is_finite() && value >= 0.0 {
+            Ok(Tolerance(value))
+        } else {
+            Err(ToleranceError)
+        }
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+
+        let invalid = |error| de::Error::custom(format_args!("invalid pattern: {error}"));
+
+        // Checked on its own first, so that errors point into the original pattern
+        Regex::new(&source).map_err(invalid)?;
+
+        // The whole answer has to match, not just a part of it
+        let regex = Regex::new(&format!("^(?:{source})$")).map_err(invalid)?;
+
+        Ok(Pattern { source, regex })
+    }
+}
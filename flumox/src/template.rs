@@ -0,0 +1,57 @@
+use crate::attributes::{GROUP, TEAM};
+
+/// The modules whose attributes can be inserted into text, such as `{team.start}`.
+const MODULES: [&str; 2] = [TEAM, GROUP];
+
+/// Finds the placeholders in `text`, returning their byte ranges and paths, such as `team.start`.
+pub(crate) fn placeholders(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+
+    std::iter::from_fn(move || {
+        while let Some(found) = text[offset..].find('{') {
+            let start = offset + found;
+            offset = start + 1;
+
+            let length = text[offset..].find('}')?;
+
+            let path = &text[offset..offset + length];
+
+            let Some((module, name)) = path.split_once('.') else {
+                continue;
+            };
+
+            let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+
+            if MODULES.contains(&module) && !name.is_empty() && name.chars().all(valid) {
+                offset += length + 1;
+                return Some((start, offset, path));
+            }
+        }
+
+        None
+    })
+}
+
+/// Lists the paths of the attributes inserted into `text`.
+pub(crate) fn variables(text: &str) -> impl Iterator<Item = &str> {
+    placeholders(text).map(|(_, _, path)| path)
+}
+
+/// Replaces every placeholder like `{team.<name>}` in `text` by the value of that attribute.
+///
+/// Placeholders naming unknown attributes are kept as they are.
+pub(crate) fn substitute(text: &str, mut value: impl FnMut(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for (start, end, path) in placeholders(text) {
+        if let Some(value) = value(path) {
+            result.push_str(&text[last..start]);
+            result.push_str(&value);
+            last = end;
+        }
+    }
+
+    result.push_str(&text[last..]);
+    result
+}
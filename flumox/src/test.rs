@@ -0,0 +1,1531 @@
+use serde::Deserialize;
+use serde_json::{json, Value as Json};
+use time::{macros::datetime, Duration, OffsetDateTime};
+use time_expr::{Value, Zone};
+
+use crate::{
+    markup::Markdown,
+    overrides::merge_object,
+    solution::{decimal_equal, exact_equal, typo_distance, words, Solution, Tolerance},
+    template::{placeholders, substitute},
+    toast::Message,
+    widget::checkpoint::distance,
+    Action, ActionContext, ActionError, Attributes, Cache, Config, Environment, Expr,
+    GameDefinition, GameState, GroupDefinition, Instance, Overrides, State, TeamDefinition,
+    TimeTracker, View, ViewContext,
+};
+
+const START: OffsetDateTime = datetime!(2024-06-01 12:00 +0);
+
+/// Fills in the fields every widget of its type needs, unless `config` sets them.
+///
+/// Prompts are solved by "Gold", questions by choosing "green" out of three colors and
+/// checkpoints are 50 meters around 50°N 14°E.
+fn widget(config: Json) -> Json {
+    let mut widget = json!({ "name": "Widget", "details": [], "visible": "always" });
+
+    let required = match config["type"].as_str() {
+        Some("prompt") => json!({
+            "prompt": "Answer",
+            "solutions": [{ "type": "alphanumeric", "solution": "Gold" }],
+        }),
+        Some("choice") => json!({
+            "question": "Which?",
+            "options": [
+                { "ident": "red", "text": "Red" },
+                { "ident": "green", "text": "Green" },
+                { "ident": "blue", "text": "Blue" },
+            ],
+            "correct": ["green"],
+        }),
+        Some("checkpoint") => json!({ "latitude": 50.0, "longitude": 14.0, "radius": 50.0 }),
+        _ => json!({}),
+    };
+
+    for fields in [required, config] {
+        widget
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+    }
+
+    widget
+}
+
+/// Returns the error of loading a widget config filled in by [`widget`].
+fn load_error(config: Json) -> String {
+    Config::deserialize(widget(config)).unwrap_err().to_string()
+}
+
+/// A game played by a single team, which keeps the state of its widgets in memory.
+struct TestGame(GameState);
+
+impl TestGame {
+    /// Builds a game out of widget configs keyed by ident, filled in by [`widget`].
+    fn new(widgets: Json) -> Self {
+        let mut game = GameState::default();
+
+        for (ident, config) in widgets.as_object().unwrap() {
+            let config = Config::deserialize(widget(config.clone())).unwrap();
+            game.instances
+                .insert(ident.clone(), config.instance_default());
+        }
+
+        TestGame(game)
+    }
+
+    /// Submits an action to a widget, keeping its new state, and returns the toast message.
+    fn submit(
+        &mut self,
+        ident: &str,
+        action: Json,
+        time: OffsetDateTime,
+    ) -> Result<Option<Message>, ActionError> {
+        let action = Action::deserialize(action).unwrap();
+        let instance = &self.0.instances[ident];
+
+        let mut cache = Cache::default();
+        let env = Environment::new(&self.0, &mut cache, ident, time);
+        let effect = instance.submit(&action, ActionContext { env, time })?;
+
+        if let Some(state) = effect.new_state {
+            let instance = match (instance.clone(), state) {
+                (Instance::Prompt(c, _), State::Prompt(s)) => Instance::Prompt(c, s),
+                (Instance::Choice(c, _), State::Choice(s)) => Instance::Choice(c, s),
+                (Instance::Checkpoint(c, _), State::Checkpoint(s)) => Instance::Checkpoint(c, s),
+                _ => panic!("widget {ident} changed its type"),
+            };
+
+            self.0.instances.insert(ident.to_owned(), instance);
+        }
+
+        Ok(effect.toast.map(|t| t.message))
+    }
+
+    fn answer(&mut self, ident: &str, answer: &str, time: OffsetDateTime) -> Message {
+        let action = json!({ "type": "answer", "answer": answer });
+        self.submit(ident, action, time).unwrap().unwrap()
+    }
+
+    fn choose(
+        &mut self,
+        ident: &str,
+        selected: &[&str],
+        time: OffsetDateTime,
+    ) -> Result<Option<Message>, ActionError> {
+        let action = json!({ "type": "choice", "selected": selected });
+        self.submit(ident, action, time)
+    }
+
+    /// Checks in north of the checkpoint.
+    fn check_in(
+        &mut self,
+        ident: &str,
+        latitude: f64,
+        accuracy: f64,
+    ) -> Result<Option<Message>, ActionError> {
+        let action = json!({
+            "type": "check-in",
+            "latitude": latitude,
+            "longitude": 14.0,
+            "accuracy": accuracy,
+        });
+
+        self.submit(ident, action, START)
+    }
+
+    fn eval(&self, expr: &str, time: OffsetDateTime) -> Value {
+        let expr: Expr = expr.parse().unwrap();
+        let mut cache = Cache::default();
+
+        Environment::new(&self.0, &mut cache, "", time)
+            .eval(&expr)
+            .unwrap()
+    }
+
+    /// Checks the game, describing each problem found.
+    fn check(&self) -> Vec<String> {
+        self.0.check().iter().map(ToString::to_string).collect()
+    }
+
+    /// Renders a widget as it's sent to the team.
+    fn render(&self, ident: &str, time: OffsetDateTime) -> Option<View> {
+        let mut cache = Cache::default();
+        let mut tracker = TimeTracker::new(time);
+        let env = Environment::new(&self.0, &mut cache, ident, time);
+
+        self.0.instances[ident]
+            .view(ViewContext::new(env, &mut tracker))
+            .unwrap()
+    }
+
+    fn view(&self, ident: &str, time: OffsetDateTime) -> Json {
+        serde_json::to_value(self.render(ident, time)).unwrap()
+    }
+}
+
+#[test]
+fn cooldown_after_wrong_answers() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "cooldown": { "after": 2, "seconds": 60 } },
+    }));
+
+    game.answer("first", "lead", START);
+    game.answer("first", "iron", START);
+
+    let action = json!({ "type": "answer", "answer": "gold" });
+    let result = game.submit("first", action, START + Duration::seconds(59));
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+
+    let message = game.answer("first", "gold", START + Duration::seconds(60));
+    assert!(matches!(message, Message::SolutionCorrect));
+}
+
+#[test]
+fn cooldown_only_every_after_answers() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "cooldown": { "after": 2, "seconds": 60 } },
+    }));
+
+    game.answer("first", "lead", START);
+    game.answer("first", "iron", START);
+    game.answer("first", "tin", START + Duration::seconds(60));
+
+    let message = game.answer("first", "gold", START + Duration::seconds(61));
+    assert!(matches!(message, Message::SolutionCorrect));
+}
+
+#[test]
+fn cooldown_in_view() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "cooldown": { "after": 1, "seconds": 60 } },
+    }));
+
+    game.answer("first", "lead", START);
+
+    let view = game.view("first", START + Duration::seconds(30));
+    assert_eq!(view["cooldown"], json!("2024-06-01T12:01:00Z"));
+
+    let view = game.view("first", START + Duration::seconds(60));
+    assert_eq!(view.get("cooldown"), None);
+}
+
+#[test]
+fn cooldown_after_zero() {
+    let error = load_error(json!({ "type": "prompt", "cooldown": { "after": 0, "seconds": 60 } }));
+    assert!(error.contains("nonzero"), "{error}");
+}
+
+#[test]
+fn max_attempts() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "max_attempts": 2 },
+    }));
+
+    game.answer("first", "lead", START);
+    assert_eq!(game.view("first", START)["attempts_left"], json!(1));
+
+    game.answer("first", "iron", START + Duration::seconds(1));
+    assert_eq!(game.view("first", START)["disabled"], json!(true));
+
+    let action = json!({ "type": "answer", "answer": "gold" });
+    let result = game.submit("first", action, START + Duration::seconds(2));
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+}
+
+#[test]
+fn max_attempts_zero() {
+    let error = load_error(json!({ "type": "prompt", "max_attempts": 0 }));
+    assert!(error.contains("nonzero"), "{error}");
+}
+
+#[test]
+fn attempts_paths() {
+    let mut game = TestGame::new(json!({ "first": { "type": "prompt" } }));
+
+    game.answer("first", "lead", START);
+    game.answer("first", "iron", START + Duration::seconds(1));
+
+    let value = game.eval("first.attempts.2", START);
+    assert_eq!(value, Value::since(START + Duration::seconds(1)));
+
+    let value = game.eval("first.attempts.3", START);
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn attempts_in_view() {
+    let mut game = TestGame::new(json!({ "first": { "type": "prompt" } }));
+
+    game.answer("first", "lead", START);
+    game.answer("first", "iron", START);
+
+    let view = game.view("first", START);
+    assert_eq!(view["attempts"], json!(["lead", "iron"]));
+}
+
+#[test]
+fn attempts_keep_latest_answers() {
+    let mut game = TestGame::new(json!({ "first": { "type": "prompt" } }));
+
+    for i in 0..12 {
+        game.answer("first", &format!("wrong {i}"), START);
+    }
+
+    let attempts = &game.view("first", START)["attempts"];
+    let attempts = attempts.as_array().unwrap();
+
+    assert_eq!(attempts.len(), 10);
+    assert_eq!(attempts[0], json!("wrong 2"));
+    assert_eq!(attempts[9], json!("wrong 11"));
+
+    let value = game.eval("first.attempts.1", START);
+    assert_eq!(value, Value::since(START));
+}
+
+#[test]
+fn attempts_without_answers() {
+    let state = json!({
+        "type": "prompt",
+        "solved": null,
+        "hints": {},
+        "attempts": [{ "time": "2024-06-01T12:00:00Z" }],
+    });
+
+    let state = State::deserialize(state).unwrap();
+    let State::Prompt(state) = state else {
+        panic!("not a prompt state");
+    };
+
+    assert_eq!(state.attempts.len(), 1);
+    assert_eq!(state.attempts[0].answer, None);
+}
+
+#[test]
+fn milestone_keep_going() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "milestones": [
+                {
+                    "ident": "half",
+                    "solutions": [{ "type": "alphanumeric", "solution": "Go" }],
+                    "record": true,
+                },
+                {
+                    "ident": "quiet",
+                    "solutions": [{ "type": "alphanumeric", "solution": "Gol" }],
+                    "message": "Almost",
+                },
+            ],
+        },
+    }));
+
+    let message = game.answer("first", "go", START);
+    assert!(matches!(message, Message::KeepGoing));
+
+    let message = game.answer("first", "gol", START);
+    assert!(matches!(message, Message::Custom(text) if text == "Almost"));
+
+    let value = game.eval("first.milestone.half", START);
+    assert_eq!(value, Value::since(START));
+
+    // Milestones aren't wrong answers
+    let value = game.eval("first.attempts.1", START);
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn milestone_recorded_once() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "milestones": [{
+                "ident": "half",
+                "solutions": [{ "type": "alphanumeric", "solution": "Go" }],
+                "record": true,
+            }],
+        },
+    }));
+
+    game.answer("first", "go", START);
+    game.answer("first", "go", START + Duration::minutes(1));
+
+    let value = game.eval("first.milestone.half", START + Duration::minutes(1));
+    assert_eq!(value, Value::since(START));
+}
+
+#[test]
+fn milestone_not_recorded() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "milestones": [{
+                "ident": "half",
+                "solutions": [{ "type": "alphanumeric", "solution": "Go" }],
+            }],
+        },
+    }));
+
+    let message = game.answer("first", "go", START);
+    assert!(matches!(message, Message::KeepGoing));
+
+    let value = game.eval("first.milestone.half", START);
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn decimal_within_tolerance() {
+    assert!(decimal_equal("49.1951", 49.195, 0.001));
+    assert!(decimal_equal("49,1941", 49.195, 0.001));
+    assert!(decimal_equal(" -16.6 ", -16.6, 0.0));
+    assert!(!decimal_equal("49.197", 49.195, 0.001));
+    assert!(!decimal_equal("north", 49.195, 0.001));
+}
+
+#[test]
+fn decimal_tolerance_invalid() {
+    let error =
+        Solution::deserialize(json!({ "type": "decimal", "solution": 1.5, "tolerance": -0.1 }))
+            .unwrap_err();
+    assert!(error.to_string().contains("tolerance must be"), "{error}");
+
+    // Neither can be written in JSON
+    assert!(Tolerance::try_from(f64::NAN).is_err());
+    assert!(Tolerance::try_from(f64::INFINITY).is_err());
+
+    assert!(Tolerance::try_from(0.0).is_ok());
+}
+
+#[test]
+fn words_in_any_order() {
+    assert_eq!(words("Red, green & BLUE"), ["BLUE", "GREEN", "RED"]);
+    assert_eq!(words("  "), Vec::<String>::new());
+    assert_eq!(words("žlutý kůň"), ["KUN", "ZLUTY"]);
+}
+
+#[test]
+fn exact_keeps_case_and_diacritics() {
+    assert!(exact_equal("Žlutý  kůň ", "Žlutý kůň"));
+    assert!(exact_equal("Z\u{30c}", "\u{17d}"));
+    assert!(!exact_equal("žlutý kůň", "Žlutý kůň"));
+    assert!(!exact_equal("Zluty kun", "Žlutý kůň"));
+}
+
+fn solution(solution: Json) -> Solution {
+    Solution::deserialize(solution).unwrap()
+}
+
+#[test]
+fn solution_words() {
+    let solution = solution(json!({ "type": "words", "words": ["red", "green"] }));
+
+    assert!(solution.check("green red"));
+    assert!(!solution.check("green"));
+    assert!(!solution.check("green red blue"));
+}
+
+#[test]
+fn pattern_matches_whole_answer() {
+    let solution = solution(json!({ "type": "regex", "pattern": "[0-9]+|x" }));
+
+    assert!(solution.check("42"));
+    assert!(solution.check(" x "));
+    assert!(!solution.check("a42"));
+    assert!(!solution.check("42a"));
+    assert!(!solution.check("xx"));
+}
+
+#[test]
+fn pattern_invalid() {
+    let error = Solution::deserialize(json!({ "type": "regex", "pattern": "(" })).unwrap_err();
+    assert!(error.to_string().starts_with("invalid pattern"), "{error}");
+}
+
+#[test]
+fn typo_distance_edits() {
+    assert_eq!(typo_distance("Praha", "praha", 2), Some(0));
+    assert_eq!(typo_distance("Prha", "Praha", 2), Some(1));
+    assert_eq!(typo_distance("Praaha", "Praha", 2), Some(1));
+    assert_eq!(typo_distance("Przha", "Praha", 2), Some(1));
+    assert_eq!(typo_distance("Parha", "Praha", 2), Some(1));
+    assert_eq!(typo_distance("Brno", "Praha", 2), None);
+}
+
+#[test]
+fn typo_distance_over_max() {
+    assert_eq!(typo_distance("Pha", "Praha", 1), None);
+    assert_eq!(typo_distance("Xyzab", "Praha", 4), None);
+    assert_eq!(typo_distance("Xyzab", "Praha", 5), Some(5));
+}
+
+#[test]
+fn typos_accepted() {
+    let solution = solution(json!({ "type": "alphanumeric", "solution": "Praha", "max_typos": 1 }));
+
+    assert!(solution.check("Parha"));
+    assert!(!solution.check("Pha"));
+    assert!(!solution.close("Parha"));
+}
+
+#[test]
+fn typos_warned() {
+    let solution = solution(json!({
+        "type": "alphanumeric",
+        "solution": "Praha",
+        "max_typos": 1,
+        "on_typo": "warn",
+    }));
+
+    assert!(!solution.check("Parha"));
+    assert!(solution.close("Parha"));
+    assert!(!solution.close("praha"));
+    assert!(!solution.close("Pha"));
+}
+
+#[test]
+fn typos_not_allowed() {
+    let solution = solution(json!({ "type": "alphanumeric", "solution": "Praha" }));
+
+    assert!(!solution.check("Parha"));
+    assert!(!solution.close("Parha"));
+}
+
+#[test]
+fn typo_check_spelling() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "solutions": [{
+                "type": "alphanumeric",
+                "solution": "Gold",
+                "max_typos": 1,
+                "on_typo": "warn",
+            }],
+        },
+    }));
+
+    let message = game.answer("first", "glod", START);
+    assert!(matches!(message, Message::CheckSpelling));
+
+    // Answers which only need their spelling checked aren't wrong
+    let value = game.eval("first.attempts.1", START);
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn typos_after_exact_matches() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "solutions": [{ "type": "alphanumeric", "solution": "Golden", "max_typos": 2 }],
+            "parts": [
+                {
+                    "ident": "north",
+                    "name": "North",
+                    "solutions": [{ "type": "alphanumeric", "solution": "Olden" }],
+                },
+                {
+                    "ident": "south",
+                    "name": "South",
+                    "solutions": [{ "type": "alphanumeric", "solution": "Sand" }],
+                },
+            ],
+            "milestones": [{
+                "ident": "gold",
+                "solutions": [{ "type": "alphanumeric", "solution": "Gold" }],
+            }],
+        },
+    }));
+
+    // Both are within two typos of the solution, but match the part and milestone exactly
+    let message = game.answer("first", "gold", START);
+    assert!(matches!(message, Message::KeepGoing));
+
+    let message = game.answer("first", "olden", START);
+    assert!(matches!(message, Message::PartCorrect));
+
+    let value = game.eval("first.solved", START);
+    assert_eq!(value, Value::never());
+
+    let message = game.answer("first", "goldn", START);
+    assert!(matches!(message, Message::SolutionCorrect));
+}
+
+#[test]
+fn typo_check_spelling_parts() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "solutions": [],
+            "parts": [{
+                "ident": "north",
+                "name": "North",
+                "solutions": [{
+                    "type": "alphanumeric",
+                    "solution": "Snow",
+                    "max_typos": 1,
+                    "on_typo": "warn",
+                }],
+            }],
+        },
+    }));
+
+    let message = game.answer("first", "snwo", START);
+    assert!(matches!(message, Message::CheckSpelling));
+}
+
+/// Parts of a prompt, each solved by a different answer.
+fn parts() -> Json {
+    json!([
+        {
+            "ident": "north",
+            "name": "North",
+            "solutions": [{ "type": "alphanumeric", "solution": "Snow" }],
+        },
+        {
+            "ident": "south",
+            "name": "South",
+            "solutions": [{ "type": "alphanumeric", "solution": "Sand" }],
+        },
+        {
+            "ident": "west",
+            "name": "West",
+            "solutions": [{ "type": "alphanumeric", "solution": "Sea" }],
+        },
+    ])
+}
+
+#[test]
+fn parts_all_required() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "solutions": [], "parts": parts() },
+    }));
+
+    let message = game.answer("first", "sand", START);
+    assert!(matches!(message, Message::PartCorrect));
+
+    let message = game.answer("first", "sand", START);
+    assert!(matches!(message, Message::PartAlreadySolved));
+
+    game.answer("first", "snow", START + Duration::minutes(1));
+
+    let value = game.eval("first.part.south.solved", START);
+    assert_eq!(value, Value::since(START));
+    assert_eq!(game.eval("first.solved", START), Value::never());
+
+    let message = game.answer("first", "sea", START + Duration::minutes(2));
+    assert!(matches!(message, Message::SolutionCorrect));
+
+    let value = game.eval("first.solved", START);
+    assert_eq!(value, Value::since(START + Duration::minutes(2)));
+
+    // Solved parts are listed in the order of the config
+    let view = game.view("first", START + Duration::minutes(2));
+    assert_eq!(view["solution"], json!("Snow, Sand, Sea"));
+}
+
+#[test]
+fn parts_some_required() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "solutions": [], "parts": parts(), "required_parts": 2 },
+    }));
+
+    game.answer("first", "sea", START);
+    let message = game.answer("first", "snow", START);
+    assert!(matches!(message, Message::SolutionCorrect));
+
+    let view = game.view("first", START);
+    assert_eq!(view["solution"], json!("Snow, Sea"));
+    assert_eq!(view["parts"][1]["solution"], Json::Null);
+}
+
+#[test]
+fn parts_required_out_of_range() {
+    for required in [0, 4] {
+        let config = json!({ "type": "prompt", "parts": parts(), "required_parts": required });
+        let error = load_error(config);
+        assert!(
+            error.contains("required_parts must be between 1 and"),
+            "{error}"
+        );
+    }
+
+    let error = load_error(json!({ "type": "prompt", "required_parts": 1 }));
+    assert!(error.contains("only be set along with parts"), "{error}");
+}
+
+#[test]
+fn choice_single() {
+    let mut game = TestGame::new(json!({ "colors": { "type": "choice" } }));
+
+    let result = game.choose("colors", &["red"], START);
+    assert!(matches!(result, Ok(Some(Message::SolutionIncorrect))));
+
+    let result = game.choose("colors", &["green", "red"], START);
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+
+    let result = game.choose("colors", &["green"], START + Duration::minutes(1));
+    assert!(matches!(result, Ok(Some(Message::SolutionCorrect))));
+
+    let value = game.eval("colors.solved", START);
+    assert_eq!(value, Value::since(START + Duration::minutes(1)));
+
+    // Solved questions can't be answered again
+    let result = game.choose("colors", &["green"], START + Duration::minutes(2));
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+}
+
+#[test]
+fn choice_multiple() {
+    let mut game = TestGame::new(json!({
+        "colors": { "type": "choice", "correct": ["blue", "red"], "multiple": true },
+    }));
+
+    let result = game.choose("colors", &["red"], START);
+    assert!(matches!(result, Ok(Some(Message::SolutionIncorrect))));
+
+    let result = game.choose("colors", &["red", "green", "blue"], START);
+    assert!(matches!(result, Ok(Some(Message::SolutionIncorrect))));
+
+    let result = game.choose("colors", &["blue", "red"], START);
+    assert!(matches!(result, Ok(Some(Message::SolutionCorrect))));
+
+    // Selected options are kept in the order they are listed in
+    let view = game.view("colors", START);
+    assert_eq!(view["result"]["selected"], json!(["red", "blue"]));
+}
+
+#[test]
+fn choice_one_shot() {
+    let mut game = TestGame::new(json!({ "colors": { "type": "choice", "one_shot": true } }));
+
+    game.choose("colors", &["blue"], START).unwrap();
+
+    let value = game.eval("colors.failed", START);
+    assert_eq!(value, Value::since(START));
+
+    let result = game.choose("colors", &["green"], START);
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+}
+
+#[test]
+fn choice_unknown_option() {
+    let mut game = TestGame::new(json!({ "colors": { "type": "choice" } }));
+
+    let result = game.choose("colors", &["purple"], START);
+    assert!(matches!(result, Err(ActionError::UnknownIdent)));
+
+    let result = game.choose("colors", &[], START);
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+}
+
+#[test]
+fn choice_invalid_correct() {
+    let error = load_error(json!({ "type": "choice", "correct": ["purple"] }));
+    assert!(error.contains("unknown option \"purple\""), "{error}");
+
+    let error = load_error(json!({ "type": "choice", "correct": ["red", "green"] }));
+    assert!(error.contains("unless multiple is set"), "{error}");
+
+    let error = load_error(json!({ "type": "choice", "correct": [] }));
+    assert!(error.contains("at least one option"), "{error}");
+}
+
+#[test]
+fn choice_duplicate_options() {
+    let error = load_error(json!({
+        "type": "choice",
+        "options": [
+            { "ident": "red", "text": "Red" },
+            { "ident": "green", "text": "Green" },
+            { "ident": "red", "text": "Also red" },
+        ],
+    }));
+    assert!(
+        error.contains("option \"red\" is listed more than once"),
+        "{error}"
+    );
+}
+
+fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!(
+        (a - b).abs() <= tolerance,
+        "{a} is not within {tolerance} of {b}"
+    );
+}
+
+#[test]
+fn distance_along_meridian() {
+    // A degree of latitude is a 360th of the circumference
+    assert_close(distance((50.0, 14.0), (51.0, 14.0)), 111_194.9, 0.1);
+    assert_close(distance((-0.5, 0.0), (0.5, 0.0)), 111_194.9, 0.1);
+}
+
+#[test]
+fn distance_along_parallel() {
+    assert_close(distance((0.0, 179.5), (0.0, -179.5)), 111_194.9, 0.1);
+
+    // Meridians get closer towards the poles
+    assert_close(distance((60.0, 0.0), (60.0, 1.0)), 55_597.2, 1.0);
+}
+
+#[test]
+fn distance_extremes() {
+    assert_eq!(distance((49.195, 16.608), (49.195, 16.608)), 0.0);
+    assert_close(distance((0.0, 0.0), (0.0, 180.0)), 20_015_086.8, 1.0);
+    assert_close(distance((90.0, 0.0), (-90.0, 0.0)), 20_015_086.8, 1.0);
+}
+
+#[test]
+fn check_in_within_radius() {
+    let mut game = TestGame::new(json!({ "square": { "type": "checkpoint" } }));
+
+    // About 44 meters north
+    let result = game.check_in("square", 50.0004, 10.0);
+    assert!(matches!(result, Ok(Some(Message::CheckpointReached))));
+
+    let value = game.eval("square.reached", START);
+    assert_eq!(value, Value::since(START));
+
+    let result = game.check_in("square", 50.0, 10.0);
+    assert!(matches!(result, Err(ActionError::NotPossible)));
+}
+
+#[test]
+fn check_in_accuracy_does_not_widen_radius() {
+    let mut game = TestGame::new(json!({ "square": { "type": "checkpoint" } }));
+
+    // About 56 meters north
+    let result = game.check_in("square", 50.0005, 100.0);
+    assert!(matches!(result, Ok(Some(Message::TooFar))));
+
+    let result = game.check_in("square", 50.0, 101.0);
+    assert!(matches!(result, Ok(Some(Message::LowAccuracy))));
+
+    let value = game.eval("square.reached", START);
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn checkpoint_invalid_location() {
+    let error = load_error(json!({ "type": "checkpoint", "latitude": 91.0 }));
+    assert!(error.contains("latitude must be between"), "{error}");
+
+    let error = load_error(json!({ "type": "checkpoint", "longitude": -180.5 }));
+    assert!(error.contains("longitude must be between"), "{error}");
+
+    let error = load_error(json!({ "type": "checkpoint", "radius": -1.0 }));
+    assert!(error.contains("isn't negative"), "{error}");
+
+    let error = load_error(json!({ "type": "checkpoint", "max_accuracy": -5.0 }));
+    assert!(error.contains("isn't negative"), "{error}");
+}
+
+/// A prompt showing the asset "map" and the asset "whisper" once its hint is taken.
+fn hinted_prompt() -> Json {
+    json!({
+        "type": "prompt",
+        "details": [
+            "Look closely",
+            { "type": "image", "asset": "map", "alt": "Map" },
+        ],
+        "hints": [{
+            "ident": "spoiler",
+            "name": "Spoiler",
+            "available": "always",
+            "content": [{ "type": "audio", "asset": "whisper" }],
+        }],
+    })
+}
+
+#[test]
+fn assets_shown_once_hint_taken() {
+    let mut game = TestGame::new(json!({ "first": hinted_prompt() }));
+
+    let view = game.render("first", START).unwrap();
+    assert_eq!(view.assets(), ["map"]);
+
+    let action = json!({ "type": "hint", "ident": "spoiler" });
+    game.submit("first", action, START).unwrap();
+
+    let view = game.render("first", START).unwrap();
+    assert_eq!(view.assets(), ["map", "whisper"]);
+}
+
+#[test]
+fn assets_checked() {
+    let game = TestGame::new(json!({ "first": hinted_prompt() }));
+
+    let errors = game
+        .0
+        .check_assets(&["map".to_owned(), "whisper".to_owned()]);
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let errors: Vec<String> = game
+        .0
+        .check_assets(&["map".to_owned()])
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        errors,
+        ["first.hint.spoiler.content.0: unknown asset \"whisper\""]
+    );
+}
+
+/// Parses Markdown into spans of a single paragraph.
+fn markdown(source: &str) -> Json {
+    let markdown = Markdown::parse(source.to_owned()).unwrap();
+    serde_json::to_value(markdown.spans()).unwrap()
+}
+
+fn text(text: &str) -> Json {
+    json!({ "type": "text", "text": text })
+}
+
+#[test]
+fn markdown_plain() {
+    assert_eq!(
+        markdown("Just <b>text</b>"),
+        json!([text("Just <b>text</b>")])
+    );
+}
+
+#[test]
+fn markdown_emphasis() {
+    assert_eq!(
+        markdown("**bold** and *italic* and __bold__ and _italic_"),
+        json!([
+            { "type": "bold", "content": [text("bold")] },
+            text(" and "),
+            { "type": "italic", "content": [text("italic")] },
+            text(" and "),
+            { "type": "bold", "content": [text("bold")] },
+            text(" and "),
+            { "type": "italic", "content": [text("italic")] },
+        ])
+    );
+}
+
+#[test]
+fn markdown_nested_emphasis() {
+    assert_eq!(
+        markdown("**very *much* so**"),
+        json!([{
+            "type": "bold",
+            "content": [
+                text("very "),
+                { "type": "italic", "content": [text("much")] },
+                text(" so"),
+            ],
+        }])
+    );
+}
+
+#[test]
+fn markdown_unmatched_emphasis() {
+    assert_eq!(markdown("2 * 3 * 4"), json!([text("2 * 3 * 4")]));
+    assert_eq!(markdown("*open"), json!([text("*open")]));
+    assert_eq!(
+        markdown("**open*"),
+        json!([text("*"), { "type": "italic", "content": [text("open")] }])
+    );
+}
+
+#[test]
+fn markdown_underscores_in_words() {
+    assert_eq!(
+        markdown("snake_case_name"),
+        json!([text("snake_case_name")])
+    );
+    assert_eq!(
+        markdown("a_b _c_"),
+        json!([text("a_b "), { "type": "italic", "content": [text("c")] }])
+    );
+}
+
+#[test]
+fn markdown_code() {
+    assert_eq!(
+        markdown("run `a*b*c` now"),
+        json!([text("run "), { "type": "code", "text": "a*b*c" }, text(" now")])
+    );
+    assert_eq!(
+        markdown("*see `x*`*"),
+        json!([{
+            "type": "italic",
+            "content": [text("see "), { "type": "code", "text": "x*" }],
+        }])
+    );
+}
+
+#[test]
+fn markdown_escapes() {
+    assert_eq!(markdown(r"\*not italic\*"), json!([text("*not italic*")]));
+    assert_eq!(markdown(r"a \\ b"), json!([text(r"a \ b")]));
+    assert_eq!(markdown(r"C:\Users"), json!([text(r"C:\Users")]));
+}
+
+#[test]
+fn markdown_links() {
+    assert_eq!(
+        markdown("see [the **map**](https://example.com/map) here"),
+        json!([
+            text("see "),
+            {
+                "type": "link",
+                "href": "https://example.com/map",
+                "content": [text("the "), { "type": "bold", "content": [text("map")] }],
+            },
+            text(" here"),
+        ])
+    );
+    assert_eq!(
+        markdown(r"[a \] b](mailto:org@example.com)"),
+        json!([{
+            "type": "link",
+            "href": "mailto:org@example.com",
+            "content": [text("a ] b")],
+        }])
+    );
+    assert_eq!(
+        markdown("[not a link] (x)"),
+        json!([text("[not a link] (x)")])
+    );
+}
+
+#[test]
+fn markdown_unsafe_links() {
+    for href in [
+        "javascript:alert(1)",
+        "JavaScript:alert(1)",
+        "data:text/html,x",
+        "/relative",
+    ] {
+        let error = Markdown::parse(format!("[x]({href})")).unwrap_err();
+        assert!(error.to_string().contains("must use http"), "{error}");
+    }
+
+    assert!(Markdown::parse("[x](HTTPS://example.com)".to_owned()).is_ok());
+}
+
+#[test]
+fn markdown_blocks() {
+    let markdown = Markdown::parse(
+        "First line\nsame paragraph\n\n- one\n  continued\n* two\n\n1. first\n2. second".to_owned(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(markdown.blocks()).unwrap(),
+        json!([
+            { "type": "rich", "content": [text("First line same paragraph")] },
+            { "type": "list", "ordered": false, "items": [[text("one continued")], [text("two")]] },
+            { "type": "list", "ordered": true, "items": [[text("first")], [text("second")]] },
+        ])
+    );
+
+    assert_eq!(
+        serde_json::to_value(markdown.spans()).unwrap(),
+        json!([text(
+            "First line same paragraph one continued two first second"
+        )])
+    );
+}
+
+#[test]
+fn markdown_errors_reported() {
+    let error = load_error(json!({
+        "type": "prompt",
+        "details": [{ "markdown": "[x](javascript:alert(1))" }],
+    }));
+    assert!(error.contains("must use http"), "{error}");
+
+    let error = load_error(json!({
+        "type": "prompt",
+        "on_solution_correct": { "markdown": "[x](javascript:alert(1))" },
+    }));
+    assert!(error.contains("must use http"), "{error}");
+}
+
+#[test]
+fn placeholders_found() {
+    let text = "Hi {team.name}, meet {group.leader} at {team.start-time}";
+    let found: Vec<_> = placeholders(text).collect();
+
+    assert_eq!(
+        found,
+        [
+            (3, 14, "team.name"),
+            (21, 35, "group.leader"),
+            (39, 56, "team.start-time"),
+        ]
+    );
+}
+
+#[test]
+fn placeholders_ignored() {
+    for text in [
+        "{}",
+        "{team}",
+        "{team.}",
+        "{other.name}",
+        "{team.two words}",
+        "{team.name",
+        "set {a, b}",
+    ] {
+        assert_eq!(placeholders(text).count(), 0, "{text}");
+    }
+
+    let found: Vec<_> = placeholders("{{team.name}}").map(|p| p.2).collect();
+    assert_eq!(found, ["team.name"]);
+}
+
+#[test]
+fn substitute_known() {
+    let value = |path: &str| (path == "team.name").then(|| "Alpha".to_owned());
+
+    assert_eq!(substitute("Go {team.name}!", value), "Go Alpha!");
+    assert_eq!(
+        substitute("{team.name} {team.motto} {team.name}", value),
+        "Alpha {team.motto} Alpha"
+    );
+    assert_eq!(substitute("no placeholders", value), "no placeholders");
+}
+
+fn attributes(name: &str, attributes: Json) -> Attributes {
+    let mut attributes = Attributes::deserialize(attributes).unwrap();
+    attributes.name = name.to_owned();
+    attributes
+}
+
+#[test]
+fn attributes_text() {
+    let attributes = attributes(
+        "Alpha",
+        json!({
+            "motto": { "text": "north|south" },
+            "members": { "number": 4 },
+            "start": "2024-06-01 12:00 +0",
+        }),
+    );
+
+    assert_eq!(attributes.text("name").as_deref(), Some("Alpha"));
+    assert_eq!(attributes.text("motto").as_deref(), Some("north|south"));
+    assert_eq!(attributes.text("members").as_deref(), Some("4"));
+    assert_eq!(attributes.text("start"), None);
+    assert_eq!(attributes.text("unknown"), None);
+}
+
+#[test]
+fn attributes_invalid() {
+    let error = Attributes::deserialize(json!({ "start": "2024-06-01 12:00 +0 &" })).unwrap_err();
+    assert!(
+        error.to_string().starts_with("invalid expression"),
+        "{error}"
+    );
+
+    let error =
+        Attributes::deserialize(json!({ "motto": { "text": "a", "number": 1 } })).unwrap_err();
+    assert!(
+        error.to_string().contains("either text or a number"),
+        "{error}"
+    );
+
+    let error = Attributes::deserialize(json!({ "motto": { "emoji": "a" } })).unwrap_err();
+    assert!(error.to_string().contains("unknown field"), "{error}");
+}
+
+#[test]
+fn attributes_in_text() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "details": [
+                "Good luck, {team.name}",
+                { "text": "{team.name} has {team.members} members" },
+            ],
+        },
+    }));
+    game.0.team = attributes("Alpha", json!({ "members": { "number": 4 } }));
+
+    let view = game.view("first", START);
+    assert_eq!(
+        view["details"],
+        json!([
+            { "type": "paragraph", "text": "Good luck, Alpha" },
+            { "type": "paragraph", "text": "Alpha has 4 members" },
+        ])
+    );
+
+    let value = game.eval("team.members > 3", START);
+    assert_eq!(value, Value::always());
+}
+
+fn merged(target: Json, patch: Json) -> Json {
+    let mut target = target;
+    merge_object(&mut target, patch.as_object().unwrap());
+    target
+}
+
+#[test]
+fn merge_patch() {
+    assert_eq!(
+        merged(
+            json!({ "a": "b", "c": { "d": "e", "f": "g" } }),
+            json!({ "a": "z", "c": { "f": null } }),
+        ),
+        json!({ "a": "z", "c": { "d": "e" } })
+    );
+}
+
+#[test]
+fn merge_patch_replaces() {
+    // Arrays and other values aren't merged
+    assert_eq!(
+        merged(
+            json!({ "a": [1, 2], "b": "c" }),
+            json!({ "a": [3], "b": { "d": 1 } })
+        ),
+        json!({ "a": [3], "b": { "d": 1 } })
+    );
+    assert_eq!(
+        merged(json!({ "a": { "b": 1 } }), json!({ "a": "flat" })),
+        json!({ "a": "flat" })
+    );
+}
+
+#[test]
+fn merge_patch_nulls() {
+    // Removing a missing field is fine, and nulls are never added
+    assert_eq!(
+        merged(json!({ "a": 1 }), json!({ "b": null, "c": { "d": null } })),
+        json!({ "a": 1, "c": {} })
+    );
+    assert_eq!(merged(json!([1]), json!({ "a": 1 })), json!({ "a": 1 }));
+}
+
+fn overrides(overrides: Json) -> Overrides {
+    Overrides::deserialize(overrides).unwrap()
+}
+
+#[test]
+fn overrides_layers() {
+    let source = widget(json!({ "type": "prompt", "points": 10, "max_attempts": 3 }));
+    let group = overrides(json!({ "first": { "points": 20, "max_attempts": 5 } }));
+    let team = overrides(json!({ "first": { "points": 30 } }));
+
+    let config = Overrides::config(&[&group, &team], "first", &source)
+        .unwrap()
+        .unwrap();
+
+    let mut game = TestGame(GameState::default());
+    game.0
+        .instances
+        .insert("first".to_owned(), config.instance_default());
+
+    // The team's overrides take precedence over the group's
+    game.answer("first", "gold", START);
+    assert_eq!(game.0.score(START).unwrap().at(START), 30);
+
+    let config = Overrides::config(&[&team, &group], "first", &source)
+        .unwrap()
+        .unwrap();
+    let config = serde_json::to_value(config).unwrap();
+    assert_eq!(config["points"], json!(20));
+    assert_eq!(config["max_attempts"], json!(5));
+}
+
+#[test]
+fn overrides_other_widgets() {
+    let source = widget(json!({ "type": "prompt" }));
+    let team = overrides(json!({ "second": { "points": 30 } }));
+
+    assert!(Overrides::config(&[&team], "first", &source).is_none());
+    assert!(Overrides::config(&[], "first", &source).is_none());
+}
+
+#[test]
+fn overrides_invalid() {
+    let error = Overrides::deserialize(json!({ "first": 5 })).unwrap_err();
+    assert!(error.to_string().contains("must be an object"), "{error}");
+
+    let error = Overrides::deserialize(json!({ "first": { "type": "text" } })).unwrap_err();
+    assert!(
+        error.to_string().contains("can't change its type"),
+        "{error}"
+    );
+
+    let source = widget(json!({ "type": "prompt" }));
+    let team = overrides(json!({ "first": { "visible": null } }));
+    let result = Overrides::config(&[&team], "first", &source).unwrap();
+    assert!(result.is_err());
+}
+
+#[test]
+fn group_attributes() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "visible": "group.open", "details": ["Hi {group.name}"] },
+    }));
+    game.0.group = attributes("north", json!({ "open": "2024-06-01 12:00 +0" }));
+    game.0.team = attributes("Alpha", json!({ "start": "group.open" }));
+
+    assert_eq!(game.eval("group.open", START), Value::since(START));
+    assert_eq!(game.eval("team.start", START), Value::since(START));
+    assert_eq!(game.0.text("group.name").as_deref(), Some("north"));
+
+    let view = game.view("first", START);
+    assert_eq!(view["details"][0]["text"], json!("Hi north"));
+
+    let errors = game.0.check();
+    assert!(errors.is_empty(), "{errors:?}");
+}
+
+#[test]
+fn group_missing() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "visible": "group.open", "details": ["Hi {group.name}"] },
+    }));
+    game.0.team = attributes("Alpha", json!({}));
+
+    // Teams without a group have a group without a name or attributes
+    assert_eq!(game.0.text("group.name"), None);
+
+    let errors: Vec<String> = game.0.check().iter().map(ToString::to_string).collect();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(
+        errors.iter().any(|e| e.contains("\"group.open\"")),
+        "{errors:?}"
+    );
+    assert!(
+        errors.iter().any(|e| e.contains("\"group.name\"")),
+        "{errors:?}"
+    );
+}
+
+/// A game with a single prompt, visible once its team's group opens, and one team in one group.
+fn definition() -> GameDefinition {
+    let prompt =
+        json!({ "type": "prompt", "visible": "group.open", "details": ["Hi {team.name}"] });
+
+    GameDefinition {
+        name: "Game".to_owned(),
+        time_zone: Some("Europe/Prague".to_owned()),
+        widgets: vec![("first".to_owned(), widget(prompt))],
+        groups: vec![GroupDefinition {
+            name: "north".to_owned(),
+            attributes: json!({ "open": "always" }),
+            overrides: json!({ "first": { "points": 5 } }),
+        }],
+        teams: vec![TeamDefinition {
+            name: "Alpha".to_owned(),
+            attributes: json!({}),
+            overrides: json!({}),
+            group: Some("north".to_owned()),
+        }],
+        assets: Vec::new(),
+    }
+}
+
+#[test]
+fn definition_valid() {
+    assert_eq!(definition().check(), Vec::<String>::new());
+}
+
+#[test]
+fn definition_unknown_group() {
+    let mut definition = definition();
+    definition.teams[0].group = Some("south".to_owned());
+
+    assert_eq!(definition.check(), ["team Alpha: unknown group \"south\""]);
+}
+
+#[test]
+fn definition_team_without_group() {
+    let mut definition = definition();
+    definition.teams[0].group = None;
+
+    let problems = definition.check();
+    assert_eq!(problems.len(), 1, "{problems:?}");
+    assert!(problems[0].contains("\"group.open\""), "{problems:?}");
+}
+
+#[test]
+fn definition_group_problems() {
+    let mut definition = definition();
+    definition.groups[0].attributes = json!({ "open": "always &" });
+    definition.groups.push(GroupDefinition {
+        name: "south".to_owned(),
+        attributes: json!({}),
+        overrides: json!({ "second": { "points": 5 } }),
+    });
+
+    let problems = definition.check();
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert!(
+        problems[0].starts_with("group north: invalid expression"),
+        "{problems:?}"
+    );
+    assert_eq!(
+        problems[1],
+        "group south: override of unknown widget \"second\""
+    );
+}
+
+#[test]
+fn check_unknown_paths() {
+    let game = TestGame::new(json!({
+        "first": { "type": "prompt", "visible": "missing.solved" },
+        "second": { "type": "prompt", "visible": "first.hint.spoiler.taken | first.bogus" },
+        "third": { "type": "prompt", "visible": "team.start & group.open" },
+    }));
+
+    assert_eq!(
+        game.check(),
+        [
+            "first.visible: unknown widget \"missing\" in path \"missing.solved\"",
+            "second.visible: unknown hint \"spoiler\" in path \"first.hint.spoiler.taken\"",
+            "second.visible: unknown path \"first.bogus\"",
+            "third.visible: unknown attribute \"team.start\"",
+            "third.visible: unknown attribute \"group.open\"",
+        ]
+    );
+}
+
+#[test]
+fn check_attempt_indices() {
+    let game = TestGame::new(json!({
+        "first": { "type": "prompt" },
+        "second": { "type": "prompt", "visible": "first.attempts.3 | first.attempts.0" },
+    }));
+
+    // Attempts are counted from one, and any of them can be referred to
+    assert_eq!(
+        game.check(),
+        ["second.visible: unknown path \"first.attempts.0\""]
+    );
+}
+
+#[test]
+fn check_time_zone() {
+    let mut game = TestGame::new(json!({
+        "first": { "type": "prompt", "visible": "2024-06-01 12:00" },
+        "second": { "type": "prompt", "visible": "2024-06-01 12:00 Europe/Prague" },
+    }));
+
+    assert_eq!(
+        game.check(),
+        ["first.visible: time literal has no time zone and the game has no default"]
+    );
+
+    game.0.zone = Zone::named("Europe/Prague");
+    assert_eq!(game.check(), Vec::<String>::new());
+}
+
+#[test]
+fn check_cycle() {
+    let game = TestGame::new(json!({
+        "a": {
+            "type": "prompt",
+            "hints": [{ "ident": "x", "name": "X", "content": [], "available": "b.visible" }],
+        },
+        "b": { "type": "prompt", "visible": "a.hint.x.available" },
+    }));
+
+    assert_eq!(
+        game.check(),
+        ["circular dependency: a.hint.x.available -> b.visible -> a.hint.x.available"]
+    );
+}
+
+#[test]
+fn definition_time_zone() {
+    let mut definition = definition();
+    definition.time_zone = Some("Mars/Olympus".to_owned());
+    definition.widgets[0].1["visible"] = json!("2024-06-01 12:00");
+
+    assert_eq!(
+        definition.check(),
+        [
+            "unknown time zone \"Mars/Olympus\"",
+            "first.visible: time literal has no time zone and the game has no default",
+        ]
+    );
+}
+
+#[test]
+fn expressions_parsed_on_load() {
+    let error = load_error(json!({ "type": "prompt", "visible": "always &" }));
+    assert!(error.starts_with("invalid expression"), "{error}");
+
+    let error = load_error(json!({
+        "type": "prompt",
+        "hints": [{ "ident": "x", "name": "X", "content": [], "available": "(always" }],
+    }));
+    assert!(error.starts_with("invalid expression"), "{error}");
+
+    let error = load_error(json!({
+        "type": "text",
+        "content": [{ "text": "Hi", "show": "always |" }],
+    }));
+    assert!(error.contains("invalid expression"), "{error}");
+}
+
+#[test]
+fn score_points_bonus_penalty() {
+    let mut game = TestGame::new(json!({
+        "first": {
+            "type": "prompt",
+            "points": 100,
+            "bonuses": [{ "points": 50, "available": "2024-06-01 12:00 +0 until 2024-06-01 12:30 +0" }],
+            "hints": [{ "ident": "x", "name": "X", "content": [], "available": "always", "penalty": 30 }],
+        },
+        "second": {
+            "type": "prompt",
+            "points": 10,
+            "bonuses": [{ "points": 5, "available": "2024-06-01 12:00 +0 until 2024-06-01 12:30 +0" }],
+        },
+    }));
+
+    let hint = json!({ "type": "hint", "ident": "x" });
+    game.submit("first", hint, START).unwrap();
+    game.answer("first", "gold", START + Duration::minutes(10));
+    game.answer("second", "gold", START + Duration::minutes(40));
+
+    let score = game.0.score(START).unwrap();
+    assert_eq!(score.at(START - Duration::minutes(1)), 0);
+    assert_eq!(score.at(START), -30);
+    assert_eq!(score.at(START + Duration::minutes(10)), 120);
+    assert_eq!(score.at(START + Duration::minutes(40)), 130);
+
+    let value = game.eval("team.score >= 120", START);
+    assert_eq!(value, Value::since(START + Duration::minutes(10)));
+
+    let value = game.eval("first.points > second.points", START);
+    assert_eq!(value, Value::since(START + Duration::minutes(10)));
+}
+
+#[test]
+fn scored_widgets() {
+    let game = TestGame::new(json!({
+        "prompt": { "type": "prompt" },
+        "choice": { "type": "choice" },
+        "checkpoint": { "type": "checkpoint" },
+        "text": { "type": "text", "content": [] },
+        "countdown": { "type": "countdown", "time": "always", "done_text": "Done" },
+    }));
+
+    // Checking the type of a widget is enough to know whether it has points
+    for (ident, instance) in &game.0.instances {
+        let points = instance
+            .fields()
+            .iter()
+            .any(|f| f.number && f.name == "points");
+
+        assert_eq!(instance.is_scored(), points, "{ident}");
+    }
+
+    let scored: Vec<_> = game.0.scored().collect();
+    assert_eq!(scored, ["checkpoint", "choice", "prompt"]);
+}
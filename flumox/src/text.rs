@@ -1,15 +1,66 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
 use time_expr::EvalError;
 
-use crate::{expr::Expr, ViewContext};
+use crate::{
+    check::Field,
+    expr::Expr,
+    markup::{Markdown, Span},
+    template, ViewContext,
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Text(Vec<Paragraph>);
 
+/// A single piece of content, which may refer to an asset stored by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum Block {
+    Paragraph {
+        text: String,
+    },
+    /// A paragraph with formatting
+    Rich {
+        content: Vec<Span>,
+    },
+    List {
+        ordered: bool,
+        items: Vec<Vec<Span>>,
+    },
+    Image {
+        asset: String,
+        #[serde(default)]
+        alt: String,
+    },
+    Audio {
+        asset: String,
+    },
+    Download {
+        asset: String,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum Content {
+    Block(Block),
+    Markdown { markdown: Markdown },
+    Text { text: String },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ConditionalParagraph {
-    text: String,
+    #[serde(flatten)]
+    content: Content,
     #[serde(default = "Expr::always")]
     show: Expr,
     #[serde(default = "Expr::never")]
@@ -20,7 +71,7 @@ struct ConditionalParagraph {
 #[serde(transparent)]
 struct SimpleParagraph(String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 enum Paragraph {
     Simple(SimpleParagraph),
@@ -28,33 +79,233 @@ enum Paragraph {
 }
 
 impl Text {
-    pub fn render(&self, ctx: &mut ViewContext) -> Result<Vec<String>, EvalError> {
+    pub fn render(&self, ctx: &mut ViewContext) -> Result<Vec<Block>, EvalError> {
         let mut result = Vec::new();
+        let game = ctx.env.game;
 
         for p in &self.0 {
-            if let Some(p) = p.content(ctx)? {
-                result.push(p.to_string());
+            for mut block in p.content(ctx)? {
+                block.substitute(&mut |path| game.text(path));
+                result.push(block);
             }
         }
 
         Ok(result)
     }
+
+    pub(crate) fn fields<'a>(&'a self, prefix: &str, fields: &mut Vec<Field<'a>>) {
+        for (i, p) in self.0.iter().enumerate() {
+            if let Paragraph::Conditional(p) = p {
+                fields.push(Field::internal(format!("{prefix}.{i}.show"), &p.show));
+                fields.push(Field::internal(format!("{prefix}.{i}.hide"), &p.hide));
+            }
+
+            let variables: Vec<String> = p
+                .sources()
+                .into_iter()
+                .flat_map(template::variables)
+                .map(str::to_owned)
+                .collect();
+
+            let assets = p.assets();
+
+            if !variables.is_empty() || !assets.is_empty() {
+                fields.push(Field::template(format!("{prefix}.{i}"), variables).showing(assets));
+            }
+        }
+    }
+}
+
+impl Block {
+    /// Returns the ident of the asset this block displays, if any.
+    pub fn asset(&self) -> Option<&str> {
+        match self {
+            Block::Paragraph { .. } | Block::Rich { .. } | Block::List { .. } => None,
+            Block::Image { asset, .. } | Block::Audio { asset } | Block::Download { asset, .. } => {
+                Some(asset)
+            }
+        }
+    }
+
+    /// Returns the text which team or group attributes can be inserted into.
+    fn sources(&self) -> Vec<&str> {
+        let mut sources = Vec::new();
+
+        match self {
+            Block::Paragraph { text } | Block::Download { text, .. } => sources.push(text.as_str()),
+            Block::Image { alt, .. } => sources.push(alt),
+            Block::Audio { .. } => {}
+            Block::Rich { content } => span_sources(content, &mut sources),
+            Block::List { items, .. } => {
+                for item in items {
+                    span_sources(item, &mut sources);
+                }
+            }
+        }
+
+        sources
+    }
+
+    /// Inserts team and group attributes into the text of the block.
+    fn substitute(&mut self, value: &mut impl FnMut(&str) -> Option<String>) {
+        match self {
+            Block::Paragraph { text } | Block::Download { text, .. } => {
+                *text = template::substitute(text, &mut *value)
+            }
+            Block::Image { alt, .. } => *alt = template::substitute(alt, &mut *value),
+            Block::Audio { .. } => {}
+            Block::Rich { content } => substitute_spans(content, value),
+            Block::List { items, .. } => {
+                for item in items {
+                    substitute_spans(item, value);
+                }
+            }
+        }
+    }
+}
+
+fn span_sources<'a>(spans: &'a [Span], sources: &mut Vec<&'a str>) {
+    for span in spans {
+        match span {
+            Span::Text { text } => sources.push(text),
+            Span::Bold { content } | Span::Italic { content } | Span::Link { content, .. } => {
+                span_sources(content, sources)
+            }
+            Span::Code { .. } => {}
+        }
+    }
+}
+
+/// Inserts team and group attributes into formatted text, after it has been parsed,
+/// so that the attributes themselves can't contain any formatting.
+fn substitute_spans(spans: &mut [Span], value: &mut impl FnMut(&str) -> Option<String>) {
+    for span in spans {
+        match span {
+            Span::Text { text } => *text = template::substitute(text, &mut *value),
+            Span::Bold { content } | Span::Italic { content } | Span::Link { content, .. } => {
+                substitute_spans(content, value)
+            }
+            Span::Code { .. } => {}
+        }
+    }
+}
+
+/// Collects the idents of all assets referred to by rendered content.
+pub(crate) fn collect_assets<'a>(blocks: &'a [Block], assets: &mut Vec<&'a str>) {
+    assets.extend(blocks.iter().filter_map(Block::asset));
+}
+
+impl Content {
+    fn sources(&self) -> Vec<&str> {
+        match self {
+            Content::Block(block) => block.sources(),
+            Content::Markdown { markdown } => vec![markdown.source()],
+            Content::Text { text } => vec![text],
+        }
+    }
+
+    fn blocks(&self) -> Vec<Block> {
+        match self {
+            Content::Block(block) => vec![block.clone()],
+            Content::Markdown { markdown } => markdown.blocks().to_vec(),
+            Content::Text { text } => vec![Block::Paragraph { text: text.clone() }],
+        }
+    }
 }
 
 impl Paragraph {
-    pub fn content(&self, ctx: &mut ViewContext) -> Result<Option<&str>, EvalError> {
+    /// Returns the idents of the assets the paragraph may show.
+    fn assets(&self) -> Vec<&str> {
+        match self {
+            Paragraph::Simple(_) => Vec::new(),
+            Paragraph::Conditional(p) => match &p.content {
+                Content::Block(block) => block.asset().into_iter().collect(),
+                Content::Markdown { markdown } => {
+                    markdown.blocks().iter().filter_map(Block::asset).collect()
+                }
+                Content::Text { .. } => Vec::new(),
+            },
+        }
+    }
+
+    fn sources(&self) -> Vec<&str> {
         match self {
-            Paragraph::Simple(p) => Ok(Some(&p.0)),
+            Paragraph::Simple(p) => vec![&p.0],
+            Paragraph::Conditional(p) => p.content.sources(),
+        }
+    }
+
+    pub fn content(&self, ctx: &mut ViewContext) -> Result<Vec<Block>, EvalError> {
+        match self {
+            Paragraph::Simple(p) => Ok(vec![Block::Paragraph { text: p.0.clone() }]),
             Paragraph::Conditional(p) => {
                 let show = ctx.eval(&p.show)?;
                 let hide = ctx.eval(&p.hide)?;
 
                 if show && !hide {
-                    Ok(Some(&p.text))
+                    Ok(p.content.blocks())
                 } else {
-                    Ok(None)
+                    Ok(Vec::new())
                 }
             }
         }
     }
 }
+
+impl<'de> Deserialize<'de> for Content {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Buffered, since the key telling the kinds apart may come after the others
+        let mut map = Map::<String, Value>::deserialize(deserializer)?;
+
+        let content = if map.contains_key("type") {
+            Block::deserialize(Value::Object(map)).map(Content::Block)
+        } else if let Some(markdown) = map.remove("markdown") {
+            Markdown::deserialize(markdown).map(|markdown| Content::Markdown { markdown })
+        } else if let Some(text) = map.remove("text") {
+            String::deserialize(text).map(|text| Content::Text { text })
+        } else {
+            return Err(de::Error::custom(
+                "paragraph must have a type, markdown or text",
+            ));
+        };
+
+        content.map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Paragraph {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ParagraphVisitor;
+
+        impl<'de> Visitor<'de> for ParagraphVisitor {
+            type Value = Paragraph;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a paragraph object")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Paragraph::Simple(SimpleParagraph(v.to_owned())))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                ConditionalParagraph::deserialize(MapAccessDeserializer::new(map))
+                    .map(Paragraph::Conditional)
+            }
+        }
+
+        deserializer.deserialize_any(ParagraphVisitor)
+    }
+}
@@ -1,4 +1,11 @@
-use serde::Serialize;
+use std::fmt;
+
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::markup::{Markdown, Span};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Toast {
@@ -25,7 +32,74 @@ impl Toast {
 #[serde(rename_all = "kebab-case", tag = "type", content = "value")]
 pub enum Message {
     Custom(String),
+    Formatted(Vec<Span>),
     SolutionCorrect,
     SolutionIncorrect,
+    PartCorrect,
+    PartAlreadySolved,
+    KeepGoing,
+    CheckSpelling,
     HintTaken,
+    CheckpointReached,
+    TooFar,
+    LowAccuracy,
+}
+
+/// A message chosen by the organizers, either plain or written in Markdown.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum CustomMessage {
+    Plain(String),
+    Markdown { markdown: Markdown },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkdownMessage {
+    markdown: Markdown,
+}
+
+impl From<CustomMessage> for Message {
+    fn from(value: CustomMessage) -> Self {
+        match value {
+            CustomMessage::Plain(text) => Message::Custom(text),
+            CustomMessage::Markdown { markdown } => Message::Formatted(markdown.spans()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MessageVisitor;
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = CustomMessage;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or an object with markdown")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(CustomMessage::Plain(v.to_owned()))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let MarkdownMessage { markdown } =
+                    MarkdownMessage::deserialize(MapAccessDeserializer::new(map))?;
+
+                Ok(CustomMessage::Markdown { markdown })
+            }
+        }
+
+        deserializer.deserialize_any(MessageVisitor)
+    }
 }
@@ -18,7 +18,7 @@ impl<'a> ViewContext<'a> {
 
     pub fn eval(&mut self, expr: &Expr) -> Result<bool, EvalError> {
         let value = self.env.eval(expr)?;
-        Ok(self.time.after(value))
+        Ok(self.time.after(&value))
     }
 }
 
@@ -38,22 +38,16 @@ impl TimeTracker {
     }
 
     #[inline]
-    pub fn after(&mut self, time: Value) -> bool {
-        match time {
-            Value::Always => true,
-            Value::Never => false,
-            Value::Since(time) => {
-                if self.current >= time {
-                    true
-                } else {
-                    self.record_change(time);
-                    false
-                }
-            }
+    pub fn after(&mut self, value: &Value) -> bool {
+        if let Some(time) = value.next_change(self.current) {
+            self.record_change(time);
         }
+
+        value.to_bool(self.current)
     }
 
-    fn record_change(&mut self, time: OffsetDateTime) {
+    /// Makes the view expire at `time`, unless it already expires sooner.
+    pub fn record_change(&mut self, time: OffsetDateTime) {
         if let Some(change) = &mut self.next_change {
             *change = min(*change, time)
         } else {
@@ -61,6 +55,11 @@ impl TimeTracker {
         }
     }
 
+    #[inline(always)]
+    pub fn current(&self) -> OffsetDateTime {
+        self.current
+    }
+
     #[inline(always)]
     pub fn valid_until(&self) -> Option<OffsetDateTime> {
         self.next_change
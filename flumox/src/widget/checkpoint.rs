@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::OffsetDateTime;
+use time_expr::{EvalError, Number};
+
+use crate::{
+    action::{ActionContext, ActionEffect, CheckIn},
+    check::Field,
+    error::{ActionResult, ViewResult},
+    expr::{Environment, Expr},
+    score::POINTS,
+    text::{collect_assets, Block, Text},
+    toast::{CustomMessage, Message},
+    view_context::ViewContext,
+    ActionError, EvalResult, Toast, ToastType,
+};
+
+/// The mean radius of the Earth in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    pub style: Style,
+    details: Text,
+    #[serde(flatten)]
+    location: Location,
+    /// How far from the location a team may check in
+    radius: Meters,
+    /// The largest uncertainty of a position which is still trusted
+    #[serde(default = "default_max_accuracy")]
+    max_accuracy: Meters,
+    visible: Expr,
+    #[serde(default = "Expr::never")]
+    disabled: Expr,
+    #[serde(default)]
+    points: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_reached: Option<CustomMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_too_far: Option<CustomMessage>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct State {
+    pub reached: Option<CheckInDetails>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct View {
+    #[serde(flatten)]
+    style: Style,
+    details: Vec<Block>,
+    disabled: bool,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    reached: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Style {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    check_in_button: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckInDetails {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: f64,
+}
+
+/// A point on the Earth, in degrees.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedLocation")]
+struct Location {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct UncheckedLocation {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Debug, Clone, Error)]
+enum LocationError {
+    #[error("latitude must be between -90 and 90")]
+    Latitude,
+    #[error("longitude must be between -180 and 180")]
+    Longitude,
+}
+
+/// A distance in meters, which is never negative.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "f64")]
+struct Meters(f64);
+
+#[derive(Debug, Clone, Error)]
+#[error("distance must be a finite number which isn't negative")]
+struct MetersError;
+
+fn default_max_accuracy() -> Meters {
+    Meters(100.0)
+}
+
+/// Computes the distance between two points on the Earth in meters using the haversine formula.
+pub(crate) fn distance((lat_a, lon_a): (f64, f64), (lat_b, lon_b): (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (lon_b - lon_a).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+impl TryFrom<UncheckedLocation> for Location {
+    type Error = LocationError;
+
+    fn try_from(value: UncheckedLocation) -> Result<Self, Self::Error> {
+        let UncheckedLocation {
+            latitude,
+            longitude,
+        } = value;
+
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(LocationError::Latitude);
+        }
+
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(LocationError::Longitude);
+        }
+
+        Ok(Location {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+impl TryFrom<f64> for Meters {
+    type Error = MetersError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if value.is_finite() && value >= 0.0 {
+            Ok(Meters(value))
+        } else {
+            Err(MetersError)
+        }
+    }
+}
+
+impl Config {
+    pub fn default_state(&self) -> State {
+        State::default()
+    }
+
+    pub fn resolve(&self, state: &State, path: &[&str], mut env: Environment) -> EvalResult {
+        match *path {
+            ["reached"] => Ok(state.reached.as_ref().map(|r| r.time).into()),
+            ["visible"] => env.eval(&self.visible),
+            ["disabled"] => env.eval(&self.disabled),
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    pub fn resolve_number(
+        &self,
+        state: &State,
+        path: &[&str],
+        env: Environment,
+    ) -> Result<Number, EvalError> {
+        match *path {
+            [POINTS] => Ok(match &state.reached {
+                Some(details) => Number::new(0, [(details.time, self.points)]),
+                None => Number::constant(0),
+            }),
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![
+            Field::state("reached"),
+            Field::expr("visible", &self.visible),
+            Field::expr("disabled", &self.disabled),
+            Field::number(POINTS),
+        ];
+
+        self.details.fields("details", &mut fields);
+
+        fields
+    }
+
+    pub fn view(&self, state: &State, mut ctx: ViewContext) -> ViewResult<View> {
+        let visible = ctx.eval(&self.visible)?;
+        let disabled = ctx.eval(&self.disabled)?;
+
+        if !visible {
+            return Ok(None);
+        }
+
+        let reached = state.reached.as_ref().map(|r| r.time);
+
+        Ok(Some(View {
+            style: self.style.clone(),
+            details: self.details.render(&mut ctx)?,
+            disabled: disabled | reached.is_some(),
+            reached,
+        }))
+    }
+
+    pub fn check_in(
+        &self,
+        state: &State,
+        action: &CheckIn,
+        mut ctx: ActionContext,
+    ) -> ActionResult<State> {
+        let visible = ctx.eval(&self.visible)?;
+        let disabled = ctx.eval(&self.disabled)?;
+
+        if !visible || disabled || state.reached.is_some() {
+            return Err(ActionError::NotPossible);
+        }
+
+        let CheckIn {
+            latitude,
+            longitude,
+            accuracy,
+        } = *action;
+
+        if !latitude.is_finite() || !longitude.is_finite() {
+            return Err(ActionError::NotPossible);
+        }
+
+        if !accuracy.is_finite() || accuracy < 0.0 || accuracy > self.max_accuracy.0 {
+            return Ok(ActionEffect::with_toast(Toast {
+                message: Message::LowAccuracy,
+                class: ToastType::Warning,
+            }));
+        }
+
+        let location = (self.location.latitude, self.location.longitude);
+        let distance = distance((latitude, longitude), location);
+
+        // The accuracy comes from the team's device, so it only decides whether
+        // the position is usable at all and doesn't widen the radius
+        if distance > self.radius.0 {
+            return Ok(ActionEffect::with_toast(Toast {
+                message: self
+                    .on_too_far
+                    .clone()
+                    .map(Message::from)
+                    .unwrap_or(Message::TooFar),
+                class: ToastType::Danger,
+            }));
+        }
+
+        let mut state = state.clone();
+
+        state.reached = Some(CheckInDetails {
+            time: ctx.time,
+            latitude,
+            longitude,
+            accuracy,
+        });
+
+        Ok(ActionEffect::new(
+            Some(state),
+            Some(Toast {
+                message: self
+                    .on_reached
+                    .clone()
+                    .map(Message::from)
+                    .unwrap_or(Message::CheckpointReached),
+                class: ToastType::Success,
+            }),
+        ))
+    }
+}
+
+impl View {
+    pub fn obsolete(&self) -> bool {
+        self.disabled
+    }
+
+    /// Returns the idents of the assets shown to the team.
+    pub fn assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        collect_assets(&self.details, &mut assets);
+
+        assets
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::OffsetDateTime;
+use time_expr::{EvalError, Number};
+
+use crate::{
+    action::{ActionContext, ActionEffect, Choice},
+    check::Field,
+    error::{ActionResult, ViewResult},
+    expr::{Environment, Expr},
+    score::POINTS,
+    text::{collect_assets, Block, Text},
+    toast::{CustomMessage, Message},
+    view_context::ViewContext,
+    ActionError, EvalResult, Toast, ToastType,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    pub style: Style,
+    details: Text,
+    #[serde(flatten)]
+    choices: Choices,
+    /// Whether the team only gets a single try
+    #[serde(default)]
+    one_shot: bool,
+    visible: Expr,
+    #[serde(default = "Expr::never")]
+    disabled: Expr,
+    #[serde(default)]
+    points: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_solution_correct: Option<CustomMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_solution_incorrect: Option<CustomMessage>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct State {
+    pub solved: Option<ChoiceDetails>,
+    /// The wrong answer of a one-shot question
+    pub failed: Option<ChoiceDetails>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct View {
+    #[serde(flatten)]
+    style: Style,
+    details: Vec<Block>,
+    options: Vec<OptionConfig>,
+    multiple: bool,
+    one_shot: bool,
+    disabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<ResultView>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Style {
+    pub name: String,
+    question: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    submit_button: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct OptionConfig {
+    ident: String,
+    text: String,
+}
+
+/// The options of a question, along with which of them are correct.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedChoices")]
+struct Choices {
+    options: Vec<OptionConfig>,
+    /// The idents of the options which have to be selected, and no others
+    correct: Vec<String>,
+    /// Whether more than one option can be selected
+    multiple: bool,
+}
+
+#[derive(Deserialize)]
+struct UncheckedChoices {
+    options: Vec<OptionConfig>,
+    correct: Vec<String>,
+    #[serde(default)]
+    multiple: bool,
+}
+
+#[derive(Debug, Clone, Error)]
+enum ChoicesError {
+    #[error("option \"{ident}\" is listed more than once")]
+    DuplicateOption { ident: String },
+    #[error("correct must list at least one option")]
+    NoneCorrect,
+    #[error("correct lists unknown option \"{ident}\"")]
+    UnknownOption { ident: String },
+    #[error("correct can only list a single option unless multiple is set")]
+    NotMultiple,
+}
+
+impl TryFrom<UncheckedChoices> for Choices {
+    type Error = ChoicesError;
+
+    fn try_from(value: UncheckedChoices) -> Result<Self, Self::Error> {
+        let UncheckedChoices {
+            options,
+            correct,
+            multiple,
+        } = value;
+
+        let mut idents = HashSet::new();
+
+        if let Some(option) = options.iter().find(|o| !idents.insert(&o.ident)) {
+            return Err(ChoicesError::DuplicateOption {
+                ident: option.ident.clone(),
+            });
+        }
+
+        if correct.is_empty() {
+            return Err(ChoicesError::NoneCorrect);
+        }
+
+        if let Some(ident) = correct
+            .iter()
+            .find(|c| !options.iter().any(|o| o.ident == **c))
+        {
+            return Err(ChoicesError::UnknownOption {
+                ident: ident.clone(),
+            });
+        }
+
+        if !multiple && correct.len() > 1 {
+            return Err(ChoicesError::NotMultiple);
+        }
+
+        Ok(Choices {
+            options,
+            correct,
+            multiple,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoiceDetails {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    pub selected: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+enum ResultView {
+    Solved { selected: Vec<String> },
+    Failed { selected: Vec<String> },
+}
+
+impl Config {
+    pub fn default_state(&self) -> State {
+        State::default()
+    }
+
+    pub fn resolve(&self, state: &State, path: &[&str], mut env: Environment) -> EvalResult {
+        match *path {
+            ["solved"] => Ok(state.solved.as_ref().map(|s| s.time).into()),
+            ["failed"] => Ok(state.failed.as_ref().map(|s| s.time).into()),
+            ["visible"] => env.eval(&self.visible),
+            ["disabled"] => env.eval(&self.disabled),
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    pub fn resolve_number(
+        &self,
+        state: &State,
+        path: &[&str],
+        env: Environment,
+    ) -> Result<Number, EvalError> {
+        match *path {
+            [POINTS] => Ok(match &state.solved {
+                Some(details) => Number::new(0, [(details.time, self.points)]),
+                None => Number::constant(0),
+            }),
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![
+            Field::state("solved"),
+            Field::state("failed"),
+            Field::expr("visible", &self.visible),
+            Field::expr("disabled", &self.disabled),
+            Field::number(POINTS),
+        ];
+
+        self.details.fields("details", &mut fields);
+
+        fields
+    }
+
+    pub fn view(&self, state: &State, mut ctx: ViewContext) -> ViewResult<View> {
+        let visible = ctx.eval(&self.visible)?;
+        let disabled = ctx.eval(&self.disabled)?;
+
+        if !visible {
+            return Ok(None);
+        }
+
+        let result = match (&state.solved, &state.failed) {
+            (Some(details), _) => Some(ResultView::Solved {
+                selected: details.selected.clone(),
+            }),
+            (None, Some(details)) => Some(ResultView::Failed {
+                selected: details.selected.clone(),
+            }),
+            (None, None) => None,
+        };
+
+        Ok(Some(View {
+            style: self.style.clone(),
+            details: self.details.render(&mut ctx)?,
+            options: self.choices.options.clone(),
+            multiple: self.choices.multiple,
+            one_shot: self.one_shot,
+            disabled: disabled | result.is_some(),
+            result,
+        }))
+    }
+
+    fn active(&self, state: &State, ctx: &mut ActionContext) -> Result<bool, EvalError> {
+        let visible = ctx.eval(&self.visible)?;
+        let disabled = ctx.eval(&self.disabled)?;
+
+        Ok(visible & state.solved.is_none() & state.failed.is_none() & !disabled)
+    }
+
+    pub fn submit_choice(
+        &self,
+        state: &State,
+        action: &Choice,
+        mut ctx: ActionContext,
+    ) -> ActionResult<State> {
+        if !self.active(state, &mut ctx)? {
+            return Err(ActionError::NotPossible);
+        }
+
+        let selected: HashSet<&str> = action.selected.iter().map(String::as_str).collect();
+
+        if selected
+            .iter()
+            .any(|s| !self.choices.options.iter().any(|o| o.ident == *s))
+        {
+            return Err(ActionError::UnknownIdent);
+        }
+
+        if selected.is_empty() || (!self.choices.multiple && selected.len() > 1) {
+            return Err(ActionError::NotPossible);
+        }
+
+        let correct: HashSet<&str> = self.choices.correct.iter().map(String::as_str).collect();
+
+        // Options are kept in the order they are listed in
+        let details = ChoiceDetails {
+            time: ctx.time,
+            selected: self
+                .choices
+                .options
+                .iter()
+                .filter(|o| selected.contains(o.ident.as_str()))
+                .map(|o| o.ident.clone())
+                .collect(),
+        };
+
+        if selected == correct {
+            let mut state = state.clone();
+            state.solved = Some(details);
+
+            Ok(ActionEffect::new(
+                Some(state),
+                Some(Toast {
+                    message: self
+                        .on_solution_correct
+                        .clone()
+                        .map(Message::from)
+                        .unwrap_or(Message::SolutionCorrect),
+                    class: ToastType::Success,
+                }),
+            ))
+        } else {
+            let state = self.one_shot.then(|| {
+                let mut state = state.clone();
+                state.failed = Some(details);
+                state
+            });
+
+            Ok(ActionEffect::new(
+                state,
+                Some(Toast {
+                    message: self
+                        .on_solution_incorrect
+                        .clone()
+                        .map(Message::from)
+                        .unwrap_or(Message::SolutionIncorrect),
+                    class: ToastType::Danger,
+                }),
+            ))
+        }
+    }
+}
+
+impl View {
+    pub fn obsolete(&self) -> bool {
+        self.disabled
+    }
+
+    /// Returns the idents of the assets shown to the team.
+    pub fn assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        collect_assets(&self.details, &mut assets);
+
+        assets
+    }
+}
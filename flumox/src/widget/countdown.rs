@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
-use time_expr::Value;
+use time_expr::{EvalError, Number};
 
 use crate::{
+    check::Field,
     error::ViewResult,
     expr::{Environment, Expr},
-    text::Text,
+    text::{collect_assets, Block, Text},
     view_context::ViewContext,
     EvalResult,
 };
@@ -25,7 +26,7 @@ pub struct State;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct View {
     name: Option<String>,
-    details: Vec<String>,
+    details: Vec<Block>,
     value: CountdownValue,
 }
 
@@ -55,6 +56,26 @@ impl Config {
         }
     }
 
+    pub fn resolve_number(
+        &self,
+        _state: &State,
+        path: &[&str],
+        env: Environment,
+    ) -> Result<Number, EvalError> {
+        Err(env.unknown_path(path))
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![
+            Field::expr("visible", &self.visible),
+            Field::expr("time", &self.time),
+        ];
+
+        self.details.fields("details", &mut fields);
+
+        fields
+    }
+
     pub fn view(&self, _state: &State, mut ctx: ViewContext) -> ViewResult<View> {
         let visible = ctx.eval(&self.visible)?;
 
@@ -64,20 +85,15 @@ impl Config {
 
         let time = ctx.env.eval(&self.time)?;
 
-        let value = match &time {
-            Value::Always => CountdownValue::Done {
+        let value = if ctx.time.after(&time) {
+            CountdownValue::Done {
                 text: self.done_text.clone(),
-            },
-            Value::Since(t) => {
-                if ctx.time.after(time) {
-                    CountdownValue::Done {
-                        text: self.done_text.clone(),
-                    }
-                } else {
-                    CountdownValue::Time { time: *t }
-                }
             }
-            Value::Never => CountdownValue::Unknown,
+        } else {
+            match time.next_change(ctx.time.current()) {
+                Some(time) => CountdownValue::Time { time },
+                None => CountdownValue::Unknown,
+            }
         };
 
         Ok(Some(View {
@@ -92,4 +108,12 @@ impl View {
     pub fn obsolete(&self) -> bool {
         matches!(self.value, CountdownValue::Done { .. })
     }
+
+    /// Returns the idents of the assets shown to the team.
+    pub fn assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        collect_assets(&self.details, &mut assets);
+
+        assets
+    }
 }
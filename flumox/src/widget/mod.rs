@@ -1,23 +1,30 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
+use time_expr::{EvalError, Number};
 
 use crate::{
+    check::Field,
     error::{StateMismatchError, ViewResult},
     expr::Environment,
     view_context::ViewContext,
     EvalResult,
 };
 
+pub mod checkpoint;
+pub mod choice;
 pub mod countdown;
 pub mod prompt;
 pub mod text;
 
 macro_rules! define_widgets {
     ($($type: ident, $module: ident);*) => {
+        /// The config of a widget, which is shared with its instances when cloned.
         #[derive(Debug, Clone, Serialize, Deserialize)]
         #[serde(rename_all = "kebab-case", tag = "type")]
         pub enum Config {
             $(
-                $type(Box<$module::Config>),
+                $type(Arc<$module::Config>),
             )*
         }
 
@@ -32,7 +39,7 @@ macro_rules! define_widgets {
         #[derive(Debug, Clone)]
         pub enum Instance {
             $(
-                $type(Box<$module::Config>, Box<$module::State>),
+                $type(Arc<$module::Config>, Box<$module::State>),
             )*
         }
 
@@ -76,6 +83,22 @@ macro_rules! define_widgets {
                 }
             }
 
+            pub fn resolve_number(&self, path: &[&str], env: Environment) -> Result<Number, EvalError> {
+                match self {
+                    $(
+                        Instance::$type(c, s) => c.resolve_number(s, path, env),
+                    )*
+                }
+            }
+
+            pub fn fields(&self) -> Vec<Field<'_>> {
+                match self {
+                    $(
+                        Instance::$type(c, _) => c.fields(),
+                    )*
+                }
+            }
+
             pub fn view(&self, ctx: ViewContext) -> ViewResult<View> {
                 let view = match self {
                     $(
@@ -95,6 +118,14 @@ macro_rules! define_widgets {
                     )*
                 }
             }
+
+            pub fn assets(&self) -> Vec<&str> {
+                match self {
+                    $(
+                        View::$type(v) => v.assets(),
+                    )*
+                }
+            }
         }
     };
 }
@@ -102,5 +133,7 @@ macro_rules! define_widgets {
 define_widgets!(
     Prompt, prompt;
     Text, text;
-    Countdown, countdown
+    Countdown, countdown;
+    Choice, choice;
+    Checkpoint, checkpoint
 );
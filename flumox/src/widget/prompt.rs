@@ -1,16 +1,22 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use time::{Duration, OffsetDateTime};
-use time_expr::{EvalError, Value};
+use time_expr::{EvalError, Number, Value};
 
 use crate::{
     action::{ActionContext, ActionEffect, Answer, Hint},
+    check::Field,
     error::{ActionResult, ViewResult},
     expr::{Environment, Expr},
+    score::POINTS,
     solution::Solution,
-    text::Text,
-    toast::Message,
+    text::{collect_assets, Block, Text},
+    toast::{CustomMessage, Message},
     view_context::ViewContext,
     ActionError, EvalResult, Instance, Toast, ToastType,
 };
@@ -20,16 +26,29 @@ pub struct Config {
     #[serde(flatten)]
     pub style: Style,
     details: Text,
+    #[serde(default)]
     solutions: Vec<Solution>,
+    #[serde(flatten)]
+    parts: Parts,
     visible: Expr,
     #[serde(default = "Expr::never")]
     disabled: Expr,
     #[serde(default)]
     hints: Vec<HintConfig>,
+    #[serde(default)]
+    milestones: Vec<MilestoneConfig>,
+    #[serde(default)]
+    points: i64,
+    #[serde(default)]
+    bonuses: Vec<BonusConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_attempts: Option<NonZeroUsize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    on_solution_correct: Option<String>,
+    cooldown: Option<CooldownConfig>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    on_solution_incorrect: Option<String>,
+    on_solution_correct: Option<CustomMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_solution_incorrect: Option<CustomMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     solution_exclusion_group: Option<String>,
 }
@@ -38,19 +57,39 @@ pub struct Config {
 pub struct State {
     pub solved: Option<SolutionDetails>,
     pub hints: HashMap<String, OffsetDateTime>,
+    /// Wrong answers submitted by the team, oldest first
+    #[serde(default)]
+    pub attempts: Vec<Attempt>,
+    /// Milestones reached by the team, if they're recorded
+    #[serde(default)]
+    pub milestones: HashMap<String, OffsetDateTime>,
+    /// Parts of the prompt solved so far
+    #[serde(default)]
+    pub parts: HashMap<String, SolutionDetails>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct View {
     #[serde(flatten)]
     style: Style,
-    details: Vec<String>,
+    details: Vec<Block>,
     disabled: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     solution: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parts: Vec<PartView>,
     hints: Vec<HintView>,
     #[serde(skip_serializing_if = "Option::is_none")]
     time: Option<TimeView>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attempts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempts_left: Option<usize>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    cooldown: Option<OffsetDateTime>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -68,18 +107,109 @@ pub struct SolutionDetails {
     pub canonical_text: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attempt {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The answer itself, which is only kept for the latest few attempts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+}
+
+/// How many of the latest wrong answers are kept and shown to the team.
+/// Older attempts only keep their time, which expressions can refer to.
+const KEPT_ANSWERS: usize = 10;
+
+/// Stops the team from answering for `seconds` after every `after` wrong answers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CooldownConfig {
+    after: NonZeroUsize,
+    seconds: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct HintConfig {
     ident: String,
     name: String,
-    content: Vec<String>,
+    content: Text,
     available: Expr,
     #[serde(default = "Expr::always")]
     visible: Expr,
+    #[serde(default)]
+    penalty: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     take_button: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    on_hint_taken: Option<String>,
+    on_hint_taken: Option<CustomMessage>,
+}
+
+/// The parts of a prompt, along with how many of them need to be solved.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedParts")]
+struct Parts {
+    #[serde(rename = "parts")]
+    list: Vec<PartConfig>,
+    /// How many parts need to be solved to solve the prompt, all of them by default
+    #[serde(rename = "required_parts", skip_serializing_if = "Option::is_none")]
+    required: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedParts {
+    #[serde(default)]
+    parts: Vec<PartConfig>,
+    #[serde(default)]
+    required_parts: Option<usize>,
+}
+
+#[derive(Debug, Clone, Error)]
+enum PartsError {
+    #[error("required_parts can only be set along with parts")]
+    NoParts,
+    #[error("required_parts must be between 1 and the number of parts, {parts}")]
+    OutOfRange { parts: usize },
+}
+
+/// One of several answers needed to solve a prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PartConfig {
+    ident: String,
+    name: String,
+    solutions: Vec<Solution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct PartView {
+    ident: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution: Option<String>,
+}
+
+/// An intermediate answer, which earns the team a nudge instead of being rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MilestoneConfig {
+    ident: String,
+    solutions: Vec<Solution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<CustomMessage>,
+    /// Whether to remember when the milestone was first reached
+    #[serde(default)]
+    record: bool,
+}
+
+/// What an answer was found to solve.
+enum Match<'a> {
+    Solution(&'a Solution),
+    Part(&'a PartConfig, &'a Solution),
+    Milestone(&'a MilestoneConfig),
+}
+
+/// Extra points awarded if the prompt is solved while `available` holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BonusConfig {
+    points: i64,
+    available: Expr,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -103,7 +233,7 @@ enum HintStateView {
         button: Option<String>,
     },
     Taken {
-        content: Vec<String>,
+        content: Vec<Block>,
     },
 }
 
@@ -119,6 +249,39 @@ enum TimeView {
     },
 }
 
+impl TryFrom<UncheckedParts> for Parts {
+    type Error = PartsError;
+
+    fn try_from(value: UncheckedParts) -> Result<Self, Self::Error> {
+        let UncheckedParts {
+            parts,
+            required_parts,
+        } = value;
+
+        if let Some(required) = required_parts {
+            if parts.is_empty() {
+                return Err(PartsError::NoParts);
+            }
+
+            if required == 0 || required > parts.len() {
+                return Err(PartsError::OutOfRange { parts: parts.len() });
+            }
+        }
+
+        Ok(Parts {
+            list: parts,
+            required: required_parts,
+        })
+    }
+}
+
+impl Parts {
+    /// How many parts need to be solved to solve the prompt.
+    fn required(&self) -> usize {
+        self.required.unwrap_or(self.list.len())
+    }
+}
+
 impl Config {
     pub fn default_state(&self) -> State {
         State::default()
@@ -141,18 +304,132 @@ impl Config {
                 .find(|h| h.ident == hint)
                 .ok_or_else(|| env.unknown_path(path))
                 .and_then(|h| env.eval(&h.visible)),
-            ["hint", hint, "taken"] => state
-                .hints
-                .get(hint)
-                .map(|time| Ok(Value::Since(*time)))
-                .unwrap_or(Ok(Value::Never)),
+            ["hint", hint, "taken"] => Ok(state.hints.get(hint).copied().into()),
+            ["part", part, "solved"] => Ok(state.parts.get(part).map(|p| p.time).into()),
+            ["milestone", milestone] => Ok(state.milestones.get(milestone).copied().into()),
+            ["attempts", n] => {
+                let n: usize = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| env.unknown_path(path))?;
+
+                Ok(state.attempts.get(n - 1).map(|a| a.time).into())
+            }
+            _ => Err(env.unknown_path(path)),
+        }
+    }
+
+    pub fn resolve_number(
+        &self,
+        state: &State,
+        path: &[&str],
+        env: Environment,
+    ) -> Result<Number, EvalError> {
+        match *path {
+            ["hints", "taken"] => {
+                let taken: Vec<Value> = state.hints.values().map(|&t| Value::since(t)).collect();
+                let taken: Vec<&Value> = taken.iter().collect();
+
+                Ok(Number::count(&taken))
+            }
+            ["parts", "solved"] => {
+                let solved: Vec<Value> =
+                    state.parts.values().map(|p| Value::since(p.time)).collect();
+                let solved: Vec<&Value> = solved.iter().collect();
+
+                Ok(Number::count(&solved))
+            }
+            ["attempts"] => {
+                let made: Vec<Value> = state
+                    .attempts
+                    .iter()
+                    .map(|a| Value::since(a.time))
+                    .collect();
+                let made: Vec<&Value> = made.iter().collect();
+
+                Ok(Number::count(&made))
+            }
+            [POINTS] => self.points(state, env),
             _ => Err(env.unknown_path(path)),
         }
     }
 
+    /// Computes the points awarded for solving the prompt, minus penalties for hints taken.
+    fn points(&self, state: &State, mut env: Environment) -> Result<Number, EvalError> {
+        let mut awarded = Vec::new();
+
+        for hint in &self.hints {
+            if let Some(&time) = state.hints.get(&hint.ident) {
+                awarded.push(Number::new(0, [(time, -hint.penalty)]));
+            }
+        }
+
+        if let Some(SolutionDetails { time, .. }) = &state.solved {
+            let mut points = self.points;
+
+            for bonus in &self.bonuses {
+                if env.eval(&bonus.available)?.to_bool(*time) {
+                    points += bonus.points;
+                }
+            }
+
+            awarded.push(Number::new(0, [(*time, points)]));
+        }
+
+        let awarded: Vec<&Number> = awarded.iter().collect();
+        Ok(Number::sum(&awarded))
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![
+            Field::state("solved"),
+            Field::expr("visible", &self.visible),
+            Field::expr("disabled", &self.disabled),
+            Field::number("hints.taken"),
+            Field::state("attempts.#"),
+            Field::number("attempts"),
+            Field::number(POINTS).using(self.bonuses.iter().map(|b| &b.available)),
+        ];
+
+        self.details.fields("details", &mut fields);
+
+        for hint in &self.hints {
+            let prefix = format!("hint.{}", hint.ident);
+
+            fields.push(Field::expr(format!("{prefix}.available"), &hint.available));
+            fields.push(Field::expr(format!("{prefix}.visible"), &hint.visible));
+            fields.push(Field::state(format!("{prefix}.taken")));
+
+            hint.content
+                .fields(&format!("{prefix}.content"), &mut fields);
+        }
+
+        if !self.parts.list.is_empty() {
+            fields.push(Field::number("parts.solved"));
+        }
+
+        for part in &self.parts.list {
+            fields.push(Field::state(format!("part.{}.solved", part.ident)));
+        }
+
+        for milestone in self.milestones.iter().filter(|m| m.record) {
+            fields.push(Field::state(format!("milestone.{}", milestone.ident)));
+        }
+
+        for (i, bonus) in self.bonuses.iter().enumerate() {
+            fields.push(Field::internal(
+                format!("bonus.{i}.available"),
+                &bonus.available,
+            ));
+        }
+
+        fields
+    }
+
     pub fn view(&self, state: &State, mut ctx: ViewContext) -> ViewResult<View> {
         let visible_since = ctx.env.eval(&self.visible)?;
-        let visible = ctx.time.after(visible_since);
+        let visible = ctx.time.after(&visible_since);
         let disabled = ctx.eval(&self.disabled)?;
 
         if !visible {
@@ -160,6 +437,16 @@ impl Config {
         }
 
         let solved = state.solved.is_some();
+        let attempts_left = self.attempts_left(state);
+        let exhausted = attempts_left == Some(0);
+
+        let cooldown = self
+            .cooldown_end(state)
+            .filter(|&end| !solved && end > ctx.time.current());
+
+        if let Some(end) = cooldown {
+            ctx.time.record_change(end);
+        }
 
         let mut hints = Vec::new();
 
@@ -170,25 +457,20 @@ impl Config {
 
             let state = if solved || state.hints.contains_key(&hint.ident) {
                 HintStateView::Taken {
-                    content: hint.content.clone(),
+                    content: hint.content.render(&mut ctx)?,
                 }
             } else {
                 let available_time = ctx.env.eval(&hint.available)?;
 
-                match available_time {
-                    Value::Always => HintStateView::Available {
+                if ctx.time.after(&available_time) {
+                    HintStateView::Available {
                         button: hint.take_button.to_owned(),
-                    },
-                    Value::Since(time) => {
-                        if ctx.time.after(available_time) {
-                            HintStateView::Available {
-                                button: hint.take_button.to_owned(),
-                            }
-                        } else {
-                            HintStateView::Future { time }
-                        }
                     }
-                    Value::Never => HintStateView::Unknown,
+                } else {
+                    match available_time.next_change(ctx.time.current()) {
+                        Some(time) => HintStateView::Future { time },
+                        None => HintStateView::Unknown,
+                    }
                 }
             };
 
@@ -199,23 +481,45 @@ impl Config {
             });
         }
 
-        let time = match (visible_since, &state.solved) {
-            (Value::Always | Value::Never, _) => None,
-            (Value::Since(since), None) => Some(TimeView::Solving { since }),
-            (Value::Since(since), Some(SolutionDetails { time: until, .. })) => {
-                Some(TimeView::Solved {
-                    after: *until - since,
-                })
+        let time = match &state.solved {
+            None => visible_since
+                .last_change(ctx.time.current())
+                .map(|since| TimeView::Solving { since }),
+            Some(SolutionDetails { time: until, .. }) => {
+                visible_since
+                    .last_change(*until)
+                    .map(|since| TimeView::Solved {
+                        after: *until - since,
+                    })
             }
         };
 
         Ok(Some(View {
             style: self.style.clone(),
             details: self.details.render(&mut ctx)?,
-            disabled: solved | disabled,
+            disabled: solved | disabled | exhausted,
             solution: state.solved.as_ref().map(|s| s.canonical_text.clone()),
+            parts: self
+                .parts
+                .list
+                .iter()
+                .map(|part| PartView {
+                    ident: part.ident.clone(),
+                    name: part.name.clone(),
+                    solution: state
+                        .parts
+                        .get(&part.ident)
+                        .map(|p| p.canonical_text.clone()),
+                })
+                .collect(),
             hints,
             time,
+            attempts: state.attempts[state.attempts.len().saturating_sub(KEPT_ANSWERS)..]
+                .iter()
+                .filter_map(|a| a.answer.clone())
+                .collect(),
+            attempts_left: attempts_left.filter(|_| !solved),
+            cooldown,
         }))
     }
 
@@ -223,7 +527,137 @@ impl Config {
         let visible = ctx.eval(&self.visible)?;
         let disabled = ctx.eval(&self.disabled)?;
 
-        Ok(visible & state.solved.is_none() & !disabled)
+        let exhausted = self.attempts_left(state) == Some(0);
+
+        Ok(visible & state.solved.is_none() & !disabled & !exhausted)
+    }
+
+    fn attempts_left(&self, state: &State) -> Option<usize> {
+        self.max_attempts
+            .map(|max| max.get().saturating_sub(state.attempts.len()))
+    }
+
+    /// Returns when the team may answer again after its latest wrong answer,
+    /// if that answer started a cooldown.
+    fn cooldown_end(&self, state: &State) -> Option<OffsetDateTime> {
+        let cooldown = self.cooldown?;
+        let last = state.attempts.last()?;
+
+        (state.attempts.len() % cooldown.after.get() == 0)
+            .then(|| last.time + Duration::seconds(cooldown.seconds.into()))
+    }
+
+    fn correct(&self) -> Toast {
+        Toast {
+            message: self
+                .on_solution_correct
+                .clone()
+                .map(Message::from)
+                .unwrap_or(Message::SolutionCorrect),
+            class: ToastType::Success,
+        }
+    }
+
+    /// Records a solved part, solving the whole prompt once enough parts are solved.
+    fn solve_part(
+        &self,
+        state: &State,
+        part: &PartConfig,
+        canonical_text: String,
+        time: OffsetDateTime,
+    ) -> ActionResult<State> {
+        let mut state = state.clone();
+
+        state.parts.insert(
+            part.ident.clone(),
+            SolutionDetails {
+                time,
+                canonical_text,
+            },
+        );
+
+        let required = self.parts.required();
+
+        if state.parts.len() < required {
+            return Ok(ActionEffect::new(
+                Some(state),
+                Some(Toast {
+                    message: Message::PartCorrect,
+                    class: ToastType::Success,
+                }),
+            ));
+        }
+
+        let canonical_text = self
+            .parts
+            .list
+            .iter()
+            .filter_map(|p| state.parts.get(&p.ident))
+            .map(|p| p.canonical_text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        state.solved = Some(SolutionDetails {
+            time,
+            canonical_text,
+        });
+
+        Ok(ActionEffect::new(Some(state), Some(self.correct())))
+    }
+
+    /// Finds what the answer solves, in the order of the solutions, parts and milestones.
+    fn find_match(&self, answer: &str, typos: bool) -> Option<Match<'_>> {
+        if let Some(solution) = Solution::find(&self.solutions, answer, typos) {
+            return Some(Match::Solution(solution));
+        }
+
+        let part = self.parts.list.iter().find_map(|p| {
+            Solution::find(&p.solutions, answer, typos).map(|solution| Match::Part(p, solution))
+        });
+
+        part.or_else(|| {
+            self.milestones
+                .iter()
+                .find(|m| Solution::find(&m.solutions, answer, typos).is_some())
+                .map(Match::Milestone)
+        })
+    }
+
+    /// Whether the answer is a few typos away from the solution or a part.
+    fn close(&self, answer: &str) -> bool {
+        let parts = self.parts.list.iter().flat_map(|p| &p.solutions);
+        self.solutions.iter().chain(parts).any(|s| s.close(answer))
+    }
+
+    /// Records a wrong answer.
+    fn incorrect(
+        &self,
+        state: &State,
+        action: &Answer,
+        time: OffsetDateTime,
+    ) -> ActionResult<State> {
+        let mut state = state.clone();
+        state.attempts.push(Attempt {
+            time,
+            answer: Some(action.answer.clone()),
+        });
+
+        let forgotten = state.attempts.len().saturating_sub(KEPT_ANSWERS);
+        for attempt in &mut state.attempts[..forgotten] {
+            attempt.answer = None;
+        }
+
+        Ok(ActionEffect::new(
+            Some(state),
+            Some(Toast {
+                message: self
+                    .on_solution_incorrect
+                    .clone()
+                    .map(Message::from)
+                    .unwrap_or(Message::SolutionIncorrect),
+                class: ToastType::Danger,
+            }),
+        ))
     }
 
     pub fn submit_answer(
@@ -236,6 +670,10 @@ impl Config {
             return Err(ActionError::NotPossible);
         }
 
+        if self.cooldown_end(state).is_some_and(|end| end > ctx.time) {
+            return Err(ActionError::NotPossible);
+        }
+
         let mut banned = HashSet::new();
 
         if let Some(group) = self.solution_exclusion_group.as_ref() {
@@ -249,54 +687,86 @@ impl Config {
                         if let Some(solution) = &state.solved {
                             banned.insert(solution.canonical_text.as_str());
                         }
+
+                        for part in state.parts.values() {
+                            banned.insert(part.canonical_text.as_str());
+                        }
                     }
                 }
             }
         }
 
-        if let Some(solution) = self.solutions.iter().find(|s| s.check(&action.answer)) {
-            let canonical_text = solution
-                .canonical_answer()
-                .unwrap_or_else(|| action.answer.to_owned());
-
-            if banned.contains(canonical_text.as_str()) {
-                return Ok(ActionEffect::with_toast(Toast {
-                    message: self
-                        .on_solution_incorrect
-                        .clone()
-                        .map(Message::Custom)
-                        .unwrap_or(Message::SolutionIncorrect),
-                    class: ToastType::Danger,
-                }));
+        let answer = &action.answer;
+
+        // Typos are only allowed once nothing matches without them, so that a part or
+        // a milestone close to the solution isn't taken for it
+        let found = self
+            .find_match(answer, false)
+            .or_else(|| self.find_match(answer, true));
+
+        match found {
+            Some(Match::Solution(solution)) => {
+                let canonical_text = solution
+                    .canonical_answer()
+                    .unwrap_or_else(|| answer.to_owned());
+
+                if banned.contains(canonical_text.as_str()) {
+                    return self.incorrect(state, action, ctx.time);
+                }
+
+                let mut state = state.clone();
+
+                state.solved = Some(SolutionDetails {
+                    time: ctx.time,
+                    canonical_text,
+                });
+
+                Ok(ActionEffect::new(Some(state), Some(self.correct())))
             }
+            Some(Match::Part(part, solution)) => {
+                if state.parts.contains_key(&part.ident) {
+                    return Ok(ActionEffect::with_toast(Toast {
+                        message: Message::PartAlreadySolved,
+                        class: ToastType::Warning,
+                    }));
+                }
 
-            let mut state = state.clone();
+                let canonical_text = solution
+                    .canonical_answer()
+                    .unwrap_or_else(|| answer.to_owned());
 
-            state.solved = Some(SolutionDetails {
-                time: ctx.time,
-                canonical_text,
-            });
+                if banned.contains(canonical_text.as_str()) {
+                    return self.incorrect(state, action, ctx.time);
+                }
 
-            Ok(ActionEffect::new(
-                Some(state),
-                Some(Toast {
-                    message: self
-                        .on_solution_correct
-                        .clone()
-                        .map(Message::Custom)
-                        .unwrap_or(Message::SolutionCorrect),
-                    class: ToastType::Success,
-                }),
-            ))
-        } else {
-            return Ok(ActionEffect::with_toast(Toast {
-                message: self
-                    .on_solution_incorrect
-                    .clone()
-                    .map(Message::Custom)
-                    .unwrap_or(Message::SolutionIncorrect),
-                class: ToastType::Danger,
-            }));
+                self.solve_part(state, part, canonical_text, ctx.time)
+            }
+            Some(Match::Milestone(milestone)) => {
+                let new_state = (milestone.record
+                    && !state.milestones.contains_key(&milestone.ident))
+                .then(|| {
+                    let mut state = state.clone();
+                    state.milestones.insert(milestone.ident.clone(), ctx.time);
+                    state
+                });
+
+                Ok(ActionEffect::new(
+                    new_state,
+                    Some(Toast {
+                        message: milestone
+                            .message
+                            .clone()
+                            .map(Message::from)
+                            .unwrap_or(Message::KeepGoing),
+                        class: ToastType::Warning,
+                    }),
+                ))
+            }
+            None if self.close(answer) => Ok(ActionEffect::with_toast(Toast {
+                message: Message::CheckSpelling,
+                class: ToastType::Warning,
+            })),
+            None => self.incorrect(state, action, ctx.time),
         }
     }
 
@@ -340,7 +810,7 @@ impl Config {
                 message: hint
                     .on_hint_taken
                     .clone()
-                    .map(Message::Custom)
+                    .map(Message::from)
                     .unwrap_or(Message::HintTaken),
                 class: ToastType::Success,
             }),
@@ -352,4 +822,18 @@ impl View {
     pub fn obsolete(&self) -> bool {
         self.disabled
     }
+
+    /// Returns the idents of the assets shown to the team.
+    pub fn assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        collect_assets(&self.details, &mut assets);
+
+        for hint in &self.hints {
+            if let HintStateView::Taken { content } = &hint.state {
+                collect_assets(content, &mut assets);
+            }
+        }
+
+        assets
+    }
 }
@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
+use time_expr::{EvalError, Number};
 
 use crate::{
+    check::Field,
     error::ViewResult,
     expr::{Environment, Expr},
-    text::Text,
+    text::{collect_assets, Block, Text},
     view_context::ViewContext,
     EvalResult,
 };
@@ -23,7 +25,7 @@ pub struct State;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct View {
     heading: Option<String>,
-    content: Vec<String>,
+    content: Vec<Block>,
     #[serde(skip)]
     obsolete: bool,
 }
@@ -36,11 +38,31 @@ impl Config {
     pub fn resolve(&self, _state: &State, path: &[&str], mut env: Environment) -> EvalResult {
         match *path {
             ["visible"] => env.eval(&self.visible),
-            ["obsolete"] => env.eval(&self.visible),
+            ["obsolete"] => env.eval(&self.obsolete),
             _ => Err(env.unknown_path(path)),
         }
     }
 
+    pub fn resolve_number(
+        &self,
+        _state: &State,
+        path: &[&str],
+        env: Environment,
+    ) -> Result<Number, EvalError> {
+        Err(env.unknown_path(path))
+    }
+
+    pub fn fields(&self) -> Vec<Field<'_>> {
+        let mut fields = vec![
+            Field::expr("visible", &self.visible),
+            Field::expr("obsolete", &self.obsolete),
+        ];
+
+        self.content.fields("content", &mut fields);
+
+        fields
+    }
+
     pub fn view(&self, _state: &State, mut ctx: ViewContext) -> ViewResult<View> {
         let visible = ctx.eval(&self.visible)?;
         let obsolete = ctx.eval(&self.obsolete)?;
@@ -61,4 +83,12 @@ impl View {
     pub fn obsolete(&self) -> bool {
         self.obsolete
     }
+
+    /// Returns the idents of the assets shown to the team.
+    pub fn assets(&self) -> Vec<&str> {
+        let mut assets = Vec::new();
+        collect_assets(&self.content, &mut assets);
+
+        assets
+    }
 }
@@ -0,0 +1,288 @@
+use std::fmt::{self, Display, Formatter};
+
+use time::{Date, Duration, OffsetDateTime, Time, UtcOffset};
+
+use crate::{
+    expr::{Day, Expr, NumExpr},
+    Value, Zone,
+};
+
+/// How tightly an expression binds, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Until,
+    Offset,
+    Or,
+    And,
+    Not,
+    Terminal,
+}
+
+/// Formats an expression in canonical form, which parses back to the same expression.
+///
+/// Only the parentheses needed to preserve the structure of the expression are included.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal { value } => write_value(f, value),
+            Expr::Local {
+                day, time, zone, ..
+            } => {
+                match day {
+                    Day::Date(date) => write_date(f, *date)?,
+                    Day::Today => write!(f, "today")?,
+                    Day::Tomorrow => write!(f, "tomorrow")?,
+                }
+
+                write!(f, " ")?;
+                write_time(f, *time)?;
+
+                match zone {
+                    Some(zone) => write!(f, " {zone}"),
+                    None => Ok(()),
+                }
+            }
+            Expr::Field { path, .. } => write!(f, "{}", path.join(".")),
+            Expr::And { left, right } => {
+                write_operand(f, left, Precedence::And)?;
+                write!(f, " & ")?;
+                write_operand(f, right, Precedence::Not)
+            }
+            Expr::Or { left, right } => {
+                write_operand(f, left, Precedence::Or)?;
+                write!(f, " | ")?;
+                write_operand(f, right, Precedence::And)
+            }
+            Expr::Until { left, right } => {
+                write_operand(f, left, Precedence::Until)?;
+                write!(f, " until ")?;
+                write_operand(f, right, Precedence::Offset)
+            }
+            Expr::Not { value } => {
+                write!(f, "!")?;
+                write_operand(f, value, Precedence::Not)
+            }
+            Expr::AtLeast { count, values } => {
+                write!(f, "{count} of ")?;
+                write_list(f, values)
+            }
+            Expr::All { values } => write_function(f, "all", values),
+            Expr::Any { values } => write_function(f, "any", values),
+            Expr::Min { values } => write_function(f, "min", values),
+            Expr::Max { values } => write_function(f, "max", values),
+            Expr::Add { value, duration } => write_offset(f, value, *duration),
+            Expr::Sub { value, duration } => write_offset(f, value, -*duration),
+            Expr::Compare { left, op, right } => write!(f, "{left} {op} {right}"),
+        }
+    }
+}
+
+impl Display for NumExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NumExpr::Literal { value } => write!(f, "{value}"),
+            NumExpr::Field { path, .. } => write!(f, "{}", path.join(".")),
+            NumExpr::Count { values } => write_function(f, "count", values),
+        }
+    }
+}
+
+impl Display for Zone {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Offset(offset) => write_offset_zone(f, *offset),
+            Zone::Named(_) => write!(f, "{}", self.name().unwrap_or_default()),
+        }
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> Precedence {
+        match self {
+            Expr::Literal { value } => match value_expr(value) {
+                Some(expr) => expr.precedence(),
+                None => Precedence::Terminal,
+            },
+            Expr::Until { .. } => Precedence::Until,
+            Expr::Add { .. } | Expr::Sub { .. } => Precedence::Offset,
+            Expr::Or { .. } => Precedence::Or,
+            Expr::And { .. } => Precedence::And,
+            Expr::Not { .. } => Precedence::Not,
+            Expr::Local { .. }
+            | Expr::Field { .. }
+            | Expr::AtLeast { .. }
+            | Expr::All { .. }
+            | Expr::Any { .. }
+            | Expr::Min { .. }
+            | Expr::Max { .. }
+            | Expr::Compare { .. } => Precedence::Terminal,
+        }
+    }
+}
+
+/// Writes an operand, wrapping it in parentheses if it binds looser than `min`.
+fn write_operand(f: &mut Formatter<'_>, expr: &Expr, min: Precedence) -> fmt::Result {
+    if expr.precedence() < min {
+        write!(f, "({expr})")
+    } else {
+        write!(f, "{expr}")
+    }
+}
+
+fn write_list(f: &mut Formatter<'_>, values: &[Expr]) -> fmt::Result {
+    write!(f, "(")?;
+
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        write!(f, "{value}")?;
+    }
+
+    write!(f, ")")
+}
+
+fn write_function(f: &mut Formatter<'_>, name: &str, values: &[Expr]) -> fmt::Result {
+    write!(f, "{name}")?;
+    write_list(f, values)
+}
+
+fn write_offset(f: &mut Formatter<'_>, value: &Expr, duration: Duration) -> fmt::Result {
+    write_operand(f, value, Precedence::Offset)?;
+
+    if duration.is_negative() {
+        write!(f, " - ")?;
+    } else {
+        write!(f, " + ")?;
+    }
+
+    write_duration(f, duration.abs())
+}
+
+fn write_duration(f: &mut Formatter<'_>, duration: Duration) -> fmt::Result {
+    let units = [
+        (duration.whole_days(), "d"),
+        (duration.whole_hours() % 24, "h"),
+        (duration.whole_minutes() % 60, "m"),
+        (duration.whole_seconds() % 60, "s"),
+        (i64::from(duration.subsec_milliseconds()), "ms"),
+    ];
+
+    let mut written = false;
+
+    for (amount, unit) in units {
+        if amount != 0 {
+            if written {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{amount} {unit}")?;
+            written = true;
+        }
+    }
+
+    if !written {
+        write!(f, "0 s")?;
+    }
+
+    Ok(())
+}
+
+fn write_date(f: &mut Formatter<'_>, date: Date) -> fmt::Result {
+    write!(
+        f,
+        "{:04}-{:02}-{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+fn write_time(f: &mut Formatter<'_>, time: Time) -> fmt::Result {
+    write!(f, "{:02}:{:02}", time.hour(), time.minute())?;
+
+    if time.second() != 0 {
+        write!(f, ":{:02}", time.second())?;
+    }
+
+    Ok(())
+}
+
+fn write_offset_zone(f: &mut Formatter<'_>, offset: UtcOffset) -> fmt::Result {
+    let sign = if offset.is_negative() { '-' } else { '+' };
+    let (hours, minutes, seconds) = offset.as_hms();
+
+    write!(
+        f,
+        "{sign}{:02}:{:02}",
+        hours.unsigned_abs(),
+        minutes.unsigned_abs()
+    )?;
+
+    if seconds != 0 {
+        write!(f, ":{:02}", seconds.unsigned_abs())?;
+    }
+
+    Ok(())
+}
+
+fn write_instant(f: &mut Formatter<'_>, time: OffsetDateTime) -> fmt::Result {
+    write_date(f, time.date())?;
+    write!(f, " ")?;
+    write_time(f, time.time())?;
+    write!(f, " ")?;
+    write_offset_zone(f, time.offset())
+}
+
+fn write_value(f: &mut Formatter<'_>, value: &Value) -> fmt::Result {
+    match value_expr(value) {
+        Some(expr) => write!(f, "{expr}"),
+        None => match (value.initial(), value.changes()) {
+            (true, []) => write!(f, "always"),
+            (false, []) => write!(f, "never"),
+            (_, [time, ..]) => write_instant(f, *time),
+        },
+    }
+}
+
+/// Builds an expression equivalent to a value which can't be written as a single literal.
+fn value_expr(value: &Value) -> Option<Expr> {
+    let single = |time: OffsetDateTime| Expr::Literal {
+        value: Value::since(time),
+    };
+
+    match (value.initial(), value.changes()) {
+        (_, []) | (false, [_]) => None,
+        (true, [time]) => Some(Expr::Not {
+            value: Box::new(single(*time)),
+        }),
+        (initial, changes) => {
+            let mut windows = Vec::new();
+            let mut changes = changes.iter().copied();
+
+            if initial {
+                if let Some(end) = changes.next() {
+                    windows.push(Expr::Not {
+                        value: Box::new(single(end)),
+                    });
+                }
+            }
+
+            while let Some(start) = changes.next() {
+                windows.push(match changes.next() {
+                    Some(end) => Expr::Until {
+                        left: Box::new(single(start)),
+                        right: Box::new(single(end)),
+                    },
+                    None => single(start),
+                });
+            }
+
+            windows.into_iter().reduce(|left, right| Expr::Or {
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+        }
+    }
+}
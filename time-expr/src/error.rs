@@ -4,33 +4,124 @@ use thiserror::Error;
 
 use crate::parse::TokenType;
 
+/// A range of bytes in the source of an expression.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Returns a span covering both spans and everything between them.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum EvalError {
     #[error("unknown path \"{path}\"")]
-    UnknownPath { path: Box<str> },
+    UnknownPath { path: Box<str>, span: Option<Span> },
     #[error("circular dependency: \"{path}\" has itself as a dependency")]
-    CircularDependency { path: Box<str> },
+    CircularDependency { path: Box<str>, span: Option<Span> },
 
-    #[error("unexpected char '{char}'", char=.char.escape_default())]
-    UnknownChar { char: char },
-    #[error("unexpected {token} at {pos}", pos=PosOrEnd(*.pos))]
+    #[error("unexpected char '{char}' at {pos}", char=.char.escape_default(), pos=.span.start)]
+    UnknownChar { char: char, span: Span },
+    #[error("unexpected {token} at {pos}", pos=PosOrEnd(*.span))]
     UnexpectedToken {
         token: TokenType,
-        pos: Option<usize>,
+        span: Option<Span>,
+    },
+    #[error("literal out of range at {pos}", pos=.span.start)]
+    LiteralOutOfRange { span: Span },
+    #[error("unknown unit \"{unit}\" at {pos}", pos=.span.start)]
+    UnknownUnit { unit: Box<str>, span: Span },
+    #[error("unknown time zone \"{zone}\" at {pos}", pos=.span.start)]
+    UnknownZone { zone: Box<str>, span: Span },
+
+    #[error("time literal has no time zone and there is no default")]
+    NoDefaultZone { span: Span },
+    #[error("relative time literal used without a current time")]
+    NoCurrentTime { span: Span },
+
+    /// An error in an expression other than the one being evaluated,
+    /// such as one defining a path it refers to
+    #[error("in {}: {error}", Location(module, field.as_deref()))]
+    InExpr {
+        module: Box<str>,
+        field: Option<Box<str>>,
+        expr: Box<str>,
+        error: Box<EvalError>,
     },
-    #[error("literal out of range at {pos}")]
-    LiteralOutOfRange { pos: usize },
-    #[error("unknown unit \"{unit}\"")]
-    UnknownUnit { unit: Box<str> },
 }
 
-struct PosOrEnd(Option<usize>);
+impl EvalError {
+    /// Returns the part of the expression the error refers to, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvalError::UnknownPath { span, .. }
+            | EvalError::CircularDependency { span, .. }
+            | EvalError::UnexpectedToken { span, .. } => *span,
+            EvalError::UnknownChar { span, .. }
+            | EvalError::LiteralOutOfRange { span }
+            | EvalError::UnknownUnit { span, .. }
+            | EvalError::UnknownZone { span, .. }
+            | EvalError::NoDefaultZone { span }
+            | EvalError::NoCurrentTime { span } => Some(*span),
+            EvalError::InExpr { .. } => None,
+        }
+    }
+
+    /// Sets the span of errors about paths which don't know where the path appeared.
+    pub(crate) fn with_path_span(mut self, new_span: Span) -> Self {
+        if let EvalError::UnknownPath { span, .. } | EvalError::CircularDependency { span, .. } =
+            &mut self
+        {
+            span.get_or_insert(new_span);
+        }
+
+        self
+    }
+
+    /// Attaches the expression and the field it came from, unless the error already has one.
+    pub fn in_expr(self, module: &str, field: Option<&str>, expr: &str) -> Self {
+        match self {
+            EvalError::InExpr { .. } => self,
+            error => EvalError::InExpr {
+                module: module.into(),
+                field: field.map(Into::into),
+                expr: expr.into(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+struct PosOrEnd(Option<Span>);
 
 impl Display for PosOrEnd {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
         match self.0 {
-            Some(pos) => pos.fmt(f),
+            Some(span) => span.start.fmt(f),
             None => write!(f, "end"),
         }
     }
 }
+
+struct Location<'a>(&'a str, Option<&'a str>);
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.1 {
+            Some(field) => write!(f, "{}.{field}", self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
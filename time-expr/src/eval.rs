@@ -1,22 +1,245 @@
-use std::cmp::{max, min};
+use time::{OffsetDateTime, PrimitiveDateTime, Time};
 
-use crate::{expr::Expr, EvalError, Value};
+use crate::{
+    expr::{Day, Expr, NumExpr},
+    EvalError, Node, Number, Span, Trace, Value, Zone,
+};
 
 pub trait Resolve {
     fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError>;
+
+    /// Resolves a path to a number, like `team.score`.
+    fn resolve_number(&mut self, path: &[&str]) -> Result<Number, EvalError> {
+        Err(EvalError::UnknownPath {
+            path: path.join(".").into(),
+            span: None,
+        })
+    }
+
+    /// The time zone of time literals which don't specify one.
+    fn default_zone(&self) -> Option<Zone> {
+        None
+    }
+
+    /// The current instant, which literals like `today 18:00` are relative to.
+    fn now(&self) -> Option<OffsetDateTime> {
+        None
+    }
+
+    /// Called when a value depends on the current instant and stops being valid at `time`,
+    /// such as `today 18:00` at the next midnight.
+    fn expires(&mut self, time: OffsetDateTime) {
+        let _ = time;
+    }
+
+    /// Resolves a path like [`Resolve::resolve`], but also records how its value was computed.
+    fn resolve_traced(&mut self, path: &[&str]) -> Trace {
+        Trace::path(path.join("."), self.resolve(path))
+    }
 }
 
-impl<'a> Expr<'a> {
+/// How many segments of a path are borrowed on the stack before falling back to the heap.
+const INLINE_SEGMENTS: usize = 8;
+
+/// Calls `f` with the segments of a path, without allocating for all but the longest paths.
+fn with_path<T>(path: &[Box<str>], f: impl FnOnce(&[&str]) -> T) -> T {
+    if path.len() > INLINE_SEGMENTS {
+        let segments: Vec<&str> = path.iter().map(|p| &**p).collect();
+        return f(&segments);
+    }
+
+    let mut segments = [""; INLINE_SEGMENTS];
+
+    for (segment, part) in segments.iter_mut().zip(path) {
+        *segment = part;
+    }
+
+    f(&segments[..path.len()])
+}
+
+impl Expr {
     pub fn eval<R>(&self, resolver: &mut R) -> Result<Value, EvalError>
     where
         R: Resolve,
     {
+        self.eval_traced(resolver, None)
+    }
+
+    /// Evaluates the expression like [`Expr::eval`], and if `traces` is given,
+    /// also pushes a [`Trace`] of every subexpression and path onto it.
+    ///
+    /// All operands are evaluated even if one of them fails,
+    /// so that the trace shows every failure.
+    pub fn eval_traced<R>(
+        &self,
+        resolver: &mut R,
+        traces: Option<&mut Vec<Trace>>,
+    ) -> Result<Value, EvalError>
+    where
+        R: Resolve,
+    {
+        let Some(traces) = traces else {
+            return self.eval_node(resolver, None);
+        };
+
+        // Paths are traced by the resolver, which knows how their values were computed
+        if let Expr::Field { path, span } = self {
+            let mut trace = with_path(path, |path| resolver.resolve_traced(path));
+            trace.result = trace.result.map_err(|e| e.with_path_span(*span));
+
+            let result = trace.result.clone();
+            traces.push(trace);
+            return result;
+        }
+
+        let mut children = Vec::new();
+        let result = self.eval_node(resolver, Some(&mut children));
+
+        traces.push(Trace {
+            node: Node::Expr(self.label().into()),
+            result: result.clone(),
+            children,
+        });
+
+        result
+    }
+
+    /// Evaluates the expression, tracing its operands into `traces` if given.
+    fn eval_node<R>(
+        &self,
+        resolver: &mut R,
+        mut traces: Option<&mut Vec<Trace>>,
+    ) -> Result<Value, EvalError>
+    where
+        R: Resolve,
+    {
+        let mut eval = |expr: &Expr| expr.eval_traced(resolver, traces.as_deref_mut());
+
         match self {
-            Expr::Literal { value } => Ok(*value),
-            Expr::Field { path } => resolver.resolve(path),
-            Expr::And { left, right } => Ok(max(left.eval(resolver)?, right.eval(resolver)?)),
-            Expr::Or { left, right } => Ok(min(left.eval(resolver)?, right.eval(resolver)?)),
-            Expr::Add { value, duration } => Ok(value.eval(resolver)? + *duration),
+            Expr::Literal { value } => Ok(value.clone()),
+            Expr::Local {
+                day,
+                time,
+                zone,
+                span,
+            } => eval_local(*day, *time, *zone, *span, resolver),
+            Expr::Field { path, span } => {
+                with_path(path, |path| resolver.resolve(path)).map_err(|e| e.with_path_span(*span))
+            }
+            Expr::And { left, right } => {
+                let (left, right) = (eval(left), eval(right));
+                Ok(left? & right?)
+            }
+            Expr::Or { left, right } => {
+                let (left, right) = (eval(left), eval(right));
+                Ok(left? | right?)
+            }
+            Expr::Until { left, right } => {
+                let (left, right) = (eval(left), eval(right));
+                Ok(left? & !right?)
+            }
+            Expr::Not { value } => Ok(!eval(value)?),
+            Expr::AtLeast { count, values } => eval_at_least(*count, values, eval),
+            Expr::All { values } | Expr::Max { values } => {
+                eval_at_least(values.len(), values, eval)
+            }
+            Expr::Any { values } | Expr::Min { values } => eval_at_least(1, values, eval),
+            Expr::Add { value, duration } => Ok(eval(value)? + *duration),
+            Expr::Sub { value, duration } => Ok(eval(value)? - *duration),
+            Expr::Compare { left, op, right } => {
+                let left = left.eval_traced(resolver, traces.as_deref_mut());
+                let right = right.eval_traced(resolver, traces);
+
+                Ok(left?.compare(*op, &right?))
+            }
         }
     }
 }
+
+impl NumExpr {
+    pub fn eval<R>(&self, resolver: &mut R) -> Result<Number, EvalError>
+    where
+        R: Resolve,
+    {
+        self.eval_traced(resolver, None)
+    }
+
+    /// Evaluates the number, tracing the conditions it counts into `traces` if given.
+    pub fn eval_traced<R>(
+        &self,
+        resolver: &mut R,
+        mut traces: Option<&mut Vec<Trace>>,
+    ) -> Result<Number, EvalError>
+    where
+        R: Resolve,
+    {
+        match self {
+            NumExpr::Literal { value } => Ok(Number::constant(*value)),
+            NumExpr::Field { path, span } => with_path(path, |path| resolver.resolve_number(path))
+                .map_err(|e| e.with_path_span(*span)),
+            NumExpr::Count { values } => {
+                let values = eval_all(values, |v| v.eval_traced(resolver, traces.as_deref_mut()))?;
+                let values: Vec<&Value> = values.iter().collect();
+
+                Ok(Number::count(&values))
+            }
+        }
+    }
+}
+
+/// Evaluates every expression, returning the first error only after all of them are evaluated.
+fn eval_all<F>(values: &[Expr], eval: F) -> Result<Vec<Value>, EvalError>
+where
+    F: FnMut(&Expr) -> Result<Value, EvalError>,
+{
+    let results: Vec<_> = values.iter().map(eval).collect();
+    results.into_iter().collect()
+}
+
+fn eval_at_least<F>(count: usize, values: &[Expr], eval: F) -> Result<Value, EvalError>
+where
+    F: FnMut(&Expr) -> Result<Value, EvalError>,
+{
+    let values = eval_all(values, eval)?;
+    let values: Vec<&Value> = values.iter().collect();
+
+    Ok(Value::at_least(count, &values))
+}
+
+pub(crate) fn eval_local<R>(
+    day: Day,
+    time: Time,
+    zone: Option<Zone>,
+    span: Span,
+    resolver: &mut R,
+) -> Result<Value, EvalError>
+where
+    R: Resolve,
+{
+    let zone = zone
+        .or_else(|| resolver.default_zone())
+        .ok_or(EvalError::NoDefaultZone { span })?;
+
+    let date = match day {
+        Day::Date(date) => Some(date),
+        Day::Today | Day::Tomorrow => {
+            let now = resolver.now().ok_or(EvalError::NoCurrentTime { span })?;
+            let today = zone.date(now);
+
+            // The literal refers to a different day once the day is over
+            if let Some(midnight) = today.and_then(|d| d.next_day()) {
+                resolver.expires(zone.localize(midnight.midnight()));
+            }
+
+            if day == Day::Tomorrow {
+                today.and_then(|d| d.next_day())
+            } else {
+                today
+            }
+        }
+    };
+
+    Ok(date
+        .map(|date| Value::since(zone.localize(PrimitiveDateTime::new(date, time))))
+        .unwrap_or_else(Value::never))
+}
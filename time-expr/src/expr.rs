@@ -1,25 +1,193 @@
-use time::Duration;
+use time::{Date, Duration, Time};
 
-use crate::Value;
+use crate::{Comparison, Span, Value, Zone};
+
+/// The day a local time literal refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Day {
+    Date(Date),
+    /// The current day in the literal's time zone
+    Today,
+    /// The day after the current day in the literal's time zone
+    Tomorrow,
+}
 
 #[derive(Debug, Clone)]
-pub enum Expr<'a> {
+pub enum Expr {
     Literal {
         value: Value,
     },
+    /// A time literal which is resolved during evaluation,
+    /// because it's relative, lacks a time zone or uses a named one.
+    Local {
+        day: Day,
+        time: Time,
+        zone: Option<Zone>,
+        span: Span,
+    },
     Field {
-        path: Vec<&'a str>,
+        path: Vec<Box<str>>,
+        span: Span,
     },
     And {
-        left: Box<Expr<'a>>,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        right: Box<Expr>,
     },
     Or {
-        left: Box<Expr<'a>>,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Until {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Not {
+        value: Box<Expr>,
+    },
+    AtLeast {
+        count: usize,
+        values: Vec<Expr>,
+    },
+    All {
+        values: Vec<Expr>,
+    },
+    Any {
+        values: Vec<Expr>,
+    },
+    /// The earliest of several times, true whenever any of the values is
+    Min {
+        values: Vec<Expr>,
+    },
+    /// The latest of several times, true whenever all of the values are
+    Max {
+        values: Vec<Expr>,
     },
     Add {
-        value: Box<Expr<'a>>,
+        value: Box<Expr>,
+        duration: Duration,
+    },
+    Sub {
+        value: Box<Expr>,
         duration: Duration,
     },
+    /// A condition which holds while a comparison of two numbers does
+    Compare {
+        left: Box<NumExpr>,
+        op: Comparison,
+        right: Box<NumExpr>,
+    },
+}
+
+/// An expression whose value is a number changing over time.
+#[derive(Debug, Clone)]
+pub enum NumExpr {
+    Literal {
+        value: i64,
+    },
+    Field {
+        path: Vec<Box<str>>,
+        span: Span,
+    },
+    /// The number of conditions which hold
+    Count {
+        values: Vec<Expr>,
+    },
+}
+
+impl Expr {
+    /// Returns all paths to conditions this expression refers to, in order of appearance.
+    pub fn paths(&self) -> Vec<&[Box<str>]> {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut paths, &mut Vec::new());
+        paths
+    }
+
+    /// Returns all paths to numbers this expression refers to, in order of appearance.
+    pub fn number_paths(&self) -> Vec<&[Box<str>]> {
+        let mut numbers = Vec::new();
+        self.collect_paths(&mut Vec::new(), &mut numbers);
+        numbers
+    }
+
+    /// Returns whether this expression contains a time literal without a time zone.
+    pub fn uses_default_zone(&self) -> bool {
+        match self {
+            Expr::Literal { .. } | Expr::Field { .. } => false,
+            Expr::Local { zone, .. } => zone.is_none(),
+            Expr::And { left, right } | Expr::Or { left, right } | Expr::Until { left, right } => {
+                left.uses_default_zone() || right.uses_default_zone()
+            }
+            Expr::Not { value } | Expr::Add { value, .. } | Expr::Sub { value, .. } => {
+                value.uses_default_zone()
+            }
+            Expr::AtLeast { values, .. }
+            | Expr::All { values }
+            | Expr::Any { values }
+            | Expr::Min { values }
+            | Expr::Max { values } => values.iter().any(Expr::uses_default_zone),
+            Expr::Compare { left, right, .. } => left
+                .conditions()
+                .chain(right.conditions())
+                .any(Expr::uses_default_zone),
+        }
+    }
+
+    fn collect_paths<'a>(
+        &'a self,
+        paths: &mut Vec<&'a [Box<str>]>,
+        numbers: &mut Vec<&'a [Box<str>]>,
+    ) {
+        match self {
+            Expr::Literal { .. } | Expr::Local { .. } => {}
+            Expr::Field { path, .. } => paths.push(path),
+            Expr::And { left, right } | Expr::Or { left, right } | Expr::Until { left, right } => {
+                left.collect_paths(paths, numbers);
+                right.collect_paths(paths, numbers);
+            }
+            Expr::Not { value } | Expr::Add { value, .. } | Expr::Sub { value, .. } => {
+                value.collect_paths(paths, numbers)
+            }
+            Expr::AtLeast { values, .. }
+            | Expr::All { values }
+            | Expr::Any { values }
+            | Expr::Min { values }
+            | Expr::Max { values } => {
+                for value in values {
+                    value.collect_paths(paths, numbers);
+                }
+            }
+            Expr::Compare { left, right, .. } => {
+                left.collect_paths(paths, numbers);
+                right.collect_paths(paths, numbers);
+            }
+        }
+    }
+}
+
+impl NumExpr {
+    /// Returns the conditions this number is computed from.
+    pub fn conditions(&self) -> impl Iterator<Item = &Expr> {
+        let values: &[Expr] = match self {
+            NumExpr::Count { values } => values,
+            NumExpr::Literal { .. } | NumExpr::Field { .. } => &[],
+        };
+
+        values.iter()
+    }
+
+    fn collect_paths<'a>(
+        &'a self,
+        paths: &mut Vec<&'a [Box<str>]>,
+        numbers: &mut Vec<&'a [Box<str>]>,
+    ) {
+        match self {
+            NumExpr::Literal { .. } => {}
+            NumExpr::Field { path, .. } => numbers.push(path),
+            NumExpr::Count { values } => {
+                for value in values {
+                    value.collect_paths(paths, numbers);
+                }
+            }
+        }
+    }
 }
@@ -1,14 +1,24 @@
+mod display;
 mod error;
 mod eval;
 mod expr;
+mod number;
 mod parse;
+mod simplify;
+mod snippet;
+mod trace;
 mod value;
+mod zone;
 
-pub use error::EvalError;
+pub use error::{EvalError, Span};
 pub use eval::Resolve;
-use parse::parse;
-pub use parse::TokenType;
+pub use expr::{Day, Expr, NumExpr};
+pub use number::{Comparison, Number};
+pub use parse::{parse, TokenType};
+pub use snippet::Snippet;
+pub use trace::{Node, Trace};
 pub use value::Value;
+pub use zone::Zone;
 
 pub fn eval<R>(expr: &str, resolver: &mut R) -> Result<Value, EvalError>
 where
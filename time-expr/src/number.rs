@@ -0,0 +1,203 @@
+use std::fmt::{self, Display, Formatter};
+
+use time::OffsetDateTime;
+
+use crate::Value;
+
+/// A whole number which changes over time, such as the number of solved prompts.
+///
+/// It is stored as the number at the beginning of time
+/// together with a strictly increasing list of instants at which it changes and its new values.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Number {
+    initial: i64,
+    changes: Vec<(OffsetDateTime, i64)>,
+}
+
+/// An operator comparing two numbers.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Number {
+    pub fn constant(value: i64) -> Self {
+        Number {
+            initial: value,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Creates a number from its initial value and a list of changes in any order.
+    ///
+    /// If several changes happen at the same instant, the last one wins.
+    pub fn new(initial: i64, changes: impl IntoIterator<Item = (OffsetDateTime, i64)>) -> Self {
+        let mut sorted: Vec<_> = changes.into_iter().collect();
+        sorted.sort_by_key(|&(time, _)| time);
+
+        let mut number = Number::constant(initial);
+
+        for (time, value) in sorted {
+            if number.changes.last().is_some_and(|&(last, _)| last == time) {
+                number.changes.pop();
+            }
+
+            if number.current() != value {
+                number.changes.push((time, value));
+            }
+        }
+
+        number
+    }
+
+    /// Counts how many of `values` hold at any given time.
+    pub fn count(values: &[&Value]) -> Self {
+        let initial = values.iter().filter(|v| v.initial()).count();
+        let mut edges: Vec<_> = values.iter().flat_map(|v| v.edges()).collect();
+        edges.sort_by_key(|&(time, _)| time);
+
+        let mut current = initial as i64;
+        let changes = edges.into_iter().map(|(time, rising)| {
+            current += if rising { 1 } else { -1 };
+            (time, current)
+        });
+
+        Number::new(initial as i64, changes.collect::<Vec<_>>())
+    }
+
+    /// Adds `numbers` together at any given time.
+    pub fn sum(numbers: &[&Number]) -> Self {
+        let initial = numbers
+            .iter()
+            .fold(0i64, |sum, n| sum.saturating_add(n.initial));
+
+        let mut deltas: Vec<(OffsetDateTime, i64)> = numbers
+            .iter()
+            .flat_map(|n| {
+                let previous = std::iter::once(n.initial).chain(n.changes.iter().map(|&(_, v)| v));
+
+                n.changes
+                    .iter()
+                    .zip(previous)
+                    .map(|(&(time, value), previous)| (time, value.saturating_sub(previous)))
+            })
+            .collect();
+        deltas.sort_by_key(|&(time, _)| time);
+
+        let mut current = initial;
+        let changes = deltas.into_iter().map(|(time, delta)| {
+            current = current.saturating_add(delta);
+            (time, current)
+        });
+
+        Number::new(initial, changes.collect::<Vec<_>>())
+    }
+
+    pub fn initial(&self) -> i64 {
+        self.initial
+    }
+
+    /// Returns the instants at which the number changes, along with its new values.
+    pub fn changes(&self) -> &[(OffsetDateTime, i64)] {
+        &self.changes
+    }
+
+    /// Returns the number at a given instant.
+    pub fn at(&self, at: OffsetDateTime) -> i64 {
+        let idx = self.changes.partition_point(|&(c, _)| c <= at);
+
+        match idx.checked_sub(1) {
+            Some(i) => self.changes[i].1,
+            None => self.initial,
+        }
+    }
+
+    /// Returns the last instant at or before `at` at which the number changed.
+    pub fn last_change(&self, at: OffsetDateTime) -> Option<OffsetDateTime> {
+        let idx = self.changes.partition_point(|&(c, _)| c <= at);
+        idx.checked_sub(1).map(|i| self.changes[i].0)
+    }
+
+    /// Returns a condition which holds whenever `self op other` does.
+    pub fn compare(&self, op: Comparison, other: &Number) -> Value {
+        let (mut left, mut right) = (self.initial, other.initial);
+        let initial = op.holds(left, right);
+
+        let mut times: Vec<OffsetDateTime> = self
+            .changes
+            .iter()
+            .chain(&other.changes)
+            .map(|&(time, _)| time)
+            .collect();
+        times.sort();
+        times.dedup();
+
+        let (mut lefts, mut rights) = (
+            self.changes.iter().peekable(),
+            other.changes.iter().peekable(),
+        );
+        let mut state = initial;
+        let mut changes = Vec::new();
+
+        for time in times {
+            if let Some(&(_, value)) = lefts.next_if(|&&(t, _)| t == time) {
+                left = value;
+            }
+
+            if let Some(&(_, value)) = rights.next_if(|&&(t, _)| t == time) {
+                right = value;
+            }
+
+            if op.holds(left, right) != state {
+                state = !state;
+                changes.push(time);
+            }
+        }
+
+        Value::new(initial, changes)
+    }
+
+    fn current(&self) -> i64 {
+        self.changes
+            .last()
+            .map(|&(_, value)| value)
+            .unwrap_or(self.initial)
+    }
+}
+
+impl Comparison {
+    pub fn holds(self, left: i64, right: i64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::Less => write!(f, "<"),
+            Comparison::LessEqual => write!(f, "<="),
+            Comparison::Greater => write!(f, ">"),
+            Comparison::GreaterEqual => write!(f, ">="),
+            Comparison::Equal => write!(f, "=="),
+            Comparison::NotEqual => write!(f, "!="),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Self {
+        Number::constant(value)
+    }
+}
@@ -1,9 +1,12 @@
 use std::iter::Peekable;
 
-use crate::{expr::Expr, EvalError, Value};
+use crate::{
+    expr::{Day, Expr, NumExpr},
+    EvalError, Span, Value,
+};
 
 use self::{
-    temporal::{parse_date, parse_duration},
+    temporal::{parse_date, parse_duration, parse_relative},
     tokens::{Token, Tokens},
 };
 pub use tokens::TokenType;
@@ -16,25 +19,25 @@ type Iter<'a> = Peekable<Tokens<'a>>;
 pub fn parse(string: &str) -> Result<Expr, EvalError> {
     let mut tokens = Tokens::new(string).peekable();
 
-    let expr = parse_add(&mut tokens)?;
+    let expr = parse_until(&mut tokens)?;
 
     expect(&mut tokens, TokenType::Eof)?;
     Ok(expr)
 }
 
-fn parse_add<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
-    let mut expr = parse_or(tokens)?;
+fn parse_until(tokens: &mut Iter) -> Result<Expr, EvalError> {
+    let mut expr = parse_add(tokens)?;
 
     loop {
         if tokens
-            .next_if(|t| matches!(t, Ok((Token::Plus, _))))
+            .next_if(|t| matches!(t, Ok((Token::Word("until"), _))))
             .is_some()
         {
-            let duration = parse_duration(tokens)?;
+            let right = parse_add(tokens)?;
 
-            expr = Expr::Add {
-                value: Box::new(expr),
-                duration,
+            expr = Expr::Until {
+                left: Box::new(expr),
+                right: Box::new(right),
             }
         } else {
             break;
@@ -44,7 +47,37 @@ fn parse_add<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
     Ok(expr)
 }
 
-fn parse_or<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
+fn parse_add(tokens: &mut Iter) -> Result<Expr, EvalError> {
+    let mut expr = parse_or(tokens)?;
+
+    loop {
+        match tokens.peek() {
+            Some(Ok((Token::Plus, _))) => {
+                tokens.next();
+                let duration = parse_duration(tokens)?;
+
+                expr = Expr::Add {
+                    value: Box::new(expr),
+                    duration,
+                }
+            }
+            Some(Ok((Token::Dash, _))) => {
+                tokens.next();
+                let duration = parse_duration(tokens)?;
+
+                expr = Expr::Sub {
+                    value: Box::new(expr),
+                    duration,
+                }
+            }
+            _ => break,
+        }
+    }
+
+    Ok(expr)
+}
+
+fn parse_or(tokens: &mut Iter) -> Result<Expr, EvalError> {
     let mut expr = parse_and(tokens)?;
 
     loop {
@@ -66,7 +99,7 @@ fn parse_or<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
     Ok(expr)
 }
 
-fn parse_and<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
+fn parse_and(tokens: &mut Iter) -> Result<Expr, EvalError> {
     let mut expr = parse_terminal(tokens)?;
 
     loop {
@@ -88,22 +121,71 @@ fn parse_and<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
     Ok(expr)
 }
 
-fn parse_terminal<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
+fn parse_terminal(tokens: &mut Iter) -> Result<Expr, EvalError> {
     match tokens.next().transpose()? {
-        Some((Token::Number(num), pos)) => Ok(Expr::Literal {
-            value: Value::Since(parse_date(tokens, num, pos)?),
-        }),
+        Some((Token::Number(num), span)) if next_is_comparison(tokens) => {
+            parse_comparison(tokens, number_literal(num, span)?)
+        }
+        Some((Token::Number(num), span)) => {
+            if tokens
+                .next_if(|t| matches!(t, Ok((Token::Word("of"), _))))
+                .is_some()
+            {
+                Ok(Expr::AtLeast {
+                    count: num
+                        .try_into()
+                        .map_err(|_| EvalError::LiteralOutOfRange { span })?,
+                    values: parse_list(tokens)?,
+                })
+            } else {
+                parse_date(tokens, num, span)
+            }
+        }
         Some((Token::Word("always"), _)) => Ok(Expr::Literal {
-            value: Value::Always,
+            value: Value::always(),
         }),
         Some((Token::Word("never"), _)) => Ok(Expr::Literal {
-            value: Value::Never,
+            value: Value::never(),
+        }),
+        Some((Token::Word("today"), span)) if next_is_number(tokens) => {
+            parse_relative(tokens, Day::Today, span)
+        }
+        Some((Token::Word("tomorrow"), span)) if next_is_number(tokens) => {
+            parse_relative(tokens, Day::Tomorrow, span)
+        }
+        Some((Token::Word("all"), _)) if next_is_paren(tokens) => Ok(Expr::All {
+            values: parse_list(tokens)?,
+        }),
+        Some((Token::Word("any"), _)) if next_is_paren(tokens) => Ok(Expr::Any {
+            values: parse_list(tokens)?,
+        }),
+        Some((Token::Word("min"), _)) if next_is_paren(tokens) => Ok(Expr::Min {
+            values: parse_list(tokens)?,
+        }),
+        Some((Token::Word("max"), _)) if next_is_paren(tokens) => Ok(Expr::Max {
+            values: parse_list(tokens)?,
         }),
-        Some((Token::Word(word), _)) => Ok(Expr::Field {
-            path: parse_path(tokens, word)?,
+        Some((Token::Word("count"), _)) if next_is_paren(tokens) => {
+            let left = NumExpr::Count {
+                values: parse_list(tokens)?,
+            };
+
+            parse_comparison(tokens, left)
+        }
+        Some((Token::Word(word), span)) => {
+            let (path, span) = parse_path(tokens, word, span)?;
+
+            if next_is_comparison(tokens) {
+                parse_comparison(tokens, NumExpr::Field { path, span })
+            } else {
+                Ok(Expr::Field { path, span })
+            }
+        }
+        Some((Token::Not, _)) => Ok(Expr::Not {
+            value: Box::new(parse_terminal(tokens)?),
         }),
         Some((Token::LeftParen, _)) => {
-            let expr = parse_add(tokens)?;
+            let expr = parse_until(tokens)?;
             expect(tokens, TokenType::RightParen)?;
             Ok(expr)
         }
@@ -111,59 +193,143 @@ fn parse_terminal<'a>(tokens: &mut Iter<'a>) -> Result<Expr<'a>, EvalError> {
     }
 }
 
-fn parse_path<'a>(tokens: &mut Iter<'a>, first: &'a str) -> Result<Vec<&'a str>, EvalError> {
-    let mut path = vec![first];
+fn next_is_paren(tokens: &mut Iter) -> bool {
+    matches!(tokens.peek(), Some(Ok((Token::LeftParen, _))))
+}
+
+fn next_is_comparison(tokens: &mut Iter) -> bool {
+    matches!(tokens.peek(), Some(Ok((Token::Compare(_), _))))
+}
+
+fn parse_comparison(tokens: &mut Iter, left: NumExpr) -> Result<Expr, EvalError> {
+    let op = match tokens.next().transpose()? {
+        Some((Token::Compare(op), _)) => op,
+        other => return Err(unexpected(other)),
+    };
+
+    let right = match tokens.next().transpose()? {
+        Some((Token::Number(num), span)) => number_literal(num, span)?,
+        Some((Token::Word("count"), _)) if next_is_paren(tokens) => NumExpr::Count {
+            values: parse_list(tokens)?,
+        },
+        Some((Token::Word(word), span)) => {
+            let (path, span) = parse_path(tokens, word, span)?;
+            NumExpr::Field { path, span }
+        }
+        other => return Err(unexpected(other)),
+    };
+
+    Ok(Expr::Compare {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    })
+}
+
+fn number_literal(num: u64, span: Span) -> Result<NumExpr, EvalError> {
+    Ok(NumExpr::Literal {
+        value: num
+            .try_into()
+            .map_err(|_| EvalError::LiteralOutOfRange { span })?,
+    })
+}
+
+fn next_is_number(tokens: &mut Iter) -> bool {
+    matches!(tokens.peek(), Some(Ok((Token::Number(_), _))))
+}
+
+fn parse_list(tokens: &mut Iter) -> Result<Vec<Expr>, EvalError> {
+    expect(tokens, TokenType::LeftParen)?;
+
+    let mut values = Vec::new();
+
+    loop {
+        if tokens
+            .next_if(|t| matches!(t, Ok((Token::RightParen, _))))
+            .is_some()
+        {
+            break;
+        }
+
+        values.push(parse_until(tokens)?);
+
+        match tokens.next().transpose()? {
+            Some((Token::Comma, _)) => {}
+            Some((Token::RightParen, _)) => break,
+            other => return Err(unexpected(other)),
+        }
+    }
+
+    Ok(values)
+}
+
+fn parse_path(
+    tokens: &mut Iter,
+    first: &str,
+    mut span: Span,
+) -> Result<(Vec<Box<str>>, Span), EvalError> {
+    let mut path = vec![first.into()];
 
     loop {
         if tokens
             .next_if(|t| matches!(t, Ok((Token::Dot, _))))
             .is_some()
         {
-            let word = match tokens.next().transpose()? {
-                Some((Token::Word(word), _)) => word,
+            let segment: Box<str> = match tokens.next().transpose()? {
+                Some((Token::Word(word), word_span)) => {
+                    span = span.to(word_span);
+                    word.into()
+                }
+                // Segments after the first may also be indices, such as in `first.attempts.3`
+                Some((Token::Number(number), number_span)) => {
+                    span = span.to(number_span);
+                    number.to_string().into()
+                }
                 other => {
                     return Err(unexpected(other));
                 }
             };
 
-            path.push(word);
+            path.push(segment);
         } else {
             break;
         }
     }
 
-    Ok(path)
+    Ok((path, span))
 }
 
-fn unexpected(token: Option<(Token, usize)>) -> EvalError {
+fn unexpected(token: Option<(Token, Span)>) -> EvalError {
     EvalError::UnexpectedToken {
         token: TokenType::new(token.map(|(t, _)| t)),
-        pos: token.map(|(_, p)| p),
+        span: token.map(|(_, s)| s),
     }
 }
 
-fn expect(tokens: &mut Iter, expected: TokenType) -> Result<(), EvalError> {
+/// Consumes a token of the expected type and returns its span, which is `None` at the end.
+fn expect(tokens: &mut Iter, expected: TokenType) -> Result<Option<Span>, EvalError> {
     let token = tokens.next().transpose()?;
     let actual = TokenType::new(token.map(|(t, _)| t));
 
     if actual != expected {
         Err(EvalError::UnexpectedToken {
             token: actual,
-            pos: token.map(|(_, p)| p),
+            span: token.map(|(_, s)| s),
         })
     } else {
-        Ok(())
+        Ok(token.map(|(_, s)| s))
     }
 }
 
-fn expect_number<I>(tokens: &mut Iter) -> Result<I, EvalError>
+fn expect_number<I>(tokens: &mut Iter) -> Result<(I, Span), EvalError>
 where
     I: TryFrom<u64>,
 {
     match tokens.next().transpose()? {
-        Some((Token::Number(number), pos)) => number
+        Some((Token::Number(number), span)) => number
             .try_into()
-            .map_err(|_| EvalError::LiteralOutOfRange { pos }),
+            .map(|number| (number, span))
+            .map_err(|_| EvalError::LiteralOutOfRange { span }),
         other => Err(unexpected(other)),
     }
 }
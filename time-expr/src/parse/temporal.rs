@@ -1,8 +1,9 @@
-use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use time::{Date, Duration, PrimitiveDateTime, Time, UtcOffset};
 
 use crate::{
+    expr::{Day, Expr},
     parse::{expect, expect_number, tokens::Token, unexpected},
-    EvalError, TokenType,
+    EvalError, Span, TokenType, Value, Zone,
 };
 
 use super::Iter;
@@ -12,7 +13,7 @@ pub fn parse_duration(tokens: &mut Iter) -> Result<Duration, EvalError> {
     let mut parsed_some = false;
 
     loop {
-        let Some(Ok((Token::Number(num), pos))) = tokens.peek() else {
+        let Some(Ok((Token::Number(num), num_span))) = tokens.peek() else {
             if parsed_some {
                 break;
             } else {
@@ -21,26 +22,31 @@ pub fn parse_duration(tokens: &mut Iter) -> Result<Duration, EvalError> {
         };
 
         let num = *num;
-        let pos = *pos;
+        let num_span = *num_span;
         tokens.next();
 
-        let unit = match tokens.next().transpose()? {
-            Some((Token::Word(word), _)) => Ok(word),
+        let (unit, unit_span) = match tokens.next().transpose()? {
+            Some((Token::Word(word), span)) => Ok((word, span)),
             other => Err(unexpected(other)),
         }?;
 
+        let span = num_span.to(unit_span);
+
         let num = num
             .try_into()
-            .map_err(|_| EvalError::LiteralOutOfRange { pos })?;
-        let unit = get_unit(unit).ok_or(EvalError::UnknownUnit { unit: unit.into() })?;
+            .map_err(|_| EvalError::LiteralOutOfRange { span })?;
+        let unit = get_unit(unit).ok_or(EvalError::UnknownUnit {
+            unit: unit.into(),
+            span: unit_span,
+        })?;
 
         let duration = unit
             .checked_mul(num)
-            .ok_or(EvalError::LiteralOutOfRange { pos })?;
+            .ok_or(EvalError::LiteralOutOfRange { span })?;
 
         total = total
             .checked_add(duration)
-            .ok_or(EvalError::LiteralOutOfRange { pos })?;
+            .ok_or(EvalError::LiteralOutOfRange { span })?;
 
         parsed_some = true;
     }
@@ -59,55 +65,195 @@ fn get_unit(name: &str) -> Option<Duration> {
     }
 }
 
-pub fn parse_date(tokens: &mut Iter, first: u64, pos: usize) -> Result<OffsetDateTime, EvalError> {
+pub fn parse_date(tokens: &mut Iter, first: u64, first_span: Span) -> Result<Expr, EvalError> {
     let date = {
         let year = first
             .try_into()
-            .map_err(|_| EvalError::LiteralOutOfRange { pos })?;
+            .map_err(|_| EvalError::LiteralOutOfRange { span: first_span })?;
 
         expect(tokens, TokenType::Dash)?;
-        let month = expect_number(tokens)?;
+        let (month, month_span) = expect_number::<u8>(tokens)?;
         expect(tokens, TokenType::Dash)?;
-        let day = expect_number(tokens)?;
+        let (day, day_span) = expect_number(tokens)?;
 
-        let month = u8::try_into(month).map_err(|_| EvalError::LiteralOutOfRange { pos })?;
+        let month = month
+            .try_into()
+            .map_err(|_| EvalError::LiteralOutOfRange { span: month_span })?;
 
-        Date::from_calendar_date(year, month, day)
-            .map_err(|_| EvalError::LiteralOutOfRange { pos })?
+        Date::from_calendar_date(year, month, day).map_err(|_| EvalError::LiteralOutOfRange {
+            span: first_span.to(day_span),
+        })?
     };
 
-    let time = {
-        let hour = expect_number(tokens)?;
-        expect(tokens, TokenType::Colon)?;
-        let minute = expect_number(tokens)?;
-        let second = parse_optional_time_component(tokens, 0)?;
+    let (time, time_span) = parse_time(tokens)?;
+    let zone = parse_zone(tokens)?;
 
-        Time::from_hms(hour, minute, second).map_err(|_| EvalError::LiteralOutOfRange { pos })?
+    let span = match zone {
+        Some((_, zone_span)) => first_span.to(zone_span),
+        None => first_span.to(time_span),
     };
 
-    let offset = {
-        let negative = match tokens.next().transpose()? {
-            Some((Token::Plus, _)) => Ok(false),
-            Some((Token::Dash, _)) => Ok(true),
-            other => Err(unexpected(other)),
-        }?;
+    // Named zones are kept around, so that the literal can be formatted the way it was written
+    Ok(match zone {
+        Some((zone @ Zone::Offset(_), _)) => Expr::Literal {
+            value: Value::since(zone.localize(PrimitiveDateTime::new(date, time))),
+        },
+        zone => Expr::Local {
+            day: Day::Date(date),
+            time,
+            zone: zone.map(|(zone, _)| zone),
+            span,
+        },
+    })
+}
 
-        let hours: i8 = expect_number(tokens)?;
-        let minutes = parse_optional_time_component(tokens, 0)?;
-        let seconds = parse_optional_time_component(tokens, 0)?;
+pub fn parse_relative(tokens: &mut Iter, day: Day, day_span: Span) -> Result<Expr, EvalError> {
+    let (time, time_span) = parse_time(tokens)?;
+    let zone = parse_zone(tokens)?;
 
-        let hours = if negative { -hours } else { hours };
+    let span = match zone {
+        Some((_, zone_span)) => day_span.to(zone_span),
+        None => day_span.to(time_span),
+    };
 
-        UtcOffset::from_hms(hours, minutes, seconds)
-            .map_err(|_| EvalError::LiteralOutOfRange { pos })?
+    Ok(Expr::Local {
+        day,
+        time,
+        zone: zone.map(|(zone, _)| zone),
+        span,
+    })
+}
+
+fn parse_time(tokens: &mut Iter) -> Result<(Time, Span), EvalError> {
+    let (hour, hour_span) = expect_number(tokens)?;
+    expect(tokens, TokenType::Colon)?;
+    let (minute, minute_span) = expect_number(tokens)?;
+    let second = parse_optional_time_component(tokens)?;
+
+    let span = match second {
+        Some((_, second_span)) => hour_span.to(second_span),
+        None => hour_span.to(minute_span),
     };
+    let second = second.map(|(s, _)| s).unwrap_or(0);
+
+    let time =
+        Time::from_hms(hour, minute, second).map_err(|_| EvalError::LiteralOutOfRange { span })?;
+
+    Ok((time, span))
+}
+
+fn parse_zone(tokens: &mut Iter) -> Result<Option<(Zone, Span)>, EvalError> {
+    match tokens.peek().cloned() {
+        Some(Ok((Token::Plus | Token::Dash, _))) if !is_duration_next(tokens) => {
+            let (offset, span) = parse_offset(tokens)?;
+            Ok(Some((Zone::Offset(offset), span)))
+        }
+        Some(Ok((Token::Word(word), mut span))) => {
+            let mut ahead = tokens.clone();
+            ahead.next();
+            let has_slash = matches!(ahead.next(), Some(Ok((Token::Slash, _))));
+
+            // Single word zones like UTC mustn't swallow keywords like until
+            if !has_slash && Zone::named(word).is_none() {
+                return Ok(None);
+            }
+
+            tokens.next();
+            let mut name = word.to_owned();
+
+            while tokens
+                .next_if(|t| matches!(t, Ok((Token::Slash, _))))
+                .is_some()
+            {
+                match tokens.next().transpose()? {
+                    Some((Token::Word(word), word_span)) => {
+                        name.push('/');
+                        name.push_str(word);
+                        span = span.to(word_span);
+                    }
+                    other => return Err(unexpected(other)),
+                }
+
+                // Words stop before a dash and a digit, so zones like Etc/GMT-1 are joined back
+                if let Some((sign, sign_span, number, number_span)) = adjacent_signed(tokens, span)
+                {
+                    name.push(sign);
+                    name.push_str(&number.to_string());
+                    span = span.to(sign_span).to(number_span);
+                }
+            }
+
+            match Zone::named(&name) {
+                Some(zone) => Ok(Some((zone, span))),
+                None => Err(EvalError::UnknownZone {
+                    zone: name.into(),
+                    span,
+                }),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Consumes a sign and a number written right after `span` without any spaces.
+fn adjacent_signed(tokens: &mut Iter, span: Span) -> Option<(char, Span, u64, Span)> {
+    let mut ahead = tokens.clone();
+
+    let (sign, sign_span) = match ahead.next()? {
+        Ok((Token::Plus, sign_span)) => ('+', sign_span),
+        Ok((Token::Dash, sign_span)) => ('-', sign_span),
+        _ => return None,
+    };
+
+    let Ok((Token::Number(number), number_span)) = ahead.next()? else {
+        return None;
+    };
+
+    if sign_span.start != span.end || number_span.start != sign_span.end {
+        return None;
+    }
+
+    *tokens = ahead;
+    Some((sign, sign_span, number, number_span))
+}
+
+/// Checks whether a sign is followed by a duration, such as `+ 1h`, rather than an offset.
+fn is_duration_next(tokens: &Iter) -> bool {
+    let mut ahead = tokens.clone();
+    ahead.next();
+
+    matches!(ahead.next(), Some(Ok((Token::Number(_), _))))
+        && matches!(ahead.next(), Some(Ok((Token::Word(unit), _))) if get_unit(unit).is_some())
+}
+
+fn parse_offset(tokens: &mut Iter) -> Result<(UtcOffset, Span), EvalError> {
+    let (negative, sign_span) = match tokens.next().transpose()? {
+        Some((Token::Plus, span)) => Ok((false, span)),
+        Some((Token::Dash, span)) => Ok((true, span)),
+        other => Err(unexpected(other)),
+    }?;
+
+    let (hours, mut span) = expect_number::<i8>(tokens)?;
+    span = sign_span.to(span);
+
+    let minutes = parse_optional_time_component(tokens)?;
+    let seconds = parse_optional_time_component(tokens)?;
+
+    for (_, component_span) in minutes.iter().chain(seconds.iter()) {
+        span = span.to(*component_span);
+    }
+
+    let hours = if negative { -hours } else { hours };
+    let minutes = minutes.map(|(m, _)| m).unwrap_or(0);
+    let seconds = seconds.map(|(s, _)| s).unwrap_or(0);
 
-    let datetime = PrimitiveDateTime::new(date, time).assume_offset(offset);
+    let offset = UtcOffset::from_hms(hours, minutes, seconds)
+        .map_err(|_| EvalError::LiteralOutOfRange { span })?;
 
-    Ok(datetime)
+    Ok((offset, span))
 }
 
-fn parse_optional_time_component<I>(tokens: &mut Iter, default: I) -> Result<I, EvalError>
+fn parse_optional_time_component<I>(tokens: &mut Iter) -> Result<Option<(I, Span)>, EvalError>
 where
     I: TryFrom<u64>,
 {
@@ -115,8 +261,8 @@ where
         .next_if(|t| matches!(t, Ok((Token::Colon, _))))
         .is_some()
     {
-        expect_number(tokens)
+        expect_number(tokens).map(Some)
     } else {
-        Ok(default)
+        Ok(None)
     }
 }
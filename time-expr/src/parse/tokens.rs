@@ -1,10 +1,10 @@
 use std::{
     fmt::{self, Display, Formatter},
-    iter::{Enumerate, Peekable},
+    iter::Peekable,
     str::CharIndices,
 };
 
-use crate::EvalError;
+use crate::{Comparison, EvalError, Span};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Token<'a> {
@@ -12,65 +12,100 @@ pub enum Token<'a> {
     Number(u64),
     And,
     Or,
+    Not,
     Plus,
     Dash,
     Colon,
     Dot,
+    Comma,
+    Slash,
     LeftParen,
     RightParen,
+    Compare(Comparison),
 }
 
+#[derive(Clone)]
 pub struct Tokens<'a> {
     string: &'a str,
-    chars: Peekable<Enumerate<CharIndices<'a>>>,
+    chars: Peekable<CharIndices<'a>>,
 }
 
 impl<'a> Iterator for Tokens<'a> {
-    type Item = Result<(Token<'a>, usize), EvalError>;
+    type Item = Result<(Token<'a>, Span), EvalError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let Some((pos, (idx, c))) = self.chars.next() else {
+            let Some((start, c)) = self.chars.next() else {
                 break None;
             };
 
-            break Some(
-                match c {
-                    c if c.is_whitespace() => continue,
-                    c @ '0'..='9' => self.parse_number(c, pos),
-                    c if c.is_alphabetic() => Ok(self.parse_word(idx)),
-                    '_' => Ok(self.parse_word(idx)),
-                    '|' => Ok(Token::Or),
-                    '&' => Ok(Token::And),
-                    '+' => Ok(Token::Plus),
-                    '-' => Ok(Token::Dash),
-                    ':' => Ok(Token::Colon),
-                    '.' => Ok(Token::Dot),
-                    '(' => Ok(Token::LeftParen),
-                    ')' => Ok(Token::RightParen),
-                    other => Err(EvalError::UnknownChar { char: other }),
-                }
-                .map(|t| (t, pos)),
-            );
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                c @ '0'..='9' => self.parse_number(c, start),
+                c if c.is_alphabetic() => Ok(self.parse_word(start)),
+                '_' => Ok(self.parse_word(start)),
+                '|' => Ok(Token::Or),
+                '&' => Ok(Token::And),
+                '!' if self.next_is('=') => Ok(Token::Compare(Comparison::NotEqual)),
+                '!' => Ok(Token::Not),
+                '<' if self.next_is('=') => Ok(Token::Compare(Comparison::LessEqual)),
+                '<' => Ok(Token::Compare(Comparison::Less)),
+                '>' if self.next_is('=') => Ok(Token::Compare(Comparison::GreaterEqual)),
+                '>' => Ok(Token::Compare(Comparison::Greater)),
+                '=' if self.next_is('=') => Ok(Token::Compare(Comparison::Equal)),
+                '+' => Ok(Token::Plus),
+                '-' => Ok(Token::Dash),
+                ':' => Ok(Token::Colon),
+                '.' => Ok(Token::Dot),
+                ',' => Ok(Token::Comma),
+                '/' => Ok(Token::Slash),
+                '(' => Ok(Token::LeftParen),
+                ')' => Ok(Token::RightParen),
+                other => Err(EvalError::UnknownChar {
+                    char: other,
+                    span: Span::new(start, start + other.len_utf8()),
+                }),
+            };
+
+            break Some(token.map(|t| (t, Span::new(start, self.offset()))));
         }
     }
 }
 
 impl<'a> Tokens<'a> {
-    fn parse_number(&mut self, first: char, pos: usize) -> Result<Token<'a>, EvalError> {
-        let mut result = first as u64 - '0' as u64;
+    /// Consumes the next char if it's `expected`.
+    fn next_is(&mut self, expected: char) -> bool {
+        self.chars.next_if(|&(_, c)| c == expected).is_some()
+    }
+
+    /// Checks whether the char after the next one is a digit, so that `start-1 h` is a subtraction.
+    fn digit_after_next(&self) -> bool {
+        let mut ahead = self.chars.clone();
+        ahead.next();
+
+        matches!(ahead.next(), Some((_, '0'..='9')))
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|&(i, _)| i)
+            .unwrap_or(self.string.len())
+    }
+
+    fn parse_number(&mut self, first: char, start: usize) -> Result<Token<'a>, EvalError> {
+        let mut result = Some(first as u64 - '0' as u64);
 
         loop {
-            match self.chars.peek().map(|(_p, (_i, c))| c).copied() {
+            match self.chars.peek().map(|&(_i, c)| c) {
                 Some(c @ '0'..='9') => {
                     self.chars.next();
 
                     let value = c as u64 - '0' as u64;
 
                     result = result
-                        .checked_mul(10)
-                        .and_then(|r| r.checked_add(value))
-                        .ok_or(EvalError::LiteralOutOfRange { pos })?;
+                        .and_then(|r| r.checked_mul(10))
+                        .and_then(|r| r.checked_add(value));
                 }
                 Some('_') => {
                     self.chars.next();
@@ -79,28 +114,30 @@ impl<'a> Tokens<'a> {
             };
         }
 
-        Ok(Token::Number(result))
+        result
+            .map(Token::Number)
+            .ok_or(EvalError::LiteralOutOfRange {
+                span: Span::new(start, self.offset()),
+            })
     }
 
     fn parse_word(&mut self, start: usize) -> Token<'a> {
-        loop {
-            if let Some((_p, (i, c))) = self.chars.peek().copied() {
-                if c.is_alphanumeric() || c == '-' || c == '_' {
-                    self.chars.next();
-                    continue;
-                }
-
-                break Token::Word(&self.string[start..i]);
+        while let Some(&(_i, c)) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' || (c == '-' && !self.digit_after_next()) {
+                self.chars.next();
             } else {
-                break Token::Word(&self.string[start..]);
+                break;
             }
         }
+
+        let end = self.offset();
+        Token::Word(&self.string[start..end])
     }
 
     pub fn new(string: &'a str) -> Tokens<'a> {
         Tokens {
             string,
-            chars: string.char_indices().enumerate().peekable(),
+            chars: string.char_indices().peekable(),
         }
     }
 }
@@ -111,12 +148,16 @@ pub enum TokenType {
     Number,
     And,
     Or,
+    Not,
     Plus,
     Dash,
     Colon,
     Dot,
+    Comma,
+    Slash,
     LeftParen,
     RightParen,
+    Comparison,
     Eof,
 }
 
@@ -128,12 +169,16 @@ impl TokenType {
             Some(Token::Number(_)) => TokenType::Number,
             Some(Token::And) => TokenType::And,
             Some(Token::Or) => TokenType::Or,
+            Some(Token::Not) => TokenType::Not,
             Some(Token::Plus) => TokenType::Plus,
             Some(Token::Dash) => TokenType::Dash,
             Some(Token::Colon) => TokenType::Colon,
             Some(Token::Dot) => TokenType::Dot,
+            Some(Token::Comma) => TokenType::Comma,
+            Some(Token::Slash) => TokenType::Slash,
             Some(Token::LeftParen) => TokenType::LeftParen,
             Some(Token::RightParen) => TokenType::RightParen,
+            Some(Token::Compare(_)) => TokenType::Comparison,
             None => TokenType::Eof,
         }
     }
@@ -146,12 +191,16 @@ impl Display for TokenType {
             TokenType::Number => write!(f, "number"),
             TokenType::And => write!(f, "'&'"),
             TokenType::Or => write!(f, "'|'"),
+            TokenType::Not => write!(f, "'!'"),
             TokenType::Plus => write!(f, "'+'"),
             TokenType::Dash => write!(f, "'-'"),
             TokenType::Colon => write!(f, "':'"),
             TokenType::Dot => write!(f, "'.'"),
+            TokenType::Comma => write!(f, "','"),
+            TokenType::Slash => write!(f, "'/'"),
             TokenType::LeftParen => write!(f, "'('"),
             TokenType::RightParen => write!(f, "')'"),
+            TokenType::Comparison => write!(f, "comparison"),
             TokenType::Eof => write!(f, "EOF"),
         }
     }
@@ -0,0 +1,299 @@
+use time::{Duration, PrimitiveDateTime};
+
+use crate::{
+    expr::{Day, Expr, NumExpr},
+    Number, Value,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chain {
+    And,
+    Or,
+}
+
+impl Chain {
+    /// The value which doesn't change the result when added to the chain.
+    fn identity(self) -> Value {
+        match self {
+            Chain::And => Value::always(),
+            Chain::Or => Value::never(),
+        }
+    }
+
+    fn combine(self, left: Value, right: Value) -> Value {
+        match self {
+            Chain::And => left & right,
+            Chain::Or => left | right,
+        }
+    }
+
+    fn join(self, left: Expr, right: Expr) -> Expr {
+        let (left, right) = (Box::new(left), Box::new(right));
+
+        match self {
+            Chain::And => Expr::And { left, right },
+            Chain::Or => Expr::Or { left, right },
+        }
+    }
+
+    /// Returns the operands of an expression which is itself this kind of chain.
+    fn operands(self, expr: Expr) -> Result<Vec<Expr>, Expr> {
+        match (self, expr) {
+            (Chain::And, Expr::And { left, right }) | (Chain::Or, Expr::Or { left, right }) => {
+                Ok(vec![*left, *right])
+            }
+            (Chain::And, Expr::All { values } | Expr::Max { values })
+            | (Chain::Or, Expr::Any { values } | Expr::Min { values }) => Ok(values),
+            (_, expr) => Err(expr),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns an equivalent expression which is cheaper to evaluate.
+    ///
+    /// Constant subexpressions are folded, nested `&` and `|` are flattened,
+    /// and paths repeated within them are only kept once.
+    /// Operands which can't affect the result are dropped,
+    /// along with any errors they would produce during evaluation.
+    pub fn simplify(self) -> Expr {
+        match self {
+            Expr::Literal { .. } | Expr::Field { .. } => self,
+            Expr::Local {
+                day: Day::Date(date),
+                time,
+                zone: Some(zone),
+                ..
+            } => Expr::Literal {
+                value: Value::since(zone.localize(PrimitiveDateTime::new(date, time))),
+            },
+            Expr::Local { .. } => self,
+            Expr::And { left, right } => simplify_chain(Chain::And, vec![*left, *right]),
+            Expr::Or { left, right } => simplify_chain(Chain::Or, vec![*left, *right]),
+            Expr::All { values } | Expr::Max { values } => simplify_chain(Chain::And, values),
+            Expr::Any { values } | Expr::Min { values } => simplify_chain(Chain::Or, values),
+            Expr::Until { left, right } => match (left.simplify(), right.simplify()) {
+                (Expr::Literal { value: left }, Expr::Literal { value: right }) => Expr::Literal {
+                    value: left & !right,
+                },
+                (left, Expr::Literal { value }) if value == Value::never() => left,
+                (Expr::Literal { value }, right) if value == Value::always() => Expr::Not {
+                    value: Box::new(right),
+                }
+                .simplify(),
+                (Expr::Literal { value }, _) | (_, Expr::Literal { value })
+                    if value == Value::never() || value == Value::always() =>
+                {
+                    Expr::Literal {
+                        value: Value::never(),
+                    }
+                }
+                (left, right) => Expr::Until {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+            },
+            Expr::Not { value } => match value.simplify() {
+                Expr::Literal { value } => Expr::Literal { value: !value },
+                Expr::Not { value } => *value,
+                value => Expr::Not {
+                    value: Box::new(value),
+                },
+            },
+            Expr::AtLeast { count, values } => simplify_at_least(count, values),
+            Expr::Compare { left, op, right } => {
+                let (left, right) = ((*left).simplify(), (*right).simplify());
+
+                match (left.constant(), right.constant()) {
+                    (Some(left), Some(right)) => Expr::Literal {
+                        value: left.compare(op, &right),
+                    },
+                    _ => Expr::Compare {
+                        left: Box::new(left),
+                        op,
+                        right: Box::new(right),
+                    },
+                }
+            }
+            Expr::Add { value, duration } => simplify_offset(*value, duration),
+            Expr::Sub { value, duration } => match duration.checked_neg() {
+                Some(duration) => simplify_offset(*value, duration),
+                None => Expr::Sub {
+                    value: Box::new(value.simplify()),
+                    duration,
+                },
+            },
+        }
+    }
+}
+
+impl NumExpr {
+    fn simplify(self) -> NumExpr {
+        match self {
+            NumExpr::Literal { .. } | NumExpr::Field { .. } => self,
+            NumExpr::Count { values } => NumExpr::Count {
+                values: values.into_iter().map(Expr::simplify).collect(),
+            },
+        }
+    }
+
+    /// Returns the number if it doesn't depend on any paths.
+    fn constant(&self) -> Option<Number> {
+        match self {
+            NumExpr::Literal { value } => Some(Number::constant(*value)),
+            NumExpr::Field { .. } => None,
+            NumExpr::Count { values } => {
+                let values: Option<Vec<&Value>> = values
+                    .iter()
+                    .map(|value| match value {
+                        Expr::Literal { value } => Some(value),
+                        _ => None,
+                    })
+                    .collect();
+
+                values.map(|values| Number::count(&values))
+            }
+        }
+    }
+}
+
+fn simplify_chain(chain: Chain, operands: Vec<Expr>) -> Expr {
+    let mut pending = operands;
+    let mut operands = Vec::new();
+    let mut constant = chain.identity();
+
+    while let Some(operand) = pending.pop() {
+        let operand = match chain.operands(operand) {
+            Ok(nested) => {
+                pending.extend(nested);
+                continue;
+            }
+            Err(operand) => operand.simplify(),
+        };
+
+        match chain.operands(operand) {
+            Ok(nested) => pending.extend(nested),
+            Err(Expr::Literal { value }) => constant = chain.combine(constant, value),
+            Err(operand) => operands.push(operand),
+        }
+    }
+
+    // Operands were popped from the back
+    operands.reverse();
+
+    let mut seen = Vec::new();
+    operands.retain(|operand| match operand {
+        Expr::Field { path, .. } if seen.contains(path) => false,
+        Expr::Field { path, .. } => {
+            seen.push(path.clone());
+            true
+        }
+        _ => true,
+    });
+
+    let absorbing = !chain.identity();
+
+    if constant == absorbing {
+        return Expr::Literal { value: constant };
+    }
+
+    if constant != chain.identity() {
+        operands.push(Expr::Literal { value: constant });
+    }
+
+    operands
+        .into_iter()
+        .reduce(|left, right| chain.join(left, right))
+        .unwrap_or(Expr::Literal {
+            value: chain.identity(),
+        })
+}
+
+fn simplify_at_least(count: usize, values: Vec<Expr>) -> Expr {
+    if count == 0 {
+        return Expr::Literal {
+            value: Value::always(),
+        };
+    } else if count > values.len() {
+        return Expr::Literal {
+            value: Value::never(),
+        };
+    } else if count == 1 {
+        return simplify_chain(Chain::Or, values);
+    } else if count == values.len() {
+        return simplify_chain(Chain::And, values);
+    }
+
+    let values: Vec<Expr> = values.into_iter().map(Expr::simplify).collect();
+
+    let literals: Option<Vec<&Value>> = values
+        .iter()
+        .map(|value| match value {
+            Expr::Literal { value } => Some(value),
+            _ => None,
+        })
+        .collect();
+
+    match literals {
+        Some(literals) => Expr::Literal {
+            value: Value::at_least(count, &literals),
+        },
+        None => Expr::AtLeast { count, values },
+    }
+}
+
+/// Simplifies `value + duration`, where subtraction is represented by a negative duration.
+fn simplify_offset(value: Expr, duration: Duration) -> Expr {
+    let value = value.simplify();
+
+    if duration.is_zero() {
+        return value;
+    }
+
+    match value {
+        Expr::Literal { value } => Expr::Literal {
+            value: value + duration,
+        },
+        // Offsets in the same direction can be merged,
+        // since changes pushed past either end of time are lost the same way
+        Expr::Add {
+            value,
+            duration: inner,
+        } if !duration.is_negative() && !inner.is_negative() => match duration.checked_add(inner) {
+            Some(duration) => Expr::Add { value, duration },
+            None => offset(
+                Expr::Add {
+                    value,
+                    duration: inner,
+                },
+                duration,
+            ),
+        },
+        Expr::Sub {
+            value,
+            duration: inner,
+        } if duration.is_negative() && !inner.is_negative() => match inner.checked_sub(duration) {
+            Some(duration) => Expr::Sub { value, duration },
+            None => offset(
+                Expr::Sub {
+                    value,
+                    duration: inner,
+                },
+                duration,
+            ),
+        },
+        value => offset(value, duration),
+    }
+}
+
+fn offset(value: Expr, duration: Duration) -> Expr {
+    let value = Box::new(value);
+
+    match duration.checked_neg() {
+        Some(negated) if duration.is_negative() => Expr::Sub {
+            value,
+            duration: negated,
+        },
+        _ => Expr::Add { value, duration },
+    }
+}
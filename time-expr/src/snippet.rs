@@ -0,0 +1,88 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{EvalError, Span};
+
+/// An error along with the line of the expression it refers to, underlined with carets.
+///
+/// ```text
+/// unexpected '&' at 16
+///     a.visible + 5 m & !c.visible
+///                     ^
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Snippet<'a> {
+    error: &'a EvalError,
+    source: Option<&'a str>,
+}
+
+impl EvalError {
+    /// Displays the error along with the part of `source` it refers to.
+    ///
+    /// Errors which occurred in another expression are shown along with that expression instead.
+    pub fn snippet<'a>(&'a self, source: Option<&'a str>) -> Snippet<'a> {
+        Snippet {
+            error: self,
+            source,
+        }
+    }
+}
+
+impl Display for Snippet<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let EvalError::InExpr {
+            module,
+            field,
+            expr,
+            error,
+        } = self.error
+        {
+            match field {
+                Some(field) => write!(f, "in {module}.{field}: ")?,
+                None => write!(f, "in {module}: ")?,
+            }
+
+            return error.snippet(Some(expr)).fmt(f);
+        }
+
+        write!(f, "{}", self.error)?;
+
+        let Some(source) = self.source else {
+            return Ok(());
+        };
+
+        let span = match (self.error, self.error.span()) {
+            (_, Some(span)) => span,
+            (EvalError::UnexpectedToken { span: None, .. }, _) => {
+                Span::new(source.len(), source.len())
+            }
+            _ => return Ok(()),
+        };
+
+        let start = floor_char_boundary(source, span.start);
+        let end = floor_char_boundary(source, span.end).max(start);
+
+        let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[start..]
+            .find('\n')
+            .map(|i| start + i)
+            .unwrap_or(source.len());
+
+        let line = &source[line_start..line_end];
+        let indent = source[line_start..start].chars().count();
+        let width = source[start..end.min(line_end)].chars().count().max(1);
+
+        writeln!(f)?;
+        writeln!(f, "    {line}")?;
+        write!(f, "    {:indent$}{:^<width$}", "", "")
+    }
+}
+
+fn floor_char_boundary(string: &str, index: usize) -> usize {
+    let mut index = index.min(string.len());
+
+    while !string.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
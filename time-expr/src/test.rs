@@ -1,6 +1,8 @@
-use time::macros::datetime;
+use std::collections::HashMap;
 
-use crate::{eval, EvalError, Resolve, Value};
+use time::{macros::datetime, OffsetDateTime};
+
+use crate::{eval, parse, EvalError, Expr, Node, Number, Resolve, Span, TokenType, Value, Zone};
 
 struct EmptyResolver;
 
@@ -8,6 +10,7 @@ impl Resolve for EmptyResolver {
     fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError> {
         Err(EvalError::UnknownPath {
             path: path.join(".").into_boxed_str(),
+            span: None,
         })
     }
 }
@@ -15,37 +18,37 @@ impl Resolve for EmptyResolver {
 #[test]
 fn literal_date() {
     let value = eval("1990-12-25 12:00 +1", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Since(datetime!(1990-12-25 12:00 +1)));
+    assert_eq!(value, Value::since(datetime!(1990-12-25 12:00 +1)));
 }
 
 #[test]
 fn literal_always() {
     let value = eval("always", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Always);
+    assert_eq!(value, Value::always());
 }
 
 #[test]
 fn literal_never() {
     let value = eval("never", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn or_never_always() {
     let value = eval("never | always", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Always);
+    assert_eq!(value, Value::always());
 }
 
 #[test]
 fn or_never_date() {
     let value = eval("never | 2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-01 00:00 +0)));
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00 +0)));
 }
 
 #[test]
 fn or_always_date() {
     let value = eval("always | 2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Always);
+    assert_eq!(value, Value::always());
 }
 
 #[test]
@@ -55,25 +58,25 @@ fn or_date_date() {
         &mut EmptyResolver,
     )
     .unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-01 00:00:00 +0)));
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00:00 +0)));
 }
 
 #[test]
 fn and_never_always() {
     let value = eval("never & always", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn and_never_date() {
     let value = eval("never & 2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn and_always_date() {
     let value = eval("always & 2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-01 00:00 +0)));
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00 +0)));
 }
 
 #[test]
@@ -83,7 +86,7 @@ fn and_date_date() {
         &mut EmptyResolver,
     )
     .unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-01 00:00:01 +0)));
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00:01 +0)));
 }
 
 #[test]
@@ -93,37 +96,227 @@ fn and_timezones() {
         &mut EmptyResolver,
     )
     .unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-01 00:00 -1)));
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00 -1)));
 }
 
 #[test]
 fn add_never() {
     let value = eval("never + 1 h", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn add_always() {
     let value = eval("always + 1 h", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Always);
+    assert_eq!(value, Value::always());
 }
 
 #[test]
 fn add_date() {
     let value = eval("2000-01-01 00:00 +0 + 1d 2h 3m 4s", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Since(datetime!(2000-01-02 02:03:04 +0)));
+    assert_eq!(value, Value::since(datetime!(2000-01-02 02:03:04 +0)));
 }
 
 #[test]
 fn parens_one() {
     let value = eval("never & (never | always)", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn parens_two() {
     let value = eval("(never & never) | always", &mut EmptyResolver).unwrap();
-    assert_eq!(value, Value::Always);
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn not_always() {
+    let value = eval("!always", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn not_never() {
+    let value = eval("!never", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn not_date() {
+    let value = eval("!2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::until(datetime!(2000-01-01 00:00 +0)));
+}
+
+#[test]
+fn not_binds_tightly() {
+    let value = eval("!never & never", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn until_date_date() {
+    let value = eval(
+        "2000-01-01 00:00 +0 until 2000-01-01 01:00 +0",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+
+    assert!(!value.to_bool(datetime!(1999-12-31 23:59 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 00:00 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 00:59 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 01:00 +0)));
+}
+
+#[test]
+fn until_always() {
+    let value = eval("always until 2000-01-01 00:00 +0", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::until(datetime!(2000-01-01 00:00 +0)));
+}
+
+#[test]
+fn until_before_start() {
+    let value = eval(
+        "2000-01-01 01:00 +0 until 2000-01-01 00:00 +0",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn until_binds_loosest() {
+    let value = eval(
+        "2000-01-01 00:00 +0 until 2000-01-01 00:00 +0 + 30 m",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+
+    assert!(value.to_bool(datetime!(2000-01-01 00:29 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 00:30 +0)));
+}
+
+#[test]
+fn or_windows() {
+    let value = eval(
+        "(2000-01-01 00:00 +0 until 2000-01-01 01:00 +0) | (2000-01-01 02:00 +0 until 2000-01-01 03:00 +0)",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+
+    assert!(value.to_bool(datetime!(2000-01-01 00:30 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 01:30 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 02:30 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 03:30 +0)));
+
+    assert_eq!(
+        value.next_change(datetime!(2000-01-01 01:30 +0)),
+        Some(datetime!(2000-01-01 02:00 +0))
+    );
+    assert_eq!(
+        value.last_change(datetime!(2000-01-01 01:30 +0)),
+        Some(datetime!(2000-01-01 01:00 +0))
+    );
+}
+
+#[test]
+fn or_adjacent_windows() {
+    let value = eval(
+        "(always until 2000-01-01 01:00 +0) | 2000-01-01 01:00 +0",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn add_window() {
+    let value = eval(
+        "(2000-01-01 00:00 +0 until 2000-01-01 01:00 +0) + 1 h",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+
+    assert!(!value.to_bool(datetime!(2000-01-01 00:30 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 01:30 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 02:00 +0)));
+}
+
+#[test]
+fn sub_date() {
+    let value = eval("2000-01-02 02:03:04 +0 - 1d 2h 3m 4s", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 00:00 +0)));
+}
+
+#[test]
+fn sub_never() {
+    let value = eval("never - 1 h", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn sub_window() {
+    let value = eval(
+        "(2000-01-01 01:00 +0 until 2000-01-01 02:00 +0) - 1 h",
+        &mut EmptyResolver,
+    )
+    .unwrap();
+
+    assert!(value.to_bool(datetime!(2000-01-01 00:30 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 01:30 +0)));
+}
+
+#[test]
+fn sub_and_add() {
+    let value = eval("2000-01-01 12:00 +0 - 1 h + 15 m", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 11:15 +0)));
+}
+
+#[test]
+fn sub_before_start_of_time() {
+    let mut resolver = MapResolver(HashMap::from([(
+        "start",
+        Value::since(datetime!(-9999-01-01 00:00 +0)),
+    )]));
+
+    let value = eval("start - 1 d", &mut resolver).unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn sub_without_spaces() {
+    let mut resolver = MapResolver(HashMap::from([
+        ("start", Value::since(datetime!(2000-01-01 12:00 +0))),
+        ("long-name", Value::since(datetime!(2000-01-01 12:00 +0))),
+    ]));
+
+    // A dash before a digit isn't part of the word
+    let value = eval("start-1 h", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 11:00 +0)));
+
+    let value = eval("long-name-30m", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 11:30 +0)));
+}
+
+#[test]
+fn literal_zone_with_sign() {
+    let value = eval("2024-07-01 12:00 Etc/GMT-2", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-01 12:00 +2)));
+
+    let value = eval("2024-07-01 12:00 Etc/GMT+1 - 1 h", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-01 11:00 -1)));
+}
+
+#[test]
+fn sub_missing_duration() {
+    let error = eval("always - a.solved", &mut EmptyResolver).unwrap_err();
+
+    assert!(matches!(
+        error,
+        EvalError::UnexpectedToken {
+            token: TokenType::Word,
+            ..
+        }
+    ));
 }
 
 struct NeverResolver;
@@ -132,18 +325,868 @@ impl Resolve for NeverResolver {
     fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError> {
         assert!(path.iter().all(|n| n.chars().all(|c| c.is_alphanumeric())));
 
-        Ok(Value::Never)
+        Ok(Value::never())
     }
 }
 
 #[test]
 fn unicode() {
     let value = eval("á ", &mut NeverResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
 }
 
 #[test]
 fn unicode_continue() {
     let value = eval("áá | b", &mut NeverResolver).unwrap();
-    assert_eq!(value, Value::Never);
+    assert_eq!(value, Value::never());
+}
+
+struct MapResolver(HashMap<&'static str, Value>);
+
+impl Resolve for MapResolver {
+    fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError> {
+        let path = path.join(".");
+
+        self.0
+            .get(path.as_str())
+            .cloned()
+            .ok_or(EvalError::UnknownPath {
+                path: path.into(),
+                span: None,
+            })
+    }
+}
+
+fn solved_resolver() -> MapResolver {
+    MapResolver(HashMap::from([
+        ("a.solved", Value::since(datetime!(2000-01-01 01:00 +0))),
+        ("b.solved", Value::since(datetime!(2000-01-01 03:00 +0))),
+        ("c.solved", Value::since(datetime!(2000-01-01 02:00 +0))),
+        ("d.solved", Value::never()),
+    ]))
+}
+
+#[test]
+fn long_paths() {
+    let mut resolver = MapResolver(HashMap::from([
+        ("a.b", Value::always()),
+        ("a.b.c.d.e.f.g.h.i.j", Value::always()),
+    ]));
+
+    let value = eval("a.b & a.b.c.d.e.f.g.h.i.j", &mut resolver).unwrap();
+    assert_eq!(value, Value::always());
+
+    let error = eval("a.b.c.d.e.f.g.h.i", &mut resolver).unwrap_err();
+    assert!(matches!(error, EvalError::UnknownPath { path, .. } if &*path == "a.b.c.d.e.f.g.h.i"));
+}
+
+#[test]
+fn at_least_paths() {
+    let value = eval(
+        "2 of (a.solved, b.solved, c.solved, d.solved)",
+        &mut solved_resolver(),
+    )
+    .unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 02:00 +0)));
+}
+
+#[test]
+fn at_least_zero() {
+    let value = eval("0 of (d.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn at_least_too_many() {
+    let value = eval("3 of (a.solved, b.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn at_least_trailing_comma() {
+    let value = eval("1 of (a.solved, b.solved,)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 01:00 +0)));
+}
+
+#[test]
+fn at_least_window() {
+    let value = eval(
+        "2 of (a.solved, b.solved, always until c.solved)",
+        &mut solved_resolver(),
+    )
+    .unwrap();
+
+    assert!(!value.to_bool(datetime!(2000-01-01 00:30 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 01:30 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 02:30 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 03:30 +0)));
+}
+
+#[test]
+fn all_paths() {
+    let value = eval("all(a.solved, b.solved, c.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 03:00 +0)));
+}
+
+#[test]
+fn any_paths() {
+    let value = eval("any(d.solved, b.solved, c.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 02:00 +0)));
+}
+
+#[test]
+fn all_empty() {
+    let value = eval("all()", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn any_empty() {
+    let value = eval("any()", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn all_as_path() {
+    let value = eval("all.solved | any", &mut NeverResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn min_paths() {
+    let value = eval("min(d.solved, b.solved, c.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 02:00 +0)));
+}
+
+#[test]
+fn max_paths() {
+    let value = eval("max(a.solved, b.solved, c.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 03:00 +0)));
+}
+
+#[test]
+fn max_minus_duration() {
+    let value = eval("max(a.solved, c.solved) - 10 m", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 01:50 +0)));
+}
+
+#[test]
+fn min_as_path() {
+    let value = eval("min.solved | max", &mut NeverResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn at_least_unclosed() {
+    let error = eval("1 of (a.solved b.solved)", &mut solved_resolver()).unwrap_err();
+    assert!(matches!(
+        error,
+        EvalError::UnexpectedToken {
+            token: TokenType::Word,
+            span: Some(Span { start: 15, end: 16 })
+        }
+    ));
+}
+
+#[test]
+fn paths() {
+    let expr = parse("a.solved & (2 of (b.hint.x.taken, !c, a.solved) + 5 m)").unwrap();
+    let paths: Vec<String> = expr.paths().iter().map(|p| p.join(".")).collect();
+
+    assert_eq!(paths, ["a.solved", "b.hint.x.taken", "c", "a.solved"]);
+}
+
+struct ZoneResolver {
+    zone: Zone,
+    now: OffsetDateTime,
+    expires: Option<OffsetDateTime>,
+}
+
+impl Resolve for ZoneResolver {
+    fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError> {
+        EmptyResolver.resolve(path)
+    }
+
+    fn default_zone(&self) -> Option<Zone> {
+        Some(self.zone)
+    }
+
+    fn now(&self) -> Option<OffsetDateTime> {
+        Some(self.now)
+    }
+
+    fn expires(&mut self, time: OffsetDateTime) {
+        self.expires = Some(self.expires.map_or(time, |t| t.min(time)));
+    }
+}
+
+fn prague_resolver(now: OffsetDateTime) -> ZoneResolver {
+    ZoneResolver {
+        zone: Zone::named("Europe/Prague").unwrap(),
+        now,
+        expires: None,
+    }
+}
+
+#[test]
+fn literal_named_zone() {
+    let value = eval("2024-07-01 12:00 Europe/Prague", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-01 12:00 +2)));
+
+    let value = eval("2024-01-01 12:00 Europe/Prague", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-01-01 12:00 +1)));
+}
+
+#[test]
+fn literal_single_word_zone() {
+    let value = eval("2024-07-01 12:00 UTC", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-01 12:00 +0)));
+}
+
+#[test]
+fn literal_ambiguous_time() {
+    let value = eval("2024-10-27 02:30 Europe/Prague", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-10-27 02:30 +2)));
+}
+
+#[test]
+fn literal_skipped_time() {
+    let value = eval("2024-03-31 02:30 Europe/Prague", &mut EmptyResolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-03-31 03:30 +2)));
+}
+
+#[test]
+fn literal_unknown_zone() {
+    let error = eval("2024-07-01 12:00 Europe/Atlantis", &mut EmptyResolver).unwrap_err();
+    assert!(matches!(
+        error,
+        EvalError::UnknownZone {
+            span: Span { start: 17, end: 32 },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn literal_default_zone() {
+    let mut resolver = prague_resolver(datetime!(2024-01-01 00:00 +0));
+
+    let value = eval("2024-07-01 12:00", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-01 12:00 +2)));
+}
+
+#[test]
+fn literal_no_default_zone() {
+    let error = eval("2024-07-01 12:00", &mut EmptyResolver).unwrap_err();
+    assert!(matches!(error, EvalError::NoDefaultZone { .. }));
+}
+
+#[test]
+fn literal_default_zone_offset() {
+    let mut resolver = prague_resolver(datetime!(2024-01-01 00:00 +0));
+
+    let value = eval(
+        "2024-07-01 12:00 + 1 h until 2024-07-01 14:00",
+        &mut resolver,
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        Value::since(datetime!(2024-07-01 13:00 +2))
+            & !Value::since(datetime!(2024-07-01 14:00 +2))
+    );
+}
+
+#[test]
+fn literal_today() {
+    let mut resolver = prague_resolver(datetime!(2024-07-01 23:30 +0));
+
+    let value = eval("today 18:00", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-02 18:00 +2)));
+
+    let value = eval("tomorrow 9:15:30 +0", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2024-07-02 09:15:30 +0)));
+}
+
+#[test]
+fn literal_today_expires_at_midnight() {
+    let mut resolver = prague_resolver(datetime!(2024-07-01 12:00 +0));
+
+    eval("2024-07-01 18:00", &mut resolver).unwrap();
+    assert_eq!(resolver.expires, None);
+
+    eval("today 18:00 | tomorrow 9:00 +0", &mut resolver).unwrap();
+    assert_eq!(resolver.expires, Some(datetime!(2024-07-02 00:00 +2)));
+}
+
+#[test]
+fn literal_today_without_time() {
+    let error = eval("today 18:00", &mut EmptyResolver).unwrap_err();
+    assert!(matches!(error, EvalError::NoDefaultZone { .. }));
+
+    let error = eval("today 18:00 +2", &mut EmptyResolver).unwrap_err();
+    assert!(matches!(error, EvalError::NoCurrentTime { .. }));
+}
+
+#[test]
+fn today_as_path() {
+    let value = eval("today.solved", &mut NeverResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn uses_default_zone() {
+    assert!(parse("a & !(2024-07-01 12:00 + 1 h)")
+        .unwrap()
+        .uses_default_zone());
+    assert!(!parse("a & today 12:00 Europe/Prague")
+        .unwrap()
+        .uses_default_zone());
+}
+
+#[test]
+fn trace_matches_eval() {
+    let source = "2 of (a.solved, b.solved + 30 m, !c.solved) until d.solved | 1990-12-25 12:00 +1";
+    let expr = parse(source).unwrap();
+
+    let value = expr.eval(&mut solved_resolver()).unwrap();
+    let trace = expr.trace(&mut solved_resolver());
+
+    assert_eq!(trace.result.unwrap(), value);
+}
+
+#[test]
+fn trace_tree() {
+    let expr = parse("a.solved & !b.solved").unwrap();
+    let trace = expr.trace(&mut solved_resolver());
+
+    assert!(matches!(&trace.node, Node::Expr(label) if &**label == "&"));
+    assert_eq!(trace.children.len(), 2);
+
+    let [a, not] = &trace.children[..] else {
+        unreachable!()
+    };
+
+    assert!(matches!(&a.node, Node::Path(path) if &**path == "a.solved"));
+    assert_eq!(
+        a.result.as_ref().unwrap(),
+        &Value::since(datetime!(2000-01-01 01:00 +0))
+    );
+
+    assert!(matches!(&not.node, Node::Expr(label) if &**label == "!"));
+    assert!(matches!(&not.children[0].node, Node::Path(path) if &**path == "b.solved"));
+}
+
+#[test]
+fn trace_error() {
+    let expr = parse("a.solved | x.solved").unwrap();
+    let trace = expr.trace(&mut solved_resolver());
+
+    assert!(trace.result.is_err());
+    assert!(trace.children[0].result.is_ok());
+    assert!(matches!(
+        trace.children[1].result,
+        Err(EvalError::UnknownPath { .. })
+    ));
+}
+
+#[test]
+fn span_unknown_path() {
+    let error = eval("a.solved | x.solved", &mut solved_resolver()).unwrap_err();
+    assert_eq!(error.span(), Some(Span { start: 11, end: 19 }));
+}
+
+#[test]
+fn span_multibyte() {
+    let error = eval("čas.ř & §", &mut EmptyResolver).unwrap_err();
+    assert!(matches!(
+        error,
+        EvalError::UnknownChar {
+            char: '§',
+            span: Span { start: 10, end: 12 }
+        }
+    ));
+}
+
+#[test]
+fn span_unknown_unit() {
+    let error = eval("a.solved + 5 m 3 y", &mut EmptyResolver).unwrap_err();
+    assert_eq!(error.span(), Some(Span { start: 17, end: 18 }));
+}
+
+#[test]
+fn snippet() {
+    let source = "a.solved & & b";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(
+        error.snippet(Some(source)).to_string(),
+        "unexpected '&' at 11\n    a.solved & & b\n               ^"
+    );
+}
+
+#[test]
+fn snippet_end() {
+    let source = "(a.solved";
+    let error = parse(source).unwrap_err();
+
+    assert_eq!(
+        error.snippet(Some(source)).to_string(),
+        "unexpected EOF at end\n    (a.solved\n             ^"
+    );
+}
+
+#[test]
+fn snippet_in_expr() {
+    let source = "a.solved | x.solved";
+    let error = eval(source, &mut solved_resolver()).unwrap_err();
+    let error = error.in_expr("b", Some("visible"), source);
+
+    assert_eq!(
+        error.snippet(None).to_string(),
+        "in b.visible: unknown path \"x.solved\"\n    a.solved | x.solved\n               ^^^^^^^^"
+    );
+}
+
+fn format(expr: &str) -> String {
+    parse(expr).unwrap().to_string()
+}
+
+#[test]
+fn format_operators() {
+    assert_eq!(format("a&b|!c"), "a & b | !c");
+    assert_eq!(format("a until b+5m"), "a until b + 5 m");
+    assert_eq!(format("x - 1h 30 m + 1ms"), "x - 1 h 30 m + 1 ms");
+    assert_eq!(format("2 of(a,b , c)"), "2 of (a, b, c)");
+    assert_eq!(format("all() | min(a, max(b))"), "all() | min(a, max(b))");
+}
+
+#[test]
+fn format_redundant_parens() {
+    assert_eq!(format("((a & b)) | (c)"), "a & b | c");
+    assert_eq!(format("(a | b) & !(c & d)"), "(a | b) & !(c & d)");
+    assert_eq!(
+        format("(a until b) until (c until d)"),
+        "a until b until (c until d)"
+    );
+    assert_eq!(format("(a | b) + 5 m"), "a | b + 5 m");
+    assert_eq!(format("((a until b) + 5 m) | c"), "((a until b) + 5 m) | c");
+    assert_eq!(format("a | (b | c)"), "a | (b | c)");
+}
+
+#[test]
+fn format_literals() {
+    assert_eq!(format("always | never"), "always | never");
+    assert_eq!(format("2000-1-2 3:04 +1"), "2000-01-02 03:04 +01:00");
+    assert_eq!(
+        format("2000-01-02 03:04:05 -5:30"),
+        "2000-01-02 03:04:05 -05:30"
+    );
+    assert_eq!(
+        format("2000-01-02 03:04 Europe/Prague"),
+        "2000-01-02 03:04 Europe/Prague"
+    );
+    assert_eq!(format("2000-01-02 03:04"), "2000-01-02 03:04");
+    assert_eq!(format("tomorrow 8:00 UTC"), "tomorrow 08:00 Etc/UTC");
+}
+
+#[test]
+fn format_values() {
+    let window =
+        Value::since(datetime!(2000-01-01 00:00 +0)) & Value::until(datetime!(2000-01-01 01:00 +0));
+    let expr = Expr::Not {
+        value: Box::new(Expr::Literal {
+            value: !window | Value::since(datetime!(2000-01-02 00:00 +0)),
+        }),
+    };
+
+    assert_eq!(
+        expr.to_string(),
+        "!(!2000-01-01 00:00 +00:00 | 2000-01-01 01:00 +00:00)"
+    );
+}
+
+#[test]
+fn format_round_trip() {
+    let sources = [
+        "a.solved & (2 of (b.hint.x.taken, !c, a.solved) + 5 m)",
+        "(a | b) & !(c & d) until e - 1 d",
+        "max(a, b) - 10 m until min(c, 2000-01-01 00:00 +0)",
+        "!!a & !(b until c)",
+        "(a until b) + 1 h",
+    ];
+
+    for source in sources {
+        let formatted = format(source);
+        assert_eq!(format(&formatted), formatted);
+
+        let expected = eval(source, &mut solved_resolver_with_letters()).unwrap();
+        let actual = eval(&formatted, &mut solved_resolver_with_letters()).unwrap();
+        assert_eq!(expected, actual, "{source} formatted as {formatted}");
+    }
+}
+
+fn solved_resolver_with_letters() -> MapResolver {
+    let mut resolver = solved_resolver();
+
+    for (name, hour) in [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5)] {
+        resolver.0.insert(
+            name,
+            Value::since(datetime!(2000-01-01 00:00 +0) + time::Duration::hours(hour)),
+        );
+    }
+
+    resolver.0.insert("b.hint.x.taken", Value::never());
+    resolver
+}
+
+fn simplify(expr: &str) -> String {
+    parse(expr).unwrap().simplify().to_string()
+}
+
+#[test]
+fn simplify_constants() {
+    assert_eq!(simplify("never | x"), "x");
+    assert_eq!(simplify("always & y"), "y");
+    assert_eq!(simplify("never & y"), "never");
+    assert_eq!(simplify("!!x"), "x");
+    assert_eq!(simplify("!always | x"), "x");
+    assert_eq!(
+        simplify("2024-01-01 10:00 +0 + 1 h"),
+        "2024-01-01 11:00 +00:00"
+    );
+    assert_eq!(
+        simplify("2024-01-01 10:00 UTC - 1 d"),
+        "2023-12-31 10:00 +00:00"
+    );
+    assert_eq!(simplify("x until never"), "x");
+    assert_eq!(simplify("always until x"), "!x");
+    assert_eq!(simplify("0 of (a, b)"), "always");
+    assert_eq!(simplify("3 of (a, b)"), "never");
+}
+
+#[test]
+fn simplify_flatten() {
+    assert_eq!(simplify("a & (b & (c & all(d, e)))"), "a & b & c & d & e");
+    assert_eq!(
+        simplify("a | (b & c) | any(d, min(e))"),
+        "a | b & c | d | e"
+    );
+    assert_eq!(simplify("1 of (a, b | c)"), "a | b | c");
+    assert_eq!(simplify("2 of (a, b)"), "a & b");
+}
+
+#[test]
+fn simplify_dedupe() {
+    assert_eq!(simplify("a.solved & b & a.solved"), "a.solved & b");
+    assert_eq!(simplify("a | (b | a)"), "a | b");
+    assert_eq!(simplify("a & !a"), "a & !a");
+}
+
+#[test]
+fn simplify_offsets() {
+    assert_eq!(simplify("a + 1 h + 30 m"), "a + 1 h 30 m");
+    assert_eq!(simplify("a - 1 h - 30 m"), "a - 1 h 30 m");
+    assert_eq!(simplify("a + 1 h - 30 m"), "a + 1 h - 30 m");
+    assert_eq!(simplify("a + 0 s"), "a");
+}
+
+#[test]
+fn simplify_keeps_local() {
+    assert_eq!(simplify("today 10:00 | never"), "today 10:00");
+    assert_eq!(simplify("2024-01-01 10:00"), "2024-01-01 10:00");
+}
+
+struct ScoreResolver(MapResolver);
+
+impl Resolve for ScoreResolver {
+    fn resolve(&mut self, path: &[&str]) -> Result<Value, EvalError> {
+        self.0.resolve(path)
+    }
+
+    fn resolve_number(&mut self, path: &[&str]) -> Result<Number, EvalError> {
+        match path {
+            ["team", "score"] => Ok(Number::new(
+                0,
+                [
+                    (datetime!(2000-01-01 03:00 +0), 150),
+                    (datetime!(2000-01-01 01:00 +0), 50),
+                ],
+            )),
+            _ => EmptyResolver.resolve_number(path),
+        }
+    }
+}
+
+#[test]
+fn compare_count() {
+    let value = eval(
+        "count(a.solved, b.solved, c.solved, d.solved) >= 2",
+        &mut solved_resolver(),
+    )
+    .unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 02:00 +0)));
+}
+
+#[test]
+fn compare_literal_first() {
+    let value = eval("3 > count(a.solved, c.solved)", &mut solved_resolver()).unwrap();
+    assert_eq!(value, Value::always());
+}
+
+#[test]
+fn compare_path() {
+    let mut resolver = ScoreResolver(solved_resolver());
+
+    let value = eval("team.score > 100", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 03:00 +0)));
+
+    let value = eval("team.score == 50", &mut resolver).unwrap();
+    assert!(!value.to_bool(datetime!(2000-01-01 00:30 +0)));
+    assert!(value.to_bool(datetime!(2000-01-01 02:00 +0)));
+    assert!(!value.to_bool(datetime!(2000-01-01 03:00 +0)));
+}
+
+#[test]
+fn compare_numbers() {
+    let mut resolver = ScoreResolver(solved_resolver());
+
+    let value = eval("team.score != count(a.solved) & !d.solved", &mut resolver).unwrap();
+    assert_eq!(value, Value::since(datetime!(2000-01-01 01:00 +0)));
+}
+
+#[test]
+fn compare_unknown_number() {
+    let error = eval("a.solved & score < 1", &mut solved_resolver()).unwrap_err();
+
+    assert!(matches!(
+        error,
+        EvalError::UnknownPath {
+            span: Some(Span { start: 11, end: 16 }),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn compare_missing_operand() {
+    let error = eval("count(a.solved) >= ", &mut solved_resolver()).unwrap_err();
+
+    assert!(matches!(
+        error,
+        EvalError::UnexpectedToken {
+            token: TokenType::Eof,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn count_as_path() {
+    let value = eval("count | count.x", &mut NeverResolver).unwrap();
+    assert_eq!(value, Value::never());
+}
+
+#[test]
+fn number_changes() {
+    let number = Number::new(
+        1,
+        [
+            (datetime!(2000-01-01 02:00 +0), 1),
+            (datetime!(2000-01-01 01:00 +0), 3),
+            (datetime!(2000-01-01 01:00 +0), 2),
+        ],
+    );
+
+    assert_eq!(
+        number.changes(),
+        [
+            (datetime!(2000-01-01 01:00 +0), 2),
+            (datetime!(2000-01-01 02:00 +0), 1),
+        ]
+    );
+    assert_eq!(number.at(datetime!(2000-01-01 01:30 +0)), 2);
+}
+
+#[test]
+fn number_sum() {
+    let first = Number::new(
+        10,
+        [
+            (datetime!(2000-01-01 01:00 +0), 15),
+            (datetime!(2000-01-01 03:00 +0), 5),
+        ],
+    );
+    let second = Number::new(
+        -5,
+        [
+            (datetime!(2000-01-01 02:00 +0), 0),
+            (datetime!(2000-01-01 03:00 +0), 10),
+        ],
+    );
+
+    let sum = Number::sum(&[&first, &second]);
+
+    assert_eq!(sum.initial(), 5);
+    assert_eq!(
+        sum.changes(),
+        [
+            (datetime!(2000-01-01 01:00 +0), 10),
+            (datetime!(2000-01-01 02:00 +0), 15),
+        ]
+    );
+    assert_eq!(
+        sum.last_change(datetime!(2000-01-01 05:00 +0)),
+        Some(datetime!(2000-01-01 02:00 +0))
+    );
+    assert_eq!(Number::sum(&[]), Number::constant(0));
+}
+
+#[test]
+fn number_paths() {
+    let expr = parse("a & count(b, team.solved > 1) >= team.goal").unwrap();
+
+    let paths: Vec<String> = expr.paths().iter().map(|p| p.join(".")).collect();
+    let numbers: Vec<String> = expr.number_paths().iter().map(|p| p.join(".")).collect();
+
+    assert_eq!(paths, ["a", "b"]);
+    assert_eq!(numbers, ["team.solved", "team.goal"]);
+}
+
+#[test]
+fn index_path() {
+    let expr = parse("first.attempts.3 | first.attempts.1_0").unwrap();
+    let paths: Vec<String> = expr.paths().iter().map(|p| p.join(".")).collect();
+
+    assert_eq!(paths, ["first.attempts.3", "first.attempts.10"]);
+    assert_eq!(format("first.attempts.3&x"), "first.attempts.3 & x");
+    assert!(parse("3.attempts").is_err());
+}
+
+#[test]
+fn format_compare() {
+    assert_eq!(format("count(a,b)>=2&c"), "count(a, b) >= 2 & c");
+    assert_eq!(format("!(team.score<10)"), "!team.score < 10");
+    assert_eq!(
+        simplify("count(always, never, x) == 3"),
+        "count(always, never, x) == 3"
+    );
+    assert_eq!(simplify("count(always, never) == 1 & x"), "x");
+}
+
+mod simplify_properties {
+    use proptest::prelude::*;
+    use time::{Duration, OffsetDateTime};
+
+    use crate::{Comparison, Expr, NumExpr, Resolve, Span, Value};
+
+    const PATHS: [&str; 3] = ["a", "b", "c"];
+
+    fn instant() -> impl Strategy<Value = OffsetDateTime> {
+        (0..48i64).prop_map(|hours| {
+            OffsetDateTime::UNIX_EPOCH + Duration::days(10_000) + Duration::hours(hours)
+        })
+    }
+
+    fn value() -> impl Strategy<Value = Value> {
+        (any::<bool>(), prop::collection::btree_set(instant(), 0..5)).prop_map(
+            |(initial, changes)| {
+                let changes: Vec<_> = changes.into_iter().collect();
+
+                let value = changes
+                    .chunks(2)
+                    .map(|window| match *window {
+                        [start, end] => Value::since(start) & Value::until(end),
+                        [start] => Value::since(start),
+                        _ => Value::never(),
+                    })
+                    .fold(Value::never(), |a, b| a | b);
+
+                if initial {
+                    !value
+                } else {
+                    value
+                }
+            },
+        )
+    }
+
+    fn comparison() -> impl Strategy<Value = Comparison> {
+        prop::sample::select(vec![
+            Comparison::Less,
+            Comparison::LessEqual,
+            Comparison::Greater,
+            Comparison::GreaterEqual,
+            Comparison::Equal,
+            Comparison::NotEqual,
+        ])
+    }
+
+    fn expr() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            value().prop_map(|value| Expr::Literal { value }),
+            prop::sample::select(&PATHS[..]).prop_map(|path| Expr::Field {
+                path: vec![path.into()],
+                span: Span::new(0, 0),
+            }),
+        ];
+
+        leaf.prop_recursive(4, 32, 4, |inner| {
+            let boxed = || inner.clone().prop_map(Box::new);
+            let list = || prop::collection::vec(inner.clone(), 0..4);
+            let duration = || (0..36i64).prop_map(Duration::hours);
+
+            prop_oneof![
+                (boxed(), boxed()).prop_map(|(left, right)| Expr::And { left, right }),
+                (boxed(), boxed()).prop_map(|(left, right)| Expr::Or { left, right }),
+                (boxed(), boxed()).prop_map(|(left, right)| Expr::Until { left, right }),
+                boxed().prop_map(|value| Expr::Not { value }),
+                (0..4usize, list()).prop_map(|(count, values)| Expr::AtLeast { count, values }),
+                list().prop_map(|values| Expr::All { values }),
+                list().prop_map(|values| Expr::Any { values }),
+                list().prop_map(|values| Expr::Min { values }),
+                list().prop_map(|values| Expr::Max { values }),
+                (boxed(), duration()).prop_map(|(value, duration)| Expr::Add { value, duration }),
+                (boxed(), duration()).prop_map(|(value, duration)| Expr::Sub { value, duration }),
+                (list(), comparison(), 0..4i64).prop_map(|(values, op, value)| Expr::Compare {
+                    left: Box::new(NumExpr::Count { values }),
+                    op,
+                    right: Box::new(NumExpr::Literal { value }),
+                }),
+            ]
+        })
+    }
+
+    struct ValueResolver(Vec<Value>);
+
+    impl Resolve for ValueResolver {
+        fn resolve(&mut self, path: &[&str]) -> Result<Value, crate::EvalError> {
+            let index = PATHS.iter().position(|p| path == [*p]).unwrap();
+            Ok(self.0[index].clone())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn simplified_evaluates_identically(
+            expr in expr(),
+            values in prop::collection::vec(value(), PATHS.len()),
+        ) {
+            let mut resolver = ValueResolver(values);
+
+            let expected = expr.eval(&mut resolver).unwrap();
+            let simplified = expr.clone().simplify();
+            let actual = simplified.eval(&mut resolver).unwrap();
+
+            prop_assert_eq!(expected, actual, "simplified {} to {}", expr, simplified);
+        }
+
+        #[test]
+        fn simplify_is_idempotent(expr in expr()) {
+            let once = expr.simplify();
+            let twice = once.clone().simplify();
+
+            prop_assert_eq!(once.to_string(), twice.to_string());
+        }
+    }
 }
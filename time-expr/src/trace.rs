@@ -0,0 +1,63 @@
+use crate::{expr::Expr, EvalError, Resolve, Value};
+
+/// A step of evaluating an expression, along with the steps it depended on.
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub node: Node,
+    pub result: Result<Value, EvalError>,
+    pub children: Vec<Trace>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    /// A subexpression, labelled by its operator such as `&` or `2 of`, whose children are its operands
+    Expr(Box<str>),
+    /// A resolved path, whose children are the expressions evaluated to resolve it
+    Path(Box<str>),
+}
+
+impl Trace {
+    /// A trace of a path whose value didn't come from evaluating any expressions.
+    pub fn path(path: impl Into<Box<str>>, result: Result<Value, EvalError>) -> Self {
+        Trace {
+            node: Node::Path(path.into()),
+            result,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Expr {
+    /// Evaluates the expression like [`Expr::eval`], but records every subexpression and path.
+    pub fn trace<R>(&self, resolver: &mut R) -> Trace
+    where
+        R: Resolve,
+    {
+        let mut traces = Vec::new();
+        let _ = self.eval_traced(resolver, Some(&mut traces));
+
+        traces
+            .pop()
+            .expect("evaluating an expression should trace it")
+    }
+
+    /// Describes the expression without its operands, which are traced separately.
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Expr::Literal { .. } | Expr::Local { .. } => self.to_string(),
+            Expr::Field { path, .. } => path.join("."),
+            Expr::And { .. } => "&".to_owned(),
+            Expr::Or { .. } => "|".to_owned(),
+            Expr::Until { .. } => "until".to_owned(),
+            Expr::Not { .. } => "!".to_owned(),
+            Expr::AtLeast { count, .. } => format!("{count} of"),
+            Expr::All { .. } => "all".to_owned(),
+            Expr::Any { .. } => "any".to_owned(),
+            Expr::Min { .. } => "min".to_owned(),
+            Expr::Max { .. } => "max".to_owned(),
+            Expr::Add { duration, .. } => format!("+ {duration}"),
+            Expr::Sub { duration, .. } => format!("- {duration}"),
+            Expr::Compare { op, .. } => op.to_string(),
+        }
+    }
+}
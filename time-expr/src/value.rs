@@ -1,22 +1,122 @@
-use std::ops::Add;
+use std::ops::{Add, BitAnd, BitOr, Not, Sub};
 
 use time::{Duration, OffsetDateTime};
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Value {
-    Always,
-    Since(OffsetDateTime),
-    Never,
+/// The set of instants during which a condition holds.
+///
+/// It is stored as the truth value at the beginning of time
+/// together with a strictly increasing list of instants at which it flips.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Value {
+    initial: bool,
+    changes: Vec<OffsetDateTime>,
 }
 
 impl Value {
+    /// Creates a value from changes which are already strictly increasing.
+    pub(crate) fn new(initial: bool, changes: Vec<OffsetDateTime>) -> Self {
+        Value { initial, changes }
+    }
+
+    pub fn always() -> Self {
+        Value {
+            initial: true,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn never() -> Self {
+        Value {
+            initial: false,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn since(time: OffsetDateTime) -> Self {
+        Value {
+            initial: false,
+            changes: vec![time],
+        }
+    }
+
+    pub fn until(time: OffsetDateTime) -> Self {
+        Value {
+            initial: true,
+            changes: vec![time],
+        }
+    }
+
+    /// Returns whether the value holds before its first change.
+    #[inline]
+    pub fn initial(&self) -> bool {
+        self.initial
+    }
+
+    /// Returns the instants at which the value flips, in increasing order.
+    #[inline]
+    pub fn changes(&self) -> &[OffsetDateTime] {
+        &self.changes
+    }
+
     #[inline]
     pub fn to_bool(&self, at: OffsetDateTime) -> bool {
-        match *self {
-            Value::Always => true,
-            Value::Since(time) => at >= time,
-            Value::Never => false,
+        let flips = self.changes.partition_point(|&c| c <= at);
+        self.initial ^ (flips % 2 == 1)
+    }
+
+    /// Returns the first instant after `at` at which the value changes.
+    #[inline]
+    pub fn next_change(&self, at: OffsetDateTime) -> Option<OffsetDateTime> {
+        let idx = self.changes.partition_point(|&c| c <= at);
+        self.changes.get(idx).copied()
+    }
+
+    /// Returns the last instant no later than `at` at which the value changed.
+    #[inline]
+    pub fn last_change(&self, at: OffsetDateTime) -> Option<OffsetDateTime> {
+        let idx = self.changes.partition_point(|&c| c <= at);
+        idx.checked_sub(1).map(|i| self.changes[i])
+    }
+
+    pub(crate) fn edges(&self) -> impl Iterator<Item = (OffsetDateTime, bool)> + '_ {
+        let initial = self.initial;
+
+        self.changes
+            .iter()
+            .enumerate()
+            .map(move |(i, &time)| (time, (i % 2 == 0) != initial))
+    }
+
+    /// Returns a value that holds whenever at least `count` of `values` hold.
+    pub(crate) fn at_least(count: usize, values: &[&Value]) -> Value {
+        let mut edges: Vec<_> = values.iter().flat_map(|v| v.edges()).collect();
+        edges.sort_by_key(|&(time, _)| time);
+
+        let mut active = values.iter().filter(|v| v.initial).count();
+        let initial = active >= count;
+
+        let mut state = initial;
+        let mut changes = Vec::new();
+        let mut edges = edges.into_iter().peekable();
+
+        while let Some((time, rising)) = edges.next() {
+            if rising {
+                active += 1;
+            } else {
+                active -= 1;
+            }
+
+            if edges.peek().is_some_and(|&(next, _)| next == time) {
+                continue;
+            }
+
+            if (active >= count) != state {
+                state = !state;
+                changes.push(time);
+            }
         }
+
+        Value { initial, changes }
     }
 }
 
@@ -24,27 +124,82 @@ impl Add<Duration> for Value {
     type Output = Value;
 
     fn add(self, rhs: Duration) -> Self::Output {
-        match self {
-            Value::Since(time) => match time.checked_add(rhs) {
-                Some(time) => Value::Since(time),
-                None => Value::Never,
-            },
-            other => other,
+        let mut initial = self.initial;
+        let mut changes = Vec::with_capacity(self.changes.len());
+
+        for time in self.changes {
+            match time.checked_add(rhs) {
+                Some(time) => changes.push(time),
+                // The change would happen before the beginning of time
+                None if rhs.is_negative() => initial = !initial,
+                // The change would happen after the end of time
+                None => break,
+            }
+        }
+
+        Value { initial, changes }
+    }
+}
+
+impl Sub<Duration> for Value {
+    type Output = Value;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        let mut initial = self.initial;
+        let mut changes = Vec::with_capacity(self.changes.len());
+
+        for time in self.changes {
+            match time.checked_sub(rhs) {
+                Some(time) => changes.push(time),
+                // The change would happen after the end of time
+                None if rhs.is_negative() => break,
+                // The change would happen before the beginning of time
+                None => initial = !initial,
+            }
         }
+
+        Value { initial, changes }
+    }
+}
+
+impl Not for Value {
+    type Output = Value;
+
+    fn not(self) -> Self::Output {
+        Value {
+            initial: !self.initial,
+            changes: self.changes,
+        }
+    }
+}
+
+impl BitAnd for Value {
+    type Output = Value;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Value::at_least(2, &[&self, &rhs])
+    }
+}
+
+impl BitOr for Value {
+    type Output = Value;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Value::at_least(1, &[&self, &rhs])
     }
 }
 
 impl From<OffsetDateTime> for Value {
     fn from(value: OffsetDateTime) -> Self {
-        Value::Since(value)
+        Value::since(value)
     }
 }
 
 impl From<Option<OffsetDateTime>> for Value {
     fn from(value: Option<OffsetDateTime>) -> Self {
         match value {
-            Some(value) => Value::Since(value),
-            None => Value::Never,
+            Some(value) => Value::since(value),
+            None => Value::never(),
         }
     }
 }
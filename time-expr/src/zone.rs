@@ -0,0 +1,58 @@
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+use time_tz::{timezones, Offset, TimeZone, Tz};
+
+/// The time zone a local date and time is interpreted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Offset(UtcOffset),
+    Named(&'static Tz),
+}
+
+impl Zone {
+    /// Looks up a zone in the bundled IANA database, such as `Europe/Prague`.
+    pub fn named(name: &str) -> Option<Zone> {
+        timezones::get_by_name(name).map(Zone::Named)
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Zone::Offset(_) => None,
+            Zone::Named(tz) => Some(tz.name()),
+        }
+    }
+
+    /// Returns the instant a local date and time refers to.
+    ///
+    /// Times that occur twice resolve to the earlier instant.
+    /// Times skipped by a transition use the offset from before it,
+    /// so they end up after the transition.
+    pub fn localize(&self, datetime: PrimitiveDateTime) -> OffsetDateTime {
+        let tz = match self {
+            Zone::Offset(offset) => return datetime.assume_offset(*offset),
+            Zone::Named(tz) => tz,
+        };
+
+        let offset = match tz.get_offset_local(&datetime.assume_utc()) {
+            time_tz::OffsetResult::Some(offset) | time_tz::OffsetResult::Ambiguous(offset, _) => {
+                offset
+            }
+            time_tz::OffsetResult::None => {
+                let before = datetime.assume_utc();
+                let before = before.checked_sub(Duration::DAY).unwrap_or(before);
+                tz.get_offset_utc(&before)
+            }
+        };
+
+        datetime.assume_offset(offset.to_utc())
+    }
+
+    /// Returns the local date at a given instant.
+    pub fn date(&self, at: OffsetDateTime) -> Option<Date> {
+        let offset = match self {
+            Zone::Offset(offset) => *offset,
+            Zone::Named(tz) => tz.get_offset_utc(&at).to_utc(),
+        };
+
+        at.checked_to_offset(offset).map(|at| at.date())
+    }
+}